@@ -0,0 +1,27 @@
+//! Fuzzing target for the lexer of the core language.
+//!
+//! Lexing must never panic, every token must span a slice of the input that lies on character
+//! boundaries, and every token must be lexed back from its own rendering.
+//!
+//! Run with `cargo fuzz run lexer corpus/lexer corpus/seed`, which starts from the seed corpus
+//! shared by both targets, and keeps the inputs it discovers within `corpus/lexer`.
+
+#![no_main]
+
+use fluxo::enc::core::lex::Lexer;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(val) = std::str::from_utf8(data) else {
+        return;
+    };
+    for (sloc, tok, eloc) in Lexer::new(val).flatten() {
+        assert!(sloc < eloc && eloc <= val.len());
+        assert!(val.is_char_boundary(sloc) && val.is_char_boundary(eloc));
+        let res: Vec<_> = Lexer::new(&tok.to_string()).collect();
+        assert_eq!(res, vec![Ok((0, tok.clone(), tok.to_string().len()))]);
+    }
+    for comment in Lexer::comments(val) {
+        assert!(val[comment.loc..].starts_with(&comment.val));
+    }
+});
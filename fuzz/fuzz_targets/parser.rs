@@ -0,0 +1,36 @@
+//! Fuzzing target for the parser of the core language.
+//!
+//! Parsing must never panic or overflow the stack, whatever the input. Any expression that is
+//! decoded successfully must also be decoded back from its encoding, with or without indices, up
+//! to the renaming of binders, and then encoded the same way again.
+//!
+//! Run with `cargo fuzz run parser corpus/parser corpus/seed`, which starts from the seed corpus
+//! shared by both targets, and keeps the inputs it discovers within `corpus/parser`.
+
+#![no_main]
+
+use fluxo::enc::core::lex::Lexer;
+use fluxo::enc::core::loc::Spans;
+use fluxo::enc::core::par::grammar::ExpParser;
+use fluxo::enc::core::par::Core;
+use fluxo::enc::Codec;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(val) = std::str::from_utf8(data) else {
+        return;
+    };
+    let _ = ExpParser::new().parse(&mut Spans::new(false), Lexer::new(val));
+    let _ = Core::new().decode_loc(val);
+    let Ok(exp) = Core::new().decode(&val.to_string()) else {
+        return;
+    };
+
+    for codec in [Core::new(), Core::with_show_indices(true)] {
+        let can = codec.encode(&exp);
+        let res = Core::new().decode(&can);
+        assert!(res.as_ref().is_ok_and(|res| res.alpha_eq(&exp)), "{}", can);
+        let res = res.map(|res| codec.encode(&res));
+        assert_eq!(res.as_ref(), Ok(&can)); // canonical, so encoded the same
+    }
+});
@@ -0,0 +1,336 @@
+//! Arena of hash-consed expressions, which share every repeated sub-expression.
+
+use super::{Abs, App, Exp, Prd, Sum, Sym, Unv, Var};
+use std::collections::HashMap;
+
+/// Handle to an expression interned within an [arena][Arena].
+///
+/// Within any one arena, two handles are equal exactly when the expressions they refer to are
+/// equal, so comparing them takes constant time. Handles from different arenas are unrelated.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ExpId(usize);
+
+/// Expression interned within an [arena][Arena], whose constituents are referred to by their
+/// [handles][ExpId].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Node {
+    /// [Variable][Var].
+    Var(Var),
+    /// [Application][App] of the first expression to the second.
+    App(ExpId, ExpId),
+    /// [λ-abstraction][Abs] with a symbol, a type and a body.
+    Abs(Sym, ExpId, ExpId),
+    /// [Π-type][Prd] with a symbol, a type and a body.
+    Prd(Sym, ExpId, ExpId),
+    /// [Σ-type][Sum] with a symbol, a type and a body.
+    Sum(Sym, ExpId, ExpId),
+    /// [Universe][Unv].
+    Unv(Unv),
+}
+
+/// Metadata about an interned expression, computed once when it is first interned.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub struct Meta {
+    /// Number of constituents of the expression, including itself, as if no constituent were
+    /// shared; saturates at [u64::MAX].
+    pub size: u64,
+    /// Length of the longest path from the expression to one of its atomic constituents.
+    pub height: u64,
+    /// Number of binders outside of the expression that it refers to, which is one more than the
+    /// highest index of a variable bound outside of it, or 0 if there is none.
+    pub bound: u64,
+    /// Whether the expression contains any free variable.
+    pub free: bool,
+}
+
+/// Arena of hash-consed [expressions][Exp].
+///
+/// Every expression is interned at most once, after its constituents, so that repeated
+/// sub-expressions are stored once and shared. Expressions are converted to and from the arena
+/// without loss, including the symbols associated with binders and bound variables.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Arena {
+    /// Interned expressions, each after its constituents.
+    nodes: Vec<Node>,
+    /// Metadata for each interned expression, in the same order.
+    meta: Vec<Meta>,
+    /// Handle of each interned expression.
+    ids: HashMap<Node, ExpId>,
+}
+
+impl Node {
+    /// Returns the handles of the constituents of this expression, in order.
+    pub fn parts(&self) -> Option<[ExpId; 2]> {
+        match self {
+            Node::App(fst, snd) => Some([*fst, *snd]),
+            Node::Abs(_, typ, exp) | Node::Prd(_, typ, exp) | Node::Sum(_, typ, exp) => {
+                Some([*typ, *exp])
+            }
+            Node::Var(_) | Node::Unv(_) => None,
+        }
+    }
+}
+
+impl Arena {
+    /// Create a new, empty instance of an [arena][Arena].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of distinct expressions interned within the arena.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Checks whether no expression has been interned within the arena.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the interned expression that a handle refers to.
+    ///
+    /// <p style="background: rgba(255, 181, 77, 0.16); padding: 0.75em">
+    /// <strong>Warning:</strong> This method panics when called with a handle from another arena
+    /// that is out of range for this one.
+    /// </p>
+    pub fn node(&self, id: ExpId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    /// Returns the metadata for the interned expression that a handle refers to.
+    ///
+    /// <p style="background: rgba(255, 181, 77, 0.16); padding: 0.75em">
+    /// <strong>Warning:</strong> This method panics when called with a handle from another arena
+    /// that is out of range for this one.
+    /// </p>
+    pub fn meta(&self, id: ExpId) -> &Meta {
+        &self.meta[id.0]
+    }
+
+    /// Interns an expression whose constituents are already interned, and returns its handle.
+    ///
+    /// If an equal expression is already interned, its handle is returned instead.
+    ///
+    /// <p style="background: rgba(255, 181, 77, 0.16); padding: 0.75em">
+    /// <strong>Warning:</strong> This method panics when the node refers to a constituent with a
+    /// handle from another arena that is out of range for this one.
+    /// </p>
+    pub fn intern(&mut self, node: Node) -> ExpId {
+        if let Some(id) = self.ids.get(&node) {
+            return *id;
+        }
+        let meta = self.compute(&node);
+        let id = ExpId(self.nodes.len());
+        self.nodes.push(node.clone());
+        self.meta.push(meta);
+        self.ids.insert(node, id);
+        id
+    }
+
+    /// Interns an [expression][Exp] along with each of its sub-expressions, and returns its
+    /// handle.
+    pub fn insert(&mut self, exp: &Exp) -> ExpId {
+        let mut stack = vec![(exp, false)];
+        let mut res = vec![];
+        while let Some((exp, done)) = stack.pop() {
+            let node = match exp {
+                Exp::Var(var) => Node::Var(var.clone()),
+                Exp::Unv(unv) => Node::Unv(*unv),
+                Exp::App(App { fst, snd }) if !done => {
+                    stack.extend([(exp, true), (snd, false), (fst, false)]);
+                    continue; // interned once its constituents are
+                }
+                Exp::Abs(Abs { typ, exp: body, .. })
+                | Exp::Prd(Prd { typ, exp: body, .. })
+                | Exp::Sum(Sum { typ, exp: body, .. })
+                    if !done =>
+                {
+                    stack.extend([(exp, true), (body, false), (typ, false)]);
+                    continue; // interned once its constituents are
+                }
+                exp => {
+                    let snd = res.pop().expect("missing constituent");
+                    let fst = res.pop().expect("missing constituent");
+                    match exp {
+                        Exp::Abs(abs) => Node::Abs(abs.sym.clone(), fst, snd),
+                        Exp::Prd(prd) => Node::Prd(prd.sym.clone(), fst, snd),
+                        Exp::Sum(sum) => Node::Sum(sum.sym.clone(), fst, snd),
+                        _ => Node::App(fst, snd),
+                    }
+                }
+            };
+            res.push(self.intern(node));
+        }
+        res.pop().expect("missing expression")
+    }
+
+    /// Converts an interned expression back into an [expression][Exp].
+    ///
+    /// Shared sub-expressions are copied wherever they occur.
+    pub fn to_exp(&self, id: ExpId) -> Exp {
+        let mut stack = vec![(id, false)];
+        let mut res: Vec<Exp> = vec![];
+        while let Some((id, done)) = stack.pop() {
+            let node = self.node(id);
+            let exp = match (node, node.parts()) {
+                (Node::Var(var), _) => Exp::Var(var.clone()),
+                (Node::Unv(unv), _) => Exp::Unv(*unv),
+                (_, Some([fst, snd])) if !done => {
+                    stack.extend([(id, true), (snd, false), (fst, false)]);
+                    continue; // built once its constituents are
+                }
+                (node, _) => {
+                    let snd = Box::new(res.pop().expect("missing constituent"));
+                    let fst = Box::new(res.pop().expect("missing constituent"));
+                    match node {
+                        Node::Abs(sym, ..) => Exp::Abs(Abs {
+                            sym: sym.clone(),
+                            typ: fst,
+                            exp: snd,
+                        }),
+                        Node::Prd(sym, ..) => Exp::Prd(Prd {
+                            sym: sym.clone(),
+                            typ: fst,
+                            exp: snd,
+                        }),
+                        Node::Sum(sym, ..) => Exp::Sum(Sum {
+                            sym: sym.clone(),
+                            typ: fst,
+                            exp: snd,
+                        }),
+                        _ => Exp::App(App { fst, snd }),
+                    }
+                }
+            };
+            res.push(exp);
+        }
+        res.pop().expect("missing expression")
+    }
+
+    /// Computes the metadata for an expression from that of its constituents.
+    fn compute(&self, node: &Node) -> Meta {
+        match node {
+            Node::Var(Var::Sym(_)) => Meta {
+                size: 1,
+                free: true,
+                ..Meta::default()
+            },
+            Node::Var(Var::Idx(idx)) => Meta {
+                size: 1,
+                bound: idx.val.saturating_add(1),
+                ..Meta::default()
+            },
+            Node::App(fst, snd) => {
+                let (fst, snd) = (self.meta(*fst), self.meta(*snd));
+                Meta {
+                    size: fst.size.saturating_add(snd.size).saturating_add(1),
+                    height: fst.height.max(snd.height) + 1,
+                    bound: fst.bound.max(snd.bound),
+                    free: fst.free || snd.free,
+                }
+            }
+            Node::Abs(_, typ, exp) | Node::Prd(_, typ, exp) | Node::Sum(_, typ, exp) => {
+                let (typ, exp) = (self.meta(*typ), self.meta(*exp));
+                Meta {
+                    size: typ.size.saturating_add(exp.size).saturating_add(1),
+                    height: typ.height.max(exp.height) + 1,
+                    bound: typ.bound.max(exp.bound.saturating_sub(1)), // body is within the binder
+                    free: typ.free || exp.free,
+                }
+            }
+            Node::Unv(_) => Meta {
+                size: 1,
+                ..Meta::default()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::enc::core::par::decode;
+
+    #[test]
+    fn test_insert() {
+        let mut arena = Arena::new();
+        let exp = decode("λa : □ . λx : a . (λy : a . y) ((λy : a . y) x)");
+        let id = arena.insert(&exp);
+        assert_eq!(arena.to_exp(id), exp);
+        assert_eq!(arena.len(), 10); // the repeated λ-abstraction and its type are shared
+        assert_eq!(arena.insert(&exp), id);
+        assert_eq!(arena.insert(&exp.clone()), id);
+        assert_eq!(arena.len(), 10);
+
+        let other = arena.insert(&decode("λa : □ . λz : a . (λy : a . y) ((λy : a . y) z)"));
+        assert_ne!(other, id); // symbols are significant, as for expressions
+        assert_eq!(arena.len(), 15);
+    }
+
+    #[test]
+    fn test_intern() {
+        let mut arena = Arena::new();
+        let unv = arena.intern(Node::Unv(Unv::new()));
+        let var = arena.intern(Node::Var(Var::Sym(Sym::new("f"))));
+        let app = arena.intern(Node::App(var, unv));
+        assert_eq!(arena.intern(Node::App(var, unv)), app);
+        assert_eq!(arena.node(app).parts(), Some([var, unv]));
+        assert_eq!(arena.to_exp(app), decode("f □"));
+        assert_eq!(arena.len(), 3);
+    }
+
+    #[test]
+    fn test_meta() {
+        let mut arena = Arena::new();
+        let exp = decode("λa : □ . λx : a . f x");
+        let id = arena.insert(&exp);
+        let meta = arena.meta(id);
+        assert_eq!(
+            (meta.size, meta.height, meta.bound, meta.free),
+            (7, 3, 0, true)
+        );
+
+        let Exp::Abs(abs) = &exp else { unreachable!() };
+        let id = arena.insert(&abs.exp);
+        let meta = arena.meta(id);
+        assert_eq!(
+            (meta.size, meta.height, meta.bound, meta.free),
+            (5, 2, 1, true)
+        );
+        let Exp::Abs(abs) = abs.exp.as_ref() else {
+            unreachable!()
+        };
+        let id = arena.insert(&abs.exp);
+        let meta = arena.meta(id);
+        assert_eq!(
+            (meta.size, meta.height, meta.bound, meta.free),
+            (3, 1, 1, true)
+        );
+
+        let id = arena.insert(&decode("λa : □ . a"));
+        assert_eq!(
+            *arena.meta(id),
+            Meta {
+                size: 3,
+                height: 1,
+                bound: 0,
+                free: false
+            }
+        );
+    }
+
+    #[test]
+    fn test_shared() {
+        let mut arena = Arena::new();
+        let mut id = arena.insert(&decode("f"));
+        for _ in 0..100 {
+            id = arena.intern(Node::App(id, id)); // doubles in size each time
+        }
+        assert_eq!(arena.len(), 101);
+        assert_eq!(arena.meta(id).size, u64::MAX);
+        assert_eq!(arena.meta(id).height, 100);
+    }
+}
@@ -1,9 +1,101 @@
 //! Typing context, and related behaviors.
 
+use super::{Exp, Idx, Sym};
+
 /// Typing context.
 ///
 /// A typing context (represented by the symbol `Γ`) is an ordered set of declarations of the form
 /// `x : N`, `x` being a [variable][super::Exp::Var], and `N` an [expression][super::Exp] denoting
 /// the type of `x`.
+///
+/// Free variables are declared up front and referenced by their [symbols][Sym]; these may also be
+/// given a value, which makes them definitions rather than assumptions. Bound variables are
+/// declared while descending into binders and referenced by their [indices][Idx]. The type of a
+/// bound variable may refer to the bound variables declared before it, and is lifted accordingly
+/// when looked up.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Ctx {
+    /// Declarations of free variables, in order.
+    syms: Vec<Decl>,
+    /// Declarations of bound variables, with the innermost binder last.
+    idxs: Vec<Decl>,
+}
+
+/// Declaration of a [variable][super::Var] within a [typing context][Ctx].
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct Ctx;
+#[non_exhaustive]
+pub struct Decl {
+    /// Symbol of the declared variable.
+    pub sym: Sym,
+    /// Type of the declared variable.
+    pub typ: Exp,
+    /// Value of the declared variable, if it is defined rather than assumed.
+    pub val: Option<Exp>,
+}
+
+impl Ctx {
+    /// Create a new, empty instance of a [typing context][Ctx].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares a free variable of the supplied type.
+    ///
+    /// A declaration shadows any earlier declaration with the same symbol. The type must not
+    /// contain any bound variables that are not bound within it.
+    pub fn declare(&mut self, sym: Sym, typ: Exp) {
+        self.syms.push(Decl {
+            sym,
+            typ,
+            val: None,
+        });
+    }
+
+    /// Defines a free variable of the supplied type, which stands for the supplied value.
+    ///
+    /// The value is not checked against the type; see [Ctx::check].
+    pub fn define(&mut self, sym: Sym, typ: Exp, val: Exp) {
+        self.syms.push(Decl {
+            sym,
+            typ,
+            val: Some(val),
+        });
+    }
+
+    /// Declares a bound variable of the supplied type, when descending into a binder.
+    pub fn bind(&mut self, sym: Sym, typ: Exp) {
+        self.idxs.push(Decl {
+            sym,
+            typ,
+            val: None,
+        });
+    }
+
+    /// Removes the declaration of the innermost bound variable, when ascending out of a binder.
+    pub fn unbind(&mut self) -> Option<Decl> {
+        self.idxs.pop()
+    }
+
+    /// Returns the declaration of the free variable with the supplied symbol.
+    pub fn lookup_sym(&self, sym: &Sym) -> Option<&Decl> {
+        self.syms.iter().rev().find(|decl| &decl.sym == sym)
+    }
+
+    /// Returns the declaration of the bound variable with the supplied index.
+    ///
+    /// The type in the declaration is relative to the binder that declares it; use
+    /// [Exp::lift] with a value one greater than the index to refer to it from the current scope.
+    pub fn lookup_idx(&self, idx: &Idx) -> Option<&Decl> {
+        idx.position(self.idxs.len()).map(|pos| &self.idxs[pos])
+    }
+
+    /// Returns the declarations of free variables, in order.
+    pub fn syms(&self) -> &[Decl] {
+        &self.syms
+    }
+
+    /// Returns the declarations of bound variables, with the innermost binder last.
+    pub fn idxs(&self) -> &[Decl] {
+        &self.idxs
+    }
+}
@@ -2,9 +2,17 @@
 
 use super::{Abs, App, Idx, Prd, Sum, Sym, Unv, Var};
 use crate::err::SystemErr;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
 
 /// Expression, which is the top-level entity within the AST.
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+///
+/// Expressions may be nested to any depth, such as when they are decoded from untrusted input.
+/// Every operation on an expression within this crate, including cloning, comparing, hashing,
+/// formatting and dropping it, therefore uses an explicit stack rather than recursion, so that no
+/// expression overflows the call stack.
+#[derive(Eq)]
 #[non_exhaustive]
 pub enum Exp {
     /// Variable, which is the atomic constituent of an expression.
@@ -31,6 +39,14 @@ pub trait Binder {
     fn typ(&self) -> &Exp;
     /// Returns a reference to the expression contained within this binder.
     fn exp(&self) -> &Exp;
+
+    /// Returns the body of this binder, [opened][Exp::open] with a free variable named by the
+    /// supplied symbol in place of the variable that the binder binds.
+    fn open(&self, sym: &Sym) -> Exp {
+        let mut exp = self.exp().clone();
+        exp.open(sym);
+        exp
+    }
 }
 
 impl Exp {
@@ -40,48 +56,934 @@ impl Exp {
     /// into an index if it references the same symbol. If the scan traverses another binder, we
     /// increment the index value before continuing. This ensures that an index counts the number
     /// of binders in between its current position and the binder that binds it.
+    ///
+    /// The type of a binder lies outside the scope of the binder, so it is indexed even when the
+    /// binder shadows the supplied symbol, and without incrementing the index value.
     pub fn index(&mut self, sym: &Sym, idx: &Idx) -> Result<(), SystemErr> {
-        match self {
-            Exp::Var(var) => {
-                if let Var::Sym(can) = var {
-                    if can == sym {
-                        *var = Var::Idx(idx.clone()); // matches, so convert variable to index
-                        Ok(())
-                    } else {
-                        Ok(()) // no match
+        let mut stack = vec![(self, idx.val)];
+        while let Some((exp, val)) = stack.pop() {
+            match exp {
+                Exp::Var(var) => {
+                    if matches!(var, Var::Sym(can) if can == sym) {
+                        let sym = idx.sym.clone();
+                        *var = Var::Idx(Idx { val, sym }); // matches, so convert variable to index
+                    } // otherwise no match, or variable is already bound
+                }
+                Exp::App(App { fst, snd }) => {
+                    stack.push((snd, val)); // branch and continue indexing
+                    stack.push((fst, val));
+                }
+                Exp::Abs(Abs { sym: can, typ, exp })
+                | Exp::Prd(Prd { sym: can, typ, exp })
+                | Exp::Sum(Sum { sym: can, typ, exp }) => {
+                    if can != sym {
+                        let val = val.checked_add(1).ok_or(SystemErr::MaxLimitIdx(val))?;
+                        stack.push((exp, val)); // descend into nested expression
+                    } // otherwise short-circuit due to shadow binding
+                    stack.push((typ, val)); // type is outside the scope of the binder
+                }
+                Exp::Unv(_) => {} // constants need no indexing
+            }
+        }
+        Ok(())
+    }
+
+    /// Increments the indices of the variables in this expression that are bound outside of it.
+    ///
+    /// This is required whenever an expression is moved underneath `num` additional binders, so
+    /// that its variables continue to refer to the same binders. The expression is left unchanged
+    /// if any index would be lifted above [u64::MAX].
+    pub fn lift(&mut self, num: u64) -> Result<(), SystemErr> {
+        let mut err = None;
+        self.scan(|var, depth| {
+            if let Var::Idx(idx) = var {
+                if idx.val >= depth && idx.val.checked_add(num).is_none() {
+                    err = Some(SystemErr::MaxLimitIdx(idx.val));
+                }
+            }
+            err.is_none()
+        });
+        if let Some(err) = err {
+            return Err(err);
+        }
+        self.scan_mut(|idx, depth| {
+            if idx.val >= depth {
+                idx.val += num; // checked before lifting
+            }
+        });
+        Ok(())
+    }
+
+    /// Opens this expression, which is the body of a binder, by replacing the variable bound by
+    /// the binder with a free variable named by the supplied symbol.
+    ///
+    /// Every other variable bound outside of this expression refers to a binder one level closer
+    /// once the binder is removed, so its index is decremented. The symbol should be
+    /// [fresh][super::Fresh], so that it does not capture a free variable already within the
+    /// expression.
+    pub fn open(&mut self, sym: &Sym) {
+        let mut stack = vec![(self, 0)];
+        while let Some((exp, depth)) = stack.pop() {
+            match exp {
+                Exp::Var(var) => match var {
+                    Var::Idx(idx) if idx.val == depth => *var = Var::Sym(sym.clone()),
+                    Var::Idx(idx) if idx.val > depth => idx.val -= 1, // cannot overflow
+                    _ => {} // bound within the expression, or free
+                },
+                Exp::App(App { fst, snd }) => {
+                    stack.push((snd, depth));
+                    stack.push((fst, depth));
+                }
+                Exp::Abs(Abs { typ, exp, .. })
+                | Exp::Prd(Prd { typ, exp, .. })
+                | Exp::Sum(Sum { typ, exp, .. }) => {
+                    stack.push((exp, depth.saturating_add(1)));
+                    stack.push((typ, depth)); // type is outside the scope of the binder
+                }
+                Exp::Unv(_) => {} // constants need no opening
+            }
+        }
+    }
+
+    /// Closes this expression over the free variable named by the supplied symbol, so that it
+    /// becomes the body of a binder for that variable.
+    ///
+    /// This is the inverse of [Exp::open]: every variable bound outside of the expression is
+    /// lifted past the new binder, and every occurrence of the free variable is converted into an
+    /// index that refers to the new binder. Unlike [Exp::index], this does not stop at binders
+    /// within the expression that share the symbol, since the variables bound by those binders
+    /// are already indices, and every occurrence of the symbol is free.
+    pub fn close(&mut self, sym: &Sym) -> Result<(), SystemErr> {
+        self.lift(1)?;
+        let mut stack = vec![(self, 0)];
+        while let Some((exp, depth)) = stack.pop() {
+            match exp {
+                Exp::Var(var) => {
+                    if matches!(var, Var::Sym(can) if can == sym) {
+                        let sym = sym.clone();
+                        *var = Var::Idx(Idx { val: depth, sym });
                     }
-                } else {
-                    Ok(()) // variable is already bound (converted to an index)
                 }
+                Exp::App(App { fst, snd }) => {
+                    stack.push((snd, depth));
+                    stack.push((fst, depth));
+                }
+                Exp::Abs(Abs { typ, exp, .. })
+                | Exp::Prd(Prd { typ, exp, .. })
+                | Exp::Sum(Sum { typ, exp, .. }) => {
+                    stack.push((exp, depth.saturating_add(1)));
+                    stack.push((typ, depth)); // type is outside the scope of the binder
+                }
+                Exp::Unv(_) => {} // constants need no closing
             }
-            Exp::Abs(abs) => {
-                let Abs { sym: can, exp, .. } = abs;
-                if can != sym {
-                    exp.index(sym, &idx.inc()?)?; // descend into nested expression
-                } // otherwise short-circuit due to shadow binding
-                Ok(())
+        }
+        Ok(())
+    }
+
+    /// Shifts the indices of the variables in this expression that are bound outside of it by
+    /// `delta`.
+    ///
+    /// This generalizes [Exp::lift] to negative shifts, such as when an expression is moved out
+    /// from underneath binders that it does not refer to. The expression is left unchanged if any
+    /// index would be shifted below 0 or above [u64::MAX].
+    pub fn shift(&mut self, delta: i64) -> Result<(), SystemErr> {
+        let mut err = None;
+        self.scan(|var, depth| {
+            if let Var::Idx(idx) = var {
+                err = idx.shift(delta, depth).err();
             }
-            Exp::App(app) => {
-                let App { fst, snd } = app;
-                fst.index(sym, idx)?; // branch and continue indexing
-                snd.index(sym, idx)?; // branch and continue indexing
+            err.is_none()
+        });
+        match err {
+            Some(err) => Err(err),
+            None => {
+                self.scan_mut(|idx, depth| {
+                    *idx = idx.shift(delta, depth).expect("checked before shifting");
+                });
                 Ok(())
             }
-            Exp::Prd(prd) => {
-                let Prd { sym: can, exp, .. } = prd;
-                if can != sym {
-                    exp.index(sym, &idx.inc()?)?; // descend into nested expression
-                } // otherwise short-circuit due to shadow binding
-                Ok(())
+        }
+    }
+
+    /// Removes a binder outside of this expression that the expression does not refer to, where
+    /// `idx` is the index of that binder counted from the outside of the expression.
+    ///
+    /// Every variable that refers to a binder further out than the one removed refers to a binder
+    /// one level closer, so its index is decremented. If any variable refers to the binder being
+    /// removed, a [SystemErr::UsedIdx] is reported instead, and the expression is left unchanged.
+    pub fn strengthen(&mut self, idx: u64) -> Result<(), SystemErr> {
+        let mut err = None;
+        self.scan(|var, depth| {
+            if let Var::Idx(can) = var {
+                if can.val.checked_sub(depth) == Some(idx) {
+                    err = Some(SystemErr::UsedIdx(can.clone()));
+                }
             }
-            Exp::Sum(sum) => {
-                let Sum { sym: can, exp, .. } = sum;
-                if can != sym {
-                    exp.index(sym, &idx.inc()?)?; // descend into nested expression
-                } // otherwise short-circuit due to shadow binding
-                Ok(())
+            err.is_none()
+        });
+        if let Some(err) = err {
+            return Err(err);
+        }
+        self.scan_mut(|can, depth| {
+            if can.val.checked_sub(depth).is_some_and(|val| val > idx) {
+                *can = can.dec(); // cannot panic, since the value is positive
+            }
+        });
+        Ok(())
+    }
+
+    /// Substitutes an expression for the variable bound by the binder that this expression is the
+    /// body of, and removes the binder.
+    ///
+    /// Each occurrence of the bound variable is replaced with the supplied value, lifted past the
+    /// binders in between. Every other variable bound outside of this expression refers to a
+    /// binder one level closer once the binder is removed, so its index is decremented.
+    pub fn instantiate(&mut self, val: &Exp) -> Result<(), SystemErr> {
+        let mut stack = vec![(self, 0)];
+        while let Some((exp, depth)) = stack.pop() {
+            match exp {
+                Exp::Var(Var::Idx(idx)) if idx.val == depth => {
+                    let mut res = val.clone();
+                    res.lift(depth)?;
+                    *exp = res;
+                }
+                Exp::Var(Var::Idx(idx)) if idx.val > depth => {
+                    *idx = idx.dec(); // cannot panic, since the value is positive
+                }
+                Exp::Var(_) => {} // bound within the expression, or free
+                Exp::App(App { fst, snd }) => {
+                    stack.push((snd, depth));
+                    stack.push((fst, depth));
+                }
+                Exp::Abs(Abs { typ, exp, .. })
+                | Exp::Prd(Prd { typ, exp, .. })
+                | Exp::Sum(Sum { typ, exp, .. }) => {
+                    stack.push((exp, depth.saturating_add(1)));
+                    stack.push((typ, depth)); // type is outside the scope of the binder
+                }
+                Exp::Unv(_) => {} // constants need no substitution
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the free variables within this expression, in order of their first occurrence.
+    ///
+    /// These are the variables still referenced by their symbols, which no binder within the
+    /// expression has [indexed][Exp::index].
+    pub fn free_syms(&self) -> Vec<&Sym> {
+        let mut res = vec![];
+        let mut seen = HashSet::new();
+        self.scan(|var, _| {
+            if let Var::Sym(sym) = var {
+                if seen.insert(sym) {
+                    res.push(sym);
+                }
+            }
+            true
+        });
+        res
+    }
+
+    /// Returns the highest index of a variable bound outside of this expression, counted from
+    /// the outside of the expression, or [None] if every bound variable is bound within it.
+    pub fn max_free_index(&self) -> Option<u64> {
+        let mut res = None;
+        self.scan(|var, depth| {
+            if let Var::Idx(idx) = var {
+                if let Some(val) = idx.val.checked_sub(depth) {
+                    res = res.max(Some(val));
+                }
+            }
+            true
+        });
+        res
+    }
+
+    /// Checks whether this expression is closed, which means that it contains no free variables,
+    /// and no variables bound outside of it.
+    pub fn is_closed(&self) -> bool {
+        let mut res = true;
+        self.scan(|var, depth| {
+            res = match var {
+                Var::Sym(_) => false,
+                Var::Idx(idx) => idx.val < depth,
+            };
+            res
+        });
+        res
+    }
+
+    /// Checks whether the variable bound outside of this expression with the supplied index,
+    /// counted from the outside of the expression, occurs within it.
+    ///
+    /// For example, the body of a [Π-type][Prd] is dependent on its argument exactly when index
+    /// `0` occurs within it.
+    pub fn occurs(&self, idx: u64) -> bool {
+        let mut res = false;
+        self.scan(|var, depth| {
+            res = matches!(var, Var::Idx(can) if can.val.checked_sub(depth) == Some(idx));
+            !res
+        });
+        res
+    }
+
+    /// Visits each variable within this expression from left to right, along with the number of
+    /// binders between it and the outside of the expression, until the supplied function returns
+    /// false.
+    fn scan<'a, F>(&'a self, mut func: F)
+    where
+        F: FnMut(&'a Var, u64) -> bool,
+    {
+        let mut stack = vec![(self, 0)];
+        while let Some((exp, depth)) = stack.pop() {
+            match exp {
+                Exp::Var(var) => {
+                    if !func(var, depth) {
+                        return;
+                    }
+                }
+                Exp::App(App { fst, snd }) => {
+                    stack.push((snd, depth));
+                    stack.push((fst, depth));
+                }
+                Exp::Abs(Abs { typ, exp, .. })
+                | Exp::Prd(Prd { typ, exp, .. })
+                | Exp::Sum(Sum { typ, exp, .. }) => {
+                    stack.push((exp, depth.saturating_add(1)));
+                    stack.push((typ, depth)); // type is outside the scope of the binder
+                }
+                Exp::Unv(_) => {} // constants contain no variables
+            }
+        }
+    }
+
+    /// Visits each bound variable within this expression, along with the number of binders
+    /// between it and the outside of the expression, and allows it to be modified.
+    fn scan_mut<F>(&mut self, mut func: F)
+    where
+        F: FnMut(&mut Idx, u64),
+    {
+        let mut stack = vec![(self, 0)];
+        while let Some((exp, depth)) = stack.pop() {
+            match exp {
+                Exp::Var(Var::Idx(idx)) => func(idx, depth),
+                Exp::Var(Var::Sym(_)) => {} // free variables are not indexed
+                Exp::App(App { fst, snd }) => {
+                    stack.push((snd, depth));
+                    stack.push((fst, depth));
+                }
+                Exp::Abs(Abs { typ, exp, .. })
+                | Exp::Prd(Prd { typ, exp, .. })
+                | Exp::Sum(Sum { typ, exp, .. }) => {
+                    stack.push((exp, depth.saturating_add(1)));
+                    stack.push((typ, depth)); // type is outside the scope of the binder
+                }
+                Exp::Unv(_) => {} // constants contain no variables
             }
-            Exp::Unv(_) => Ok(()), // constants need no indexing
         }
     }
+
+    /// Checks whether two expressions are α-equivalent.
+    ///
+    /// Expressions are α-equivalent when they differ only in the symbols associated with their
+    /// binders and bound variables, which have no semantic significance.
+    pub fn alpha_eq(&self, other: &Exp) -> bool {
+        self.eq_by(other, true)
+    }
+
+    /// Checks whether two expressions are equal, ignoring the symbols associated with binders and
+    /// bound variables if `alpha` is set.
+    fn eq_by(&self, other: &Exp, alpha: bool) -> bool {
+        let mut stack = vec![(self, other)];
+        while let Some(pair) = stack.pop() {
+            let eq = match pair {
+                (Exp::Var(Var::Idx(lhs)), Exp::Var(Var::Idx(rhs))) if alpha => lhs.val == rhs.val,
+                (Exp::Var(lhs), Exp::Var(rhs)) => lhs == rhs,
+                (Exp::App(lhs), Exp::App(rhs)) => {
+                    stack.push((&lhs.snd, &rhs.snd));
+                    stack.push((&lhs.fst, &rhs.fst));
+                    true
+                }
+                (
+                    Exp::Abs(Abs {
+                        sym: ls,
+                        typ: lt,
+                        exp: le,
+                    }),
+                    Exp::Abs(Abs {
+                        sym: rs,
+                        typ: rt,
+                        exp: re,
+                    }),
+                )
+                | (
+                    Exp::Prd(Prd {
+                        sym: ls,
+                        typ: lt,
+                        exp: le,
+                    }),
+                    Exp::Prd(Prd {
+                        sym: rs,
+                        typ: rt,
+                        exp: re,
+                    }),
+                )
+                | (
+                    Exp::Sum(Sum {
+                        sym: ls,
+                        typ: lt,
+                        exp: le,
+                    }),
+                    Exp::Sum(Sum {
+                        sym: rs,
+                        typ: rt,
+                        exp: re,
+                    }),
+                ) => {
+                    stack.push((le, re));
+                    stack.push((lt, rt));
+                    alpha || ls == rs
+                }
+                (Exp::Unv(lhs), Exp::Unv(rhs)) => lhs == rhs,
+                _ => false,
+            };
+            if !eq {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Checks whether the expression has no constituents.
+    fn is_atomic(&self) -> bool {
+        matches!(self, Exp::Var(_) | Exp::Unv(_))
+    }
+
+    /// Returns mutable references to the constituents of the expression, if it has any.
+    fn parts_mut(&mut self) -> Option<[&mut Exp; 2]> {
+        match self {
+            Exp::App(App { fst, snd }) => Some([fst, snd]),
+            Exp::Abs(Abs { typ, exp, .. })
+            | Exp::Prd(Prd { typ, exp, .. })
+            | Exp::Sum(Sum { typ, exp, .. }) => Some([typ, exp]),
+            Exp::Var(_) | Exp::Unv(_) => None,
+        }
+    }
+}
+
+impl Default for Exp {
+    /// Returns the lowest [universe][Unv], which is left behind when an expression is moved out
+    /// of its place with [std::mem::take].
+    fn default() -> Self {
+        Exp::Unv(Unv::new())
+    }
+}
+
+impl Drop for Exp {
+    /// Each nested expression is detached from its constituents before it is dropped, so that the
+    /// drop of any one expression never reaches further than its immediate constituents.
+    fn drop(&mut self) {
+        let Some(parts) = self.parts_mut() else {
+            return; // nothing nested, so nothing to detach
+        };
+        fn detach(parts: [&mut Exp; 2]) -> impl Iterator<Item = Exp> + '_ {
+            parts
+                .into_iter()
+                .filter(|part| !part.is_atomic())
+                .map(std::mem::take)
+        }
+        let mut stack: Vec<Exp> = detach(parts).collect();
+        while let Some(mut exp) = stack.pop() {
+            if let Some(parts) = exp.parts_mut() {
+                stack.extend(detach(parts));
+            }
+        } // each expression is dropped here, with only atomic constituents left
+    }
+}
+
+impl Clone for Exp {
+    fn clone(&self) -> Self {
+        /// Pending step in cloning an expression.
+        enum Task<'a> {
+            /// Clone an expression, and push the result.
+            Exp(&'a Exp),
+            /// Pop the clones of the constituents of an expression, and push the expression.
+            Build(&'a Exp),
+        }
+        let mut tasks = vec![Task::Exp(self)];
+        let mut res: Vec<Exp> = vec![];
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Exp(Exp::Var(var)) => res.push(Exp::Var(var.clone())),
+                Task::Exp(Exp::Unv(unv)) => res.push(Exp::Unv(*unv)),
+                Task::Exp(exp) => {
+                    tasks.push(Task::Build(exp));
+                    match exp {
+                        Exp::App(App { fst, snd }) => {
+                            tasks.extend([Task::Exp(snd), Task::Exp(fst)])
+                        }
+                        Exp::Abs(Abs { typ, exp, .. })
+                        | Exp::Prd(Prd { typ, exp, .. })
+                        | Exp::Sum(Sum { typ, exp, .. }) => {
+                            tasks.extend([Task::Exp(exp), Task::Exp(typ)])
+                        }
+                        Exp::Var(_) | Exp::Unv(_) => {} // handled above
+                    }
+                }
+                Task::Build(exp) => {
+                    let snd = Box::new(res.pop().expect("missing constituent"));
+                    let fst = Box::new(res.pop().expect("missing constituent"));
+                    res.push(match exp {
+                        Exp::App(_) => Exp::App(App { fst, snd }),
+                        Exp::Abs(abs) => Exp::Abs(Abs {
+                            sym: abs.sym.clone(),
+                            typ: fst,
+                            exp: snd,
+                        }),
+                        Exp::Prd(prd) => Exp::Prd(Prd {
+                            sym: prd.sym.clone(),
+                            typ: fst,
+                            exp: snd,
+                        }),
+                        Exp::Sum(sum) => Exp::Sum(Sum {
+                            sym: sum.sym.clone(),
+                            typ: fst,
+                            exp: snd,
+                        }),
+                        Exp::Var(_) | Exp::Unv(_) => {
+                            unreachable!("atomic expressions are cloned directly")
+                        }
+                    });
+                }
+            }
+        }
+        res.pop().expect("missing expression")
+    }
+}
+
+impl PartialEq for Exp {
+    /// Checks whether two expressions are structurally equal, including the symbols associated
+    /// with their binders and bound variables.
+    fn eq(&self, other: &Self) -> bool {
+        self.eq_by(other, false)
+    }
+}
+
+impl Hash for Exp {
+    /// Hashes the expression consistently with structural [equality][PartialEq].
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut stack = vec![self];
+        while let Some(exp) = stack.pop() {
+            std::mem::discriminant(exp).hash(state);
+            match exp {
+                Exp::Var(var) => var.hash(state),
+                Exp::App(App { fst, snd }) => stack.extend([&**snd, &**fst]),
+                Exp::Abs(Abs { sym, typ, exp })
+                | Exp::Prd(Prd { sym, typ, exp })
+                | Exp::Sum(Sum { sym, typ, exp }) => {
+                    sym.hash(state);
+                    stack.extend([&**exp, &**typ]);
+                }
+                Exp::Unv(unv) => unv.hash(state),
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Exp {
+    /// Formats the expression as a derived implementation would, in both its compact and its
+    /// alternate form.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pretty = f.alternate();
+        let mut stack = vec![Part::Exp(self, 0)];
+        while let Some(part) = stack.pop() {
+            match part {
+                Part::Exp(exp, level) => {
+                    let parts = Part::split(exp, level, pretty);
+                    stack.extend(parts.into_iter().rev());
+                }
+                Part::Leaf(leaf, level) if pretty => {
+                    let indent = format!("\n{}", "    ".repeat(level));
+                    f.write_str(&format!("{:#?}", leaf).replace('\n', &indent))?;
+                }
+                Part::Leaf(leaf, _) => write!(f, "{:?}", leaf)?,
+                Part::Str(val) => f.write_str(val)?,
+                Part::Line(level) if pretty => write!(f, "\n{}", "    ".repeat(level))?,
+                Part::Line(_) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Part of the formatted [Debug] representation of an expression.
+enum Part<'a> {
+    /// Expression, nested within the supplied level of indentation.
+    Exp(&'a Exp, usize),
+    /// Constituent with no nested expressions, formatted by its own implementation.
+    Leaf(&'a dyn fmt::Debug, usize),
+    /// Literal text.
+    Str(&'static str),
+    /// Line break followed by the supplied level of indentation, in the alternate form only.
+    Line(usize),
+}
+
+impl<'a> Part<'a> {
+    /// Splits an expression nested within `level` into the parts that represent it, where only
+    /// its constituents are left to be split in turn.
+    fn split(exp: &'a Exp, level: usize, pretty: bool) -> Vec<Part<'a>> {
+        let name = match exp {
+            Exp::Var(_) => "Var",
+            Exp::App(_) => "App",
+            Exp::Abs(_) => "Abs",
+            Exp::Prd(_) => "Prd",
+            Exp::Sum(_) => "Sum",
+            Exp::Unv(_) => "Unv",
+        };
+        let fields = match exp {
+            Exp::Var(var) => Err(Part::Leaf(var, level + 1)),
+            Exp::App(App { fst, snd }) => Ok(vec![
+                ("fst: ", Part::Exp(fst, level + 2)),
+                ("snd: ", Part::Exp(snd, level + 2)),
+            ]),
+            Exp::Abs(Abs { sym, typ, exp })
+            | Exp::Prd(Prd { sym, typ, exp })
+            | Exp::Sum(Sum { sym, typ, exp }) => Ok(vec![
+                ("sym: ", Part::Leaf(sym, level + 2)),
+                ("typ: ", Part::Exp(typ, level + 2)),
+                ("exp: ", Part::Exp(exp, level + 2)),
+            ]),
+            Exp::Unv(unv) => Err(Part::Leaf(unv, level + 1)),
+        };
+        let mut res = vec![Part::Str(name), Part::Str("("), Part::Line(level + 1)];
+        match fields {
+            Err(leaf) => res.push(leaf), // variables and universes are formatted as a whole
+            Ok(fields) => {
+                res.extend([Part::Str(name), Part::Str(" {")]);
+                for (pos, (field, val)) in fields.into_iter().enumerate() {
+                    res.push(match pretty {
+                        true => Part::Line(level + 2),
+                        false if pos == 0 => Part::Str(" "),
+                        false => Part::Str(", "),
+                    });
+                    res.extend([
+                        Part::Str(field),
+                        val,
+                        Part::Str(if pretty { "," } else { "" }),
+                    ]);
+                }
+                let close = if pretty { "}" } else { " }" };
+                res.extend([Part::Line(level + 1), Part::Str(close)]);
+            }
+        }
+        let close = if pretty { "," } else { "" };
+        res.extend([Part::Str(close), Part::Line(level), Part::Str(")")]);
+        res
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::enc::core::par::decode;
+
+    /// Depth of the expressions used to check that traversals do not overflow the stack.
+    const DEPTH: u64 = 1_000_000;
+
+    /// Creates a chain of λ-abstractions, each binding a distinct symbol, around a body.
+    fn binders(body: Exp) -> Exp {
+        (0..DEPTH).rev().fold(body, |exp, num| {
+            Exp::Abs(Abs {
+                sym: Sym::new(&format!("x{}", num)),
+                typ: Box::new(Exp::Unv(Unv::new())),
+                exp: Box::new(exp),
+            })
+        })
+    }
+
+    /// Creates a spine of applications of a variable to itself.
+    fn spine(sym: &Sym) -> Exp {
+        let var = || Exp::Var(Var::Sym(sym.clone()));
+        (0..DEPTH).fold(var(), |exp, _| Exp::App(App::new(exp, var())))
+    }
+
+    /// Returns the innermost body of a chain of binders.
+    fn body(mut exp: &Exp) -> &Exp {
+        while let Exp::Abs(abs) = exp {
+            exp = &abs.exp;
+        }
+        exp
+    }
+
+    #[test]
+    fn test_debug() {
+        assert_eq!(
+            format!("{:?}", decode("λx : □ . f x")),
+            concat!(
+                r#"Abs(Abs { sym: Sym { val: "x" }, typ: Unv(Unv { level: 0 }), exp: App(App { "#,
+                r#"fst: Var(Sym(Sym { val: "f" })), snd: Var(Idx(Idx { val: 0, sym: Sym { val: "x" } })) "#,
+                r#"}) })"#,
+            )
+        );
+        assert_eq!(
+            format!("{:#?}", decode("f □")),
+            r#"App(
+    App {
+        fst: Var(
+            Sym(
+                Sym {
+                    val: "f",
+                },
+            ),
+        ),
+        snd: Unv(
+            Unv {
+                level: 0,
+            },
+        ),
+    },
+)"#
+        );
+    }
+
+    #[test]
+    fn test_deep_debug() {
+        let exp = binders(Exp::Unv(Unv::new()));
+        let val = format!("{:?}", exp);
+        assert!(
+            val.starts_with(r#"Abs(Abs { sym: Sym { val: "x0" }, typ: Unv(Unv { level: 0 }), "#)
+        );
+        assert!(val.ends_with(&format!(
+            "exp: Unv(Unv {{ level: 0 }}){}",
+            " })".repeat(DEPTH as usize)
+        )));
+    }
+
+    #[test]
+    fn test_deep_index() -> Result<(), SystemErr> {
+        let sym = Sym::new("y");
+        let mut exp = binders(Exp::Var(Var::Sym(sym.clone())));
+        exp.index(&sym, &Idx::new(&sym))?;
+        assert_eq!(body(&exp), &Exp::Var(Var::Idx(Idx { val: DEPTH, sym })));
+
+        let sym = Sym::new("f");
+        let mut exp = spine(&sym);
+        exp.index(&sym, &Idx::new(&sym))?;
+        let mut fst = &exp;
+        while let Exp::App(app) = fst {
+            assert_eq!(app.snd.as_ref(), &Exp::Var(Var::Idx(Idx::new(&sym))));
+            fst = &app.fst;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_deep_lift() -> Result<(), SystemErr> {
+        let sym = Sym::new("y");
+        let var = |val| {
+            Exp::Var(Var::Idx(Idx {
+                val,
+                sym: sym.clone(),
+            }))
+        };
+        let mut exp = binders(var(DEPTH));
+        exp.lift(2)?;
+        assert_eq!(body(&exp), &var(DEPTH + 2));
+        assert_eq!(exp.lift(u64::MAX), Err(SystemErr::MaxLimitIdx(DEPTH + 2)));
+        assert_eq!(body(&exp), &var(DEPTH + 2));
+
+        exp.instantiate(&Exp::Var(Var::Sym(Sym::new("z"))))?;
+        assert_eq!(body(&exp), &var(DEPTH + 1));
+        let mut exp = binders(var(DEPTH));
+        exp.instantiate(&var(3))?;
+        assert_eq!(body(&exp), &var(DEPTH + 3));
+        Ok(())
+    }
+
+    #[test]
+    fn test_deep_eq() {
+        let exp = binders(Exp::Var(Var::Sym(Sym::new("y"))));
+        let other = exp.clone();
+        assert_eq!(exp, other);
+        assert!(exp.alpha_eq(&other));
+
+        let other = binders(Exp::Var(Var::Sym(Sym::new("z"))));
+        assert_ne!(exp, other);
+        assert!(!exp.alpha_eq(&other));
+
+        let exp = spine(&Sym::new("f"));
+        assert_eq!(exp, exp.clone());
+        assert_ne!(exp, spine(&Sym::new("g")));
+    }
+
+    #[test]
+    fn test_deep_drop() {
+        drop(binders(Exp::Unv(Unv::new())));
+        drop(spine(&Sym::new("f")));
+        let mut exp = binders(Exp::Unv(Unv::new()));
+        if let Exp::Abs(abs) = &mut exp {
+            drop(std::mem::take(&mut *abs.exp)); // dropped independently of the outermost binder
+            assert_eq!(abs.exp.as_ref(), &Exp::Unv(Unv::new()));
+        }
+    }
+
+    #[test]
+    fn test_index_typ() -> Result<(), SystemErr> {
+        let sym = Sym::new("a");
+        let var = || Exp::Var(Var::Sym(sym.clone()));
+        let idx = |val| {
+            Exp::Var(Var::Idx(Idx {
+                val,
+                sym: sym.clone(),
+            }))
+        };
+        let abs = |sym: &str, typ, exp| {
+            Exp::Abs(Abs {
+                sym: Sym::new(sym),
+                typ: Box::new(typ),
+                exp: Box::new(exp),
+            })
+        };
+
+        let mut exp = abs("x", var(), abs("y", var(), var()));
+        exp.index(&sym, &Idx::new(&sym))?;
+        assert_eq!(exp, abs("x", idx(0), abs("y", idx(1), idx(2))));
+
+        let mut exp = abs("a", var(), var()); // shadows the symbol, but not within its own type
+        exp.index(&sym, &Idx::new(&sym))?;
+        assert_eq!(exp, abs("a", idx(0), var()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_free_syms() {
+        let exp = decode("λa : b . f a (g b) f");
+        let res = [Sym::new("b"), Sym::new("f"), Sym::new("g")];
+        assert_eq!(exp.free_syms(), res.iter().collect::<Vec<_>>());
+        assert!(decode("λa : □ . a").free_syms().is_empty());
+    }
+
+    #[test]
+    fn test_max_free_index() {
+        let Exp::Abs(abs) = &decode("λa : □ . λx : a . Πy : a . x a") else {
+            unreachable!()
+        };
+        assert_eq!(abs.exp.max_free_index(), Some(0));
+        let Exp::Abs(abs) = abs.exp.as_ref() else {
+            unreachable!()
+        };
+        assert_eq!(abs.exp.max_free_index(), Some(1));
+        assert_eq!(abs.typ.max_free_index(), Some(0));
+        assert_eq!(decode("λa : □ . f a").max_free_index(), None);
+    }
+
+    #[test]
+    fn test_is_closed() {
+        assert!(decode("λa : □ . λx : a . x").is_closed());
+        assert!(decode("□").is_closed());
+        assert!(!decode("λa : □ . f a").is_closed());
+        let Exp::Abs(abs) = &decode("λa : □ . λx : a . x") else {
+            unreachable!()
+        };
+        assert!(!abs.exp.is_closed());
+    }
+
+    #[test]
+    fn test_occurs() {
+        let Exp::Prd(prd) = &decode("Πa : □ . Πx : a . a") else {
+            unreachable!()
+        };
+        assert!(prd.exp.occurs(0)); // dependent on its argument
+        let Exp::Prd(prd) = prd.exp.as_ref() else {
+            unreachable!()
+        };
+        assert!(!prd.exp.occurs(0)); // not dependent on its argument
+        assert!(prd.exp.occurs(1));
+        assert!(!prd.exp.occurs(2));
+    }
+
+    #[test]
+    fn test_open_close() -> Result<(), SystemErr> {
+        let exp = decode("λa : □ . λx : a . Πy : a . f x y");
+        let Exp::Abs(abs) = &exp else { unreachable!() };
+        let Exp::Abs(inner) = abs.exp.as_ref() else {
+            unreachable!()
+        };
+        let mut body = inner.open(&Sym::new("z"));
+        let Exp::Prd(prd) = &body else { unreachable!() };
+        assert_eq!(
+            prd.typ.as_ref(),
+            &Exp::Var(Var::Idx(Idx::new(&Sym::new("a"))))
+        );
+        assert_eq!(
+            prd.exp.free_syms(),
+            vec![&Sym::new("f"), &Sym::new("z")] // no longer bound
+        );
+        assert_eq!(body.max_free_index(), Some(0)); // refers to the outermost binder only
+
+        body.close(&Sym::new("z"))?;
+        assert!(body.alpha_eq(&inner.exp));
+        assert_ne!(body, *inner.exp); // closed over a different symbol
+
+        let sym = Sym::new("x");
+        let exp = Exp::Abs(Abs {
+            sym: sym.clone(),
+            typ: Box::new(Exp::Unv(Unv::new())),
+            exp: Box::new(Exp::Var(Var::Idx(Idx {
+                val: 1,
+                sym: sym.clone(),
+            }))),
+        });
+        let mut body = exp.clone();
+        body.open(&sym);
+        body.close(&sym)?; // an inner binder for the same symbol does not shadow it
+        assert_eq!(body, exp);
+        Ok(())
+    }
+
+    #[test]
+    fn test_shift() -> Result<(), SystemErr> {
+        let Exp::Abs(abs) = &decode("λa : □ . λx : a . λy : a . x") else {
+            unreachable!()
+        };
+        let mut exp = abs.exp.as_ref().clone();
+        exp.shift(2)?;
+        assert_eq!(exp.max_free_index(), Some(2));
+        exp.shift(-2)?;
+        assert_eq!(exp, *abs.exp);
+        assert_eq!(exp.shift(-1), Err(SystemErr::MinLimitIdx(0)));
+        assert_eq!(exp, *abs.exp); // unchanged
+        Ok(())
+    }
+
+    #[test]
+    fn test_strengthen() -> Result<(), SystemErr> {
+        let Exp::Abs(abs) = &decode("λa : □ . λx : a . λy : a . x") else {
+            unreachable!()
+        };
+        let Exp::Abs(abs) = abs.exp.as_ref() else {
+            unreachable!()
+        };
+        let mut exp = abs.exp.as_ref().clone(); // λy : a . x, within a and x
+        let err = exp.strengthen(0);
+        assert_eq!(
+            err,
+            Err(SystemErr::UsedIdx(Idx::new(&Sym::new("x")).inc()?))
+        );
+        assert_eq!(exp, *abs.exp); // unchanged
+        exp.strengthen(2)?; // nothing refers that far out
+        assert_eq!(exp, *abs.exp);
+
+        let mut exp = abs.typ.as_ref().clone(); // a, within a
+        assert!(exp.strengthen(0).is_err());
+        let Exp::Abs(abs) = abs.exp.as_ref() else {
+            unreachable!()
+        };
+        let mut exp = abs.typ.as_ref().clone(); // a, within a, x
+        exp.strengthen(0)?; // removes x, which a does not refer to
+        assert_eq!(exp, Exp::Var(Var::Idx(Idx::new(&Sym::new("a")))));
+        Ok(())
+    }
 }
@@ -2,18 +2,29 @@
 
 mod abs;
 mod app;
+mod arn;
 mod ctx;
 mod exp;
 mod prd;
+mod rnd;
 mod sum;
 mod unv;
+mod val;
 mod var;
+mod vis;
+mod zip;
 
 pub use abs::Abs;
 pub use app::App;
-pub use ctx::Ctx;
+pub use arn::{Arena, ExpId, Meta, Node};
+pub use ctx::{Ctx, Decl};
 pub use exp::{Binder, Exp};
 pub use prd::Prd;
+pub use rnd::{Gen, Weights};
 pub use sum::Sum;
 pub use unv::Unv;
-pub use var::{Idx, Sym, Var};
+pub use var::{Fresh, Idx, Sym, Var};
+pub use vis::{Fold, Visitor, VisitorMut};
+pub use zip::{Path, Step, Zipper};
+
+pub(crate) use vis::Walk;
@@ -0,0 +1,571 @@
+//! Random generation of expressions, for property tests and training data.
+
+use super::{Abs, App, Ctx, Exp, Idx, Prd, Sum, Sym, Unv, Var};
+
+/// Symbols given to the binders of generated expressions, which deliberately shadow one another.
+const BINDERS: [&str; 4] = ["x", "y", "z", "f"];
+
+/// Relative weights with which each variant of [expression][Exp] is chosen while generating.
+///
+/// A variant with a weight of 0 is never chosen, unless nothing else can be. Variants that are not
+/// atomic are only chosen while there is room left within the size and depth of the expression.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub struct Weights {
+    /// Weight of [variables][Var].
+    pub var: u32,
+    /// Weight of [applications][App].
+    pub app: u32,
+    /// Weight of [λ-abstractions][Abs].
+    pub abs: u32,
+    /// Weight of [Π-types][Prd].
+    pub prd: u32,
+    /// Weight of [Σ-types][Sum].
+    pub sum: u32,
+    /// Weight of [universes][Unv].
+    pub unv: u32,
+}
+
+/// Generator of random [expressions][Exp], which is reproducible from a seed.
+///
+/// Every generated expression is well-scoped: each bound variable refers to an enclosing binder,
+/// and is annotated with its symbol. Expressions may also be generated to be well-typed within a
+/// [typing context][Ctx].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Gen {
+    /// State of the pseudo-random number generator.
+    state: u64,
+    /// Maximum number of sub-expressions within each expression, including itself.
+    size: usize,
+    /// Maximum number of binders and applications that any sub-expression is nested within.
+    depth: usize,
+    /// Weight of each variant.
+    weights: Weights,
+    /// Symbols of the free variables that may occur, for well-scoped expressions.
+    free: Vec<Sym>,
+    /// Maximum level of the universes that may occur, for well-scoped expressions.
+    level: u64,
+}
+
+/// Variant of an expression, as chosen while generating.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Kind {
+    Var,
+    App,
+    Abs,
+    Prd,
+    Sum,
+    Unv,
+}
+
+/// Pending step in generating a well-scoped expression.
+enum Task {
+    /// Generate an expression of at most the supplied size and nested within the supplied depth,
+    /// within a scope of the supplied length that is extended by the binder symbol, if any.
+    Exp(usize, usize, usize, Option<Sym>),
+    /// Pop the generated constituents of an expression of the supplied kind, and push the
+    /// expression.
+    Build(Kind, Sym),
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            var: 4,
+            app: 3,
+            abs: 2,
+            prd: 1,
+            sum: 1,
+            unv: 1,
+        }
+    }
+}
+
+impl Weights {
+    /// Returns the weight of a variant.
+    fn get(&self, kind: Kind) -> u32 {
+        match kind {
+            Kind::Var => self.var,
+            Kind::App => self.app,
+            Kind::Abs => self.abs,
+            Kind::Prd => self.prd,
+            Kind::Sum => self.sum,
+            Kind::Unv => self.unv,
+        }
+    }
+}
+
+impl Gen {
+    /// Create a new instance of a generator from a seed, which generates expressions of up to 32
+    /// sub-expressions, nested up to 8 deep, with no free variables and only universes at level 0.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: seed,
+            size: 32,
+            depth: 8,
+            weights: Weights::default(),
+            free: vec![],
+            level: 0,
+        }
+    }
+
+    /// Returns a copy of the generator with a maximum number of sub-expressions within each
+    /// expression, which is at least 1.
+    pub fn with_size(self, size: usize) -> Self {
+        Self {
+            size: size.max(1),
+            ..self
+        }
+    }
+
+    /// Returns a copy of the generator with a maximum depth, which is the number of binders and
+    /// applications that any sub-expression is nested within.
+    pub fn with_depth(self, depth: usize) -> Self {
+        Self { depth, ..self }
+    }
+
+    /// Returns a copy of the generator with the supplied weights for each variant.
+    pub fn with_weights(self, weights: Weights) -> Self {
+        Self { weights, ..self }
+    }
+
+    /// Returns a copy of the generator that may use the supplied symbols as free variables within
+    /// well-scoped expressions.
+    pub fn with_free(self, free: &[Sym]) -> Self {
+        Self {
+            free: free.to_vec(),
+            ..self
+        }
+    }
+
+    /// Returns a copy of the generator that may use universes of up to the supplied level within
+    /// well-scoped expressions.
+    pub fn with_level(self, level: u64) -> Self {
+        Self { level, ..self }
+    }
+
+    /// Generates a well-scoped expression.
+    pub fn exp(&mut self) -> Exp {
+        let mut scope: Vec<Sym> = vec![];
+        let mut tasks = vec![Task::Exp(self.size, 0, 0, None)];
+        let mut res: Vec<Exp> = vec![];
+        while let Some(task) = tasks.pop() {
+            let (size, depth, len, sym) = match task {
+                Task::Exp(size, depth, len, sym) => (size, depth, len, sym),
+                Task::Build(kind, sym) => {
+                    let snd = Box::new(res.pop().unwrap_or(Exp::Unv(Unv::new())));
+                    let fst = Box::new(res.pop().unwrap_or(Exp::Unv(Unv::new())));
+                    res.push(build(kind, sym, fst, snd));
+                    continue;
+                }
+            };
+            scope.truncate(len);
+            scope.extend(sym);
+            let len = scope.len();
+            let nested = size >= 3 && depth < self.depth;
+            let vars = len + self.free.len();
+            match self.pick(nested, vars > 0) {
+                Kind::Var => {
+                    let pos = self.below(vars);
+                    res.push(Exp::Var(match pos.checked_sub(len) {
+                        Some(pos) => Var::Sym(self.free[pos].clone()),
+                        None => Var::Idx(Idx {
+                            val: pos as u64,
+                            sym: scope[len - 1 - pos].clone(),
+                        }),
+                    }));
+                }
+                Kind::Unv => {
+                    let level = self.level();
+                    res.push(Exp::Unv(Unv { level }));
+                }
+                kind => {
+                    let (fst, snd) = self.split(size);
+                    let (sym, bound) = match kind {
+                        Kind::App => (Sym::new(BINDERS[0]), None),
+                        _ => {
+                            let sym = Sym::new(BINDERS[self.below(BINDERS.len())]);
+                            (sym.clone(), Some(sym))
+                        }
+                    };
+                    tasks.push(Task::Build(kind, sym));
+                    tasks.push(Task::Exp(snd, depth + 1, len, bound));
+                    tasks.push(Task::Exp(fst, depth + 1, len, None));
+                }
+            }
+        }
+        res.pop().unwrap_or(Exp::Unv(Unv::new()))
+    }
+
+    /// Generates an expression that is well-typed within the supplied typing context, whose
+    /// declared free variables may occur within it.
+    ///
+    /// <p style="background: rgba(255, 181, 77, 0.16); padding: 0.75em">
+    /// <strong>Warning:</strong> Unlike [Gen::exp], this method recurses to the depth of the
+    /// generator, as type inference does.
+    /// </p>
+    pub fn typed(&mut self, ctx: &Ctx) -> Exp {
+        self.typed_exp(&mut ctx.clone(), 0, self.size)
+    }
+
+    /// Generates a well-typed expression of at most the supplied size, which is at least 1.
+    fn typed_exp(&mut self, ctx: &mut Ctx, depth: usize, size: usize) -> Exp {
+        let nested = size >= 3 && depth < self.depth;
+        let vars = vars(ctx);
+        match self.pick(nested, !vars.is_empty()) {
+            Kind::Var => vars[self.below(vars.len())].clone(),
+            Kind::Unv => Exp::Unv(Unv::new()),
+            Kind::App => {
+                let (fst, snd) = self.split(size);
+                let fst = self.typed_exp(ctx, depth + 1, fst);
+                let typ = ctx.infer(&fst).ok().and_then(|typ| ctx.whnf(&typ).ok());
+                let snd = match &typ {
+                    Some(Exp::Prd(prd)) => self.inhabit(ctx, &prd.typ, depth + 1, snd),
+                    _ => None,
+                };
+                match snd {
+                    Some(snd) => Exp::App(App::new(fst, snd)),
+                    None => fst, // nothing to apply it to
+                }
+            }
+            kind => {
+                let sym = Sym::new(BINDERS[self.below(BINDERS.len())]);
+                let (typ, exp) = self.split(size);
+                let typ = self
+                    .typed_type(ctx, true, depth + 1, typ)
+                    .unwrap_or(Exp::Unv(Unv::new()));
+                ctx.bind(sym.clone(), typ.clone());
+                let exp = if kind == Kind::Abs {
+                    Some(self.typed_exp(ctx, depth + 1, exp))
+                } else {
+                    self.typed_type(ctx, true, depth + 1, exp)
+                };
+                ctx.unbind();
+                build(
+                    kind,
+                    sym,
+                    Box::new(typ),
+                    Box::new(exp.unwrap_or(Exp::Unv(Unv::new()))),
+                )
+            }
+        }
+    }
+
+    /// Generates a type of at most the supplied size, which is at least 1, and which belongs to
+    /// the universe at level 0, or at level 1 if `large` is set.
+    ///
+    /// Returns `None` when no such type can be generated, which is only the case for level 0.
+    fn typed_type(&mut self, ctx: &mut Ctx, large: bool, depth: usize, size: usize) -> Option<Exp> {
+        let nested = size >= 3 && depth < self.depth;
+        let level = Exp::Unv(Unv::new());
+        let vars: Vec<Exp> = vars(ctx)
+            .into_iter()
+            .filter(|var| ctx.check(var, &level).is_ok())
+            .collect();
+        let mut kinds = vec![];
+        if !vars.is_empty() {
+            kinds.push((Kind::Var, self.weights.var));
+        }
+        if nested {
+            kinds.push((Kind::Prd, self.weights.prd));
+            kinds.push((Kind::Sum, self.weights.sum));
+        }
+        if large {
+            kinds.push((Kind::Unv, self.weights.unv));
+        }
+        match self.choose(&kinds)? {
+            Kind::Var => Some(vars[self.below(vars.len())].clone()),
+            Kind::Unv => Some(Exp::Unv(Unv::new())),
+            kind => {
+                let sym = Sym::new(BINDERS[self.below(BINDERS.len())]);
+                let (typ, exp) = self.split(size);
+                let typ = self.typed_type(ctx, large, depth + 1, typ)?;
+                ctx.bind(sym.clone(), typ.clone());
+                let exp = self.typed_type(ctx, large, depth + 1, exp);
+                ctx.unbind();
+                Some(build(kind, sym, Box::new(typ), Box::new(exp?)))
+            }
+        }
+    }
+
+    /// Generates an expression of the supplied type and of at most the supplied size, preferring
+    /// a variable of that type, or returns `None` if there is none.
+    fn inhabit(&mut self, ctx: &mut Ctx, typ: &Exp, depth: usize, size: usize) -> Option<Exp> {
+        let vars: Vec<Exp> = vars(ctx)
+            .into_iter()
+            .filter(|var| ctx.check(var, typ).is_ok())
+            .collect();
+        if !vars.is_empty() {
+            return Some(vars[self.below(vars.len())].clone());
+        }
+        if depth >= self.depth {
+            return None;
+        }
+        match &ctx.whnf(typ).ok()? {
+            Exp::Unv(unv) => self.typed_type(ctx, unv.level > 0, depth, size),
+            Exp::Prd(prd) => {
+                let size = size.checked_sub(1 + self::size(&prd.typ))?; // left for the body
+                if size == 0 {
+                    return None;
+                }
+                ctx.bind(prd.sym.clone(), (*prd.typ).clone());
+                let exp = self.inhabit(ctx, &prd.exp, depth + 1, size);
+                ctx.unbind();
+                Some(Exp::Abs(Abs {
+                    sym: prd.sym.clone(),
+                    typ: prd.typ.clone(),
+                    exp: Box::new(exp?),
+                }))
+            }
+            _ => None,
+        }
+    }
+
+    /// Splits the size of an expression with two constituents, which is at least 3, between them,
+    /// so that each is left with a size of at least 1.
+    fn split(&mut self, size: usize) -> (usize, usize) {
+        let fst = 1 + self.below(size - 2);
+        (fst, size - 1 - fst)
+    }
+
+    /// Chooses a variant, which is not atomic only if the expression may be `nested`, and is not a
+    /// variable unless there are `vars` to choose from.
+    fn pick(&mut self, nested: bool, vars: bool) -> Kind {
+        let mut kinds = vec![(Kind::Unv, self.weights.unv)];
+        if vars {
+            kinds.push((Kind::Var, self.weights.var));
+        }
+        if nested {
+            kinds.extend([Kind::App, Kind::Abs, Kind::Prd, Kind::Sum].map(|kind| {
+                let weight = self.weights.get(kind);
+                (kind, weight)
+            }));
+        }
+        self.choose(&kinds).unwrap_or(Kind::Unv)
+    }
+
+    /// Chooses one of the supplied variants with probability proportional to its weight, or the
+    /// first if every weight is 0; returns `None` if there are no variants.
+    fn choose(&mut self, kinds: &[(Kind, u32)]) -> Option<Kind> {
+        let total: u64 = kinds.iter().map(|(_, weight)| u64::from(*weight)).sum();
+        if total == 0 {
+            return kinds.first().map(|(kind, _)| *kind);
+        }
+        let mut pos = self.next() % total;
+        for (kind, weight) in kinds {
+            match pos.checked_sub(u64::from(*weight)) {
+                Some(val) => pos = val,
+                None => return Some(*kind),
+            }
+        }
+        None
+    }
+
+    /// Returns the level of a universe, which is at most the maximum level of the generator.
+    fn level(&mut self) -> u64 {
+        match self.level {
+            0 => 0, // nothing to choose, so the sequence of numbers is unaffected
+            u64::MAX => self.next(),
+            level => self.next() % (level + 1),
+        }
+    }
+
+    /// Returns a number that is less than the supplied bound, which must be positive.
+    fn below(&mut self, bound: usize) -> usize {
+        ((u128::from(self.next()) * bound as u128) >> 64) as usize
+    }
+
+    /// Returns the next pseudo-random number, using the *SplitMix64* algorithm.
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut res = self.state;
+        res = (res ^ (res >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        res = (res ^ (res >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        res ^ (res >> 31)
+    }
+}
+
+#[cfg(feature = "proptest")]
+impl Gen {
+    /// Returns a [proptest] strategy that generates well-scoped expressions with the settings of
+    /// this generator, from random seeds.
+    ///
+    /// Failing cases are shrunk by lowering both the seed and the size, from which each case is
+    /// generated afresh. A shrunk case is therefore bounded by a lower size, but is otherwise
+    /// unrelated to the case that failed.
+    pub fn strategy(self) -> impl proptest::strategy::Strategy<Value = Exp> {
+        use proptest::strategy::Strategy;
+        (proptest::num::u64::ANY, 1..=self.size).prop_map(move |(seed, size)| {
+            let mut gen = Gen::new(seed).with_size(size);
+            gen.depth = self.depth;
+            gen.weights = self.weights;
+            gen.free = self.free.clone();
+            gen.level = self.level;
+            gen.exp()
+        })
+    }
+
+    /// Returns a [proptest] strategy that generates expressions that are well-typed within the
+    /// supplied typing context, with the settings of this generator, from random seeds.
+    ///
+    /// Failing cases are shrunk as with [Gen::strategy].
+    pub fn typed_strategy(self, ctx: Ctx) -> impl proptest::strategy::Strategy<Value = Exp> {
+        use proptest::strategy::Strategy;
+        (proptest::num::u64::ANY, 1..=self.size).prop_map(move |(seed, size)| {
+            let mut gen = Gen::new(seed).with_size(size);
+            gen.depth = self.depth;
+            gen.weights = self.weights;
+            gen.typed(&ctx)
+        })
+    }
+}
+
+/// Returns every variable within a typing context: bound variables, innermost first, and then
+/// declared free variables.
+fn vars(ctx: &Ctx) -> Vec<Exp> {
+    let bound = ctx.idxs().iter().rev().enumerate().map(|(val, decl)| {
+        Exp::Var(Var::Idx(Idx {
+            val: val as u64,
+            sym: decl.sym.clone(),
+        }))
+    });
+    let free = ctx
+        .syms()
+        .iter()
+        .map(|decl| Exp::Var(Var::Sym(decl.sym.clone())));
+    bound.chain(free).collect()
+}
+
+/// Builds an expression of the supplied kind from its constituents, where the symbol is only used
+/// by binders.
+fn build(kind: Kind, sym: Sym, fst: Box<Exp>, snd: Box<Exp>) -> Exp {
+    match kind {
+        Kind::Abs => Exp::Abs(Abs {
+            sym,
+            typ: fst,
+            exp: snd,
+        }),
+        Kind::Prd => Exp::Prd(Prd {
+            sym,
+            typ: fst,
+            exp: snd,
+        }),
+        Kind::Sum => Exp::Sum(Sum {
+            sym,
+            typ: fst,
+            exp: snd,
+        }),
+        _ => Exp::App(App { fst, snd }),
+    }
+}
+
+/// Returns the number of sub-expressions within an expression, including itself.
+fn size(exp: &Exp) -> usize {
+    let mut res = 0;
+    let mut stack = vec![exp];
+    while let Some(exp) = stack.pop() {
+        res += 1;
+        match exp {
+            Exp::App(App { fst, snd }) => stack.extend([&**fst, &**snd]),
+            Exp::Abs(Abs { typ, exp, .. })
+            | Exp::Prd(Prd { typ, exp, .. })
+            | Exp::Sum(Sum { typ, exp, .. }) => stack.extend([&**typ, &**exp]),
+            Exp::Var(_) | Exp::Unv(_) => {}
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_exp() {
+        for seed in 0..200 {
+            let exp = Gen::new(seed).with_size(40).exp();
+            assert!(exp.validate().is_empty());
+            assert!(exp.is_closed());
+            assert!(size(&exp) <= 40);
+            assert_eq!(Gen::new(seed).with_size(40).exp(), exp); // reproducible
+        }
+        let free = [Sym::new("a"), Sym::new("b")];
+        let mut gen = Gen::new(7).with_free(&free).with_depth(3);
+        for _ in 0..50 {
+            let exp = gen.exp();
+            assert!(exp.validate().is_empty());
+            assert!(exp.free_syms().iter().all(|sym| free.contains(sym)));
+        }
+    }
+
+    #[test]
+    fn test_exp_level() {
+        let mut levels = vec![];
+        let mut gen = Gen::new(5).with_level(2);
+        for _ in 0..50 {
+            let exp = gen.exp();
+            let mut stack = vec![&exp];
+            while let Some(exp) = stack.pop() {
+                match exp {
+                    Exp::App(App { fst, snd }) => stack.extend([&**fst, &**snd]),
+                    Exp::Abs(Abs { typ, exp, .. })
+                    | Exp::Prd(Prd { typ, exp, .. })
+                    | Exp::Sum(Sum { typ, exp, .. }) => stack.extend([&**typ, &**exp]),
+                    Exp::Unv(unv) => levels.push(unv.level),
+                    Exp::Var(_) => {}
+                }
+            }
+        }
+        assert!(levels.iter().all(|level| *level <= 2));
+        assert!((0..=2).all(|level| levels.contains(&level)));
+    }
+
+    #[test]
+    fn test_exp_weights() {
+        let weights = Weights {
+            var: 0,
+            app: 0,
+            prd: 0,
+            sum: 0,
+            ..Weights::default()
+        };
+        let exp = Gen::new(1).with_weights(weights).with_size(9).exp();
+        assert!(matches!(exp, Exp::Abs(_)));
+        let mut stack = vec![&exp];
+        while let Some(exp) = stack.pop() {
+            match exp {
+                Exp::Abs(Abs { typ, exp, .. }) => stack.extend([&**typ, &**exp]),
+                exp => assert_eq!(exp, &Exp::Unv(Unv::new())), // nothing else has any weight
+            }
+        }
+
+        let exp = Gen::new(1).with_depth(1_000).with_size(5_000).exp();
+        assert!(exp.validate().is_empty());
+    }
+
+    #[test]
+    fn test_typed() {
+        let mut ctx = Ctx::new();
+        ctx.declare(Sym::new("nat"), Exp::Unv(Unv::new()));
+        ctx.declare(Sym::new("zero"), Exp::Var(Var::Sym(Sym::new("nat"))));
+        for seed in 0..200 {
+            for max in 1..=24 {
+                let exp = Gen::new(seed).with_size(max).typed(&ctx);
+                assert!(exp.validate().is_empty());
+                assert!(ctx.infer(&exp).is_ok(), "{:?}", exp);
+                assert!(size(&exp) <= max, "{:?}", exp);
+            }
+        }
+        let exp = Gen::new(3).typed(&Ctx::new());
+        assert!(Ctx::new().infer(&exp).is_ok());
+    }
+
+    #[cfg(feature = "proptest")]
+    proptest::proptest! {
+        #[test]
+        fn test_strategy(exp in Gen::new(0).strategy()) {
+            proptest::prop_assert!(exp.validate().is_empty());
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Validation of the scoping of expressions.
+
+use super::{Abs, App, Exp, Path, Prd, Step, Sum, Sym, Var};
+use crate::err::ScopeErr;
+
+impl Exp {
+    /// Checks whether this expression is well-scoped, and returns each scope error within it, in
+    /// order of occurrence.
+    ///
+    /// The fields of expressions are public, so that expressions built outside of the parser may
+    /// contain bound variables that refer to no binder, or that are annotated with a symbol other
+    /// than that of the binder they refer to. Neither occurs in an expression that has only been
+    /// built with [Abs::new] and its counterparts, and transformed by the methods of this crate.
+    pub fn validate(&self) -> Vec<ScopeErr> {
+        let mut res = vec![];
+        let mut steps: Vec<Step> = vec![];
+        let mut syms: Vec<&Sym> = vec![];
+        // each expression is paired with the step to it and the binder it is the body of, if any,
+        // along with the lengths of the path and scope of the expression it is reached from
+        let mut stack = vec![(self, None, None, 0, 0)];
+        while let Some((exp, step, sym, len, depth)) = stack.pop() {
+            steps.truncate(len);
+            steps.extend(step);
+            syms.truncate(depth);
+            syms.extend(sym);
+            let (len, depth) = (steps.len(), syms.len());
+            match exp {
+                Exp::Var(Var::Idx(idx)) => {
+                    let path = || Path::from(steps.clone());
+                    match idx.position(depth).map(|pos| syms[pos]) {
+                        None => res.push(ScopeErr::Dangling(path(), idx.clone())),
+                        Some(sym) if sym != &idx.sym => {
+                            res.push(ScopeErr::Mismatch(path(), idx.clone(), sym.clone()))
+                        }
+                        Some(_) => {} // refers to the binder it is annotated with
+                    }
+                }
+                Exp::Var(Var::Sym(_)) => {} // free variables are always in scope
+                Exp::App(App { fst, snd }) => {
+                    stack.push((snd, Some(Step::Snd), None, len, depth));
+                    stack.push((fst, Some(Step::Fst), None, len, depth));
+                }
+                Exp::Abs(Abs { sym, typ, exp })
+                | Exp::Prd(Prd { sym, typ, exp })
+                | Exp::Sum(Sum { sym, typ, exp }) => {
+                    stack.push((exp, Some(Step::Exp), Some(sym), len, depth));
+                    stack.push((typ, Some(Step::Typ), None, len, depth)); // outside the scope
+                }
+                Exp::Unv(_) => {} // constants contain no variables
+            }
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::ast::{Idx, Unv};
+    use crate::enc::core::par::decode;
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(
+            decode("λa : □ . λx : a . Πy : a . f x y").validate(),
+            vec![]
+        );
+        assert_eq!(decode("λx : □ . λx : □ . x").validate(), vec![]);
+
+        let Exp::Abs(abs) = &decode("λa : □ . λx : a . a x") else {
+            unreachable!()
+        };
+        let idx = |val, sym| Idx {
+            val,
+            sym: Sym::new(sym),
+        };
+        let path = |val: &str| val.parse::<Path>().unwrap();
+        assert_eq!(
+            abs.exp.validate(),
+            vec![
+                ScopeErr::Dangling(path("/typ"), idx(0, "a")),
+                ScopeErr::Dangling(path("/exp/fst"), idx(1, "a"))
+            ]
+        );
+
+        let exp = Exp::Abs(Abs {
+            sym: Sym::new("x"),
+            typ: Box::new(Exp::Unv(Unv::new())),
+            exp: Box::new(Exp::App(App::new(
+                Exp::Var(Var::Idx(idx(0, "y"))),
+                Exp::Var(Var::Idx(idx(0, "x"))),
+            ))),
+        });
+        let res = exp.validate();
+        assert_eq!(
+            res,
+            vec![ScopeErr::Mismatch(
+                path("/exp/fst"),
+                idx(0, "y"),
+                Sym::new("x")
+            )]
+        );
+        assert_eq!(
+            res[0].to_string(),
+            "mismatched variable: y, at index: 0, bound as: x, at path: /exp/fst"
+        );
+    }
+}
@@ -1,7 +1,9 @@
 //! Variable, which is one of the atomic constituents of any [expression][super::Exp].
 
+use super::Exp;
 use crate::err::SystemErr;
 use crate::fmt::Formatted;
+use std::collections::HashSet;
 use std::fmt::{Display, Formatter};
 
 /// Variable, which is one of the atomic constituents of any [expression][super::Exp].
@@ -42,6 +44,17 @@ pub struct Idx {
     pub sym: Sym,
 }
 
+/// Supply of fresh [symbols][Sym], each distinct from every other symbol that it has supplied or
+/// been asked to avoid.
+///
+/// A fresh symbol is used to [open][Exp::open] the body of a binder, so that the variable it binds
+/// can be referred to by name without capturing any other free variable.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Fresh {
+    /// Names that are no longer fresh.
+    used: HashSet<String>,
+}
+
 impl Sym {
     /// Create a new instance of a [symbol][Sym].
     pub fn new(val: &str) -> Self {
@@ -49,6 +62,17 @@ impl Sym {
             val: val.to_string(),
         }
     }
+
+    /// Checks whether a raw value is a valid name for a symbol.
+    ///
+    /// As with an identifier in the core language, a valid name starts with exactly one lowercase
+    /// alphabet, and is followed by zero or more lowercase alphabets, decimal numbers or
+    /// underscores, which is the pattern `[a-z][a-z0-9_]*`.
+    pub fn is_valid(val: &str) -> bool {
+        let mut chars = val.chars();
+        chars.next().is_some_and(|ch| ch.is_ascii_lowercase())
+            && chars.all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '_')
+    }
 }
 
 impl Idx {
@@ -71,10 +95,20 @@ impl Idx {
             .ok_or(SystemErr::MaxLimitIdx(self.val))
     }
 
+    /// Returns the position of the binder that this index refers to, within a scope of `len`
+    /// binders ordered with the innermost last, or [None] if it refers to a binder outside of the
+    /// scope.
+    pub fn position(&self, len: usize) -> Option<usize> {
+        usize::try_from(self.val)
+            .ok()
+            .and_then(|val| len.checked_sub(val)?.checked_sub(1))
+    }
+
     /// Create a new instance of an index with a lower value.
     ///
     /// <p style="background: rgba(255, 181, 77, 0.16); padding: 0.75em">
-    /// <strong>Warning:</strong> This method panics when called if the current index value is 0.
+    /// <strong>Warning:</strong> This method panics when called if the current index value is 0;
+    /// see [Idx::checked_dec] for a method that does not.
     /// </p>
     pub fn dec(&self) -> Self {
         Self {
@@ -82,6 +116,81 @@ impl Idx {
             sym: self.sym.clone(),
         }
     }
+
+    /// Create a new instance of an index with a lower value, unless the current value is 0.
+    pub fn checked_dec(&self) -> Result<Self, SystemErr> {
+        self.val
+            .checked_sub(1)
+            .map(|val| Self {
+                val,
+                sym: self.sym.clone(),
+            })
+            .ok_or(SystemErr::MinLimitIdx(self.val))
+    }
+
+    /// Create a new instance of an index shifted by `delta`, unless its value is below `cutoff`.
+    ///
+    /// An index with a value below the cutoff refers to one of the `cutoff` binders nearest to it,
+    /// which are unaffected by the shift, so it is returned unchanged. Otherwise the shift must not
+    /// take the value below 0 or above [u64::MAX].
+    pub fn shift(&self, delta: i64, cutoff: u64) -> Result<Self, SystemErr> {
+        if self.val < cutoff {
+            return Ok(self.clone()); // bound by one of the nearer binders
+        }
+        let val = if delta.is_negative() {
+            self.val
+                .checked_sub(delta.unsigned_abs())
+                .ok_or(SystemErr::MinLimitIdx(self.val))?
+        } else {
+            self.val
+                .checked_add(delta.unsigned_abs())
+                .ok_or(SystemErr::MaxLimitIdx(self.val))?
+        };
+        Ok(Self {
+            val,
+            sym: self.sym.clone(),
+        })
+    }
+}
+
+impl Fresh {
+    /// Create a new instance of a supply, which has no symbols to avoid.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new instance of a supply, which avoids the free variables within an expression.
+    pub fn avoiding(exp: &Exp) -> Self {
+        let mut res = Self::new();
+        for sym in exp.free_syms() {
+            res.avoid(sym);
+        }
+        res
+    }
+
+    /// Marks a symbol as no longer fresh.
+    pub fn avoid(&mut self, sym: &Sym) {
+        self.used.insert(sym.val.clone());
+    }
+
+    /// Returns a fresh symbol based on the supplied one, and marks it as no longer fresh.
+    ///
+    /// The supplied symbol is preferred. Otherwise, any numeric suffix is replaced with the lowest
+    /// number that yields a fresh name, as when [encoding][crate::enc::core::par::Core] an
+    /// expression.
+    pub fn next(&mut self, sym: &Sym) -> Sym {
+        let stem = sym.val.trim_end_matches(|c: char| c.is_ascii_digit());
+        let val = if self.used.contains(&sym.val) {
+            (1..)
+                .map(|num| format!("{}{}", stem, num))
+                .find(|val| !self.used.contains(val))
+                .expect("exhausted numeric suffixes")
+        } else {
+            sym.val.clone()
+        };
+        self.used.insert(val.clone());
+        Sym { val }
+    }
 }
 
 impl From<Sym> for Var {
@@ -158,6 +267,16 @@ mod test {
         assert_eq!(o1.to_string(), "tangerine");
     }
 
+    #[test]
+    fn test_is_valid() {
+        for val in ["x", "tangerine", "x1", "is_sub"] {
+            assert!(Sym::is_valid(val), "{}", val);
+        }
+        for val in ["", "X", "1x", "_x", "x-y", "λ", "x y"] {
+            assert!(!Sym::is_valid(val), "{}", val);
+        }
+    }
+
     #[test]
     fn test_display_idx() -> Result<(), SystemErr> {
         let o1 = Idx::new(&Sym::new("foo"));
@@ -171,4 +290,62 @@ mod test {
         assert_eq!(o3.to_string(), "3944");
         Ok(())
     }
+
+    #[test]
+    fn test_fresh() {
+        let mut fresh = Fresh::new();
+        let x = Sym::new("x");
+        assert_eq!(fresh.next(&x), x);
+        assert_eq!(fresh.next(&x), Sym::new("x1"));
+        assert_eq!(fresh.next(&Sym::new("x1")), Sym::new("x2"));
+        fresh.avoid(&Sym::new("y"));
+        assert_eq!(fresh.next(&Sym::new("y")), Sym::new("y1"));
+
+        let mut fresh = Fresh::avoiding(&Exp::Var(Var::Sym(x.clone())));
+        assert_eq!(fresh.next(&Sym::new("x7")), Sym::new("x7"));
+        assert_eq!(fresh.next(&x), Sym::new("x1"));
+    }
+
+    #[test]
+    fn test_position() {
+        let idx = |val| Idx {
+            val,
+            sym: Sym::new("x"),
+        };
+        assert_eq!(idx(0).position(3), Some(2)); // innermost binder
+        assert_eq!(idx(2).position(3), Some(0));
+        assert_eq!(idx(3).position(3), None);
+        assert_eq!(idx(u64::MAX).position(3), None);
+        assert_eq!(idx(0).position(0), None);
+    }
+
+    #[test]
+    fn test_checked_dec() -> Result<(), SystemErr> {
+        let o1 = Idx::new(&Sym::new("foo")).inc()?;
+        assert_eq!(o1.checked_dec()?, Idx::new(&Sym::new("foo")));
+        assert_eq!(
+            o1.checked_dec()?.checked_dec(),
+            Err(SystemErr::MinLimitIdx(0))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_shift() -> Result<(), SystemErr> {
+        let idx = |val| Idx {
+            val,
+            sym: Sym::new("foo"),
+        };
+        assert_eq!(idx(3).shift(2, 0)?, idx(5));
+        assert_eq!(idx(3).shift(-3, 0)?, idx(0));
+        assert_eq!(idx(3).shift(-4, 0), Err(SystemErr::MinLimitIdx(3)));
+        assert_eq!(idx(3).shift(-4, 4)?, idx(3)); // below the cutoff
+        assert_eq!(idx(3).shift(-3, 3)?, idx(0));
+        assert_eq!(
+            idx(u64::MAX).shift(1, 0),
+            Err(SystemErr::MaxLimitIdx(u64::MAX))
+        );
+        assert_eq!(idx(u64::MAX).shift(i64::MIN, 0)?, idx(i64::MAX as u64));
+        Ok(())
+    }
 }
@@ -0,0 +1,472 @@
+//! Traversals over expressions, which passes customize by overriding only the cases they need.
+//!
+//! Each traversal tracks the depth of the sub-expression it reaches, which is the number of
+//! binders whose scope encloses it within the outermost expression traversed. A bound variable
+//! with an index below the depth refers to one of those binders; any other refers to a binder
+//! outside of the outermost expression.
+
+use super::{Abs, App, Exp, Prd, Step, Sum, Sym, Unv, Var};
+use crate::err::SystemErr;
+
+/// Traversal over a shared reference to an [expression][Exp].
+///
+/// Each sub-expression is visited before its constituents, left to right, with the type of a
+/// binder visited before its body. Every method but [Visitor::visit_exp] visits a single variant,
+/// and does nothing by default; the traversal continues into the constituents of each
+/// sub-expression regardless.
+pub trait Visitor {
+    /// Visits a [variable][Var].
+    fn visit_var(&mut self, _var: &Var, _depth: u64) {}
+
+    /// Visits an [application][App].
+    fn visit_app(&mut self, _app: &App, _depth: u64) {}
+
+    /// Visits a [λ-abstraction][Abs].
+    fn visit_abs(&mut self, _abs: &Abs, _depth: u64) {}
+
+    /// Visits a [Π-type][Prd].
+    fn visit_prd(&mut self, _prd: &Prd, _depth: u64) {}
+
+    /// Visits a [Σ-type][Sum].
+    fn visit_sum(&mut self, _sum: &Sum, _depth: u64) {}
+
+    /// Visits a [universe][Unv].
+    fn visit_unv(&mut self, _unv: &Unv, _depth: u64) {}
+
+    /// Visits an expression and each of its sub-expressions, where the expression lies at the
+    /// supplied depth.
+    fn visit_exp(&mut self, exp: &Exp, depth: u64) {
+        let mut stack = vec![(exp, depth)];
+        while let Some((exp, depth)) = stack.pop() {
+            match exp {
+                Exp::Var(var) => self.visit_var(var, depth),
+                Exp::App(app) => {
+                    self.visit_app(app, depth);
+                    stack.push((&app.snd, depth));
+                    stack.push((&app.fst, depth));
+                }
+                Exp::Abs(abs) => {
+                    self.visit_abs(abs, depth);
+                    stack.push((&abs.exp, depth.saturating_add(1)));
+                    stack.push((&abs.typ, depth)); // type is outside the scope of the binder
+                }
+                Exp::Prd(prd) => {
+                    self.visit_prd(prd, depth);
+                    stack.push((&prd.exp, depth.saturating_add(1)));
+                    stack.push((&prd.typ, depth));
+                }
+                Exp::Sum(sum) => {
+                    self.visit_sum(sum, depth);
+                    stack.push((&sum.exp, depth.saturating_add(1)));
+                    stack.push((&sum.typ, depth));
+                }
+                Exp::Unv(unv) => self.visit_unv(unv, depth),
+            }
+        }
+    }
+}
+
+/// Traversal over a mutable reference to an [expression][Exp], which modifies it in place.
+///
+/// Sub-expressions are visited in the same order as by a [Visitor]. The traversal continues into
+/// the constituents of each sub-expression as they are left by its visit.
+pub trait VisitorMut {
+    /// Visits a [variable][Var].
+    fn visit_var_mut(&mut self, _var: &mut Var, _depth: u64) {}
+
+    /// Visits an [application][App].
+    fn visit_app_mut(&mut self, _app: &mut App, _depth: u64) {}
+
+    /// Visits a [λ-abstraction][Abs].
+    fn visit_abs_mut(&mut self, _abs: &mut Abs, _depth: u64) {}
+
+    /// Visits a [Π-type][Prd].
+    fn visit_prd_mut(&mut self, _prd: &mut Prd, _depth: u64) {}
+
+    /// Visits a [Σ-type][Sum].
+    fn visit_sum_mut(&mut self, _sum: &mut Sum, _depth: u64) {}
+
+    /// Visits a [universe][Unv].
+    fn visit_unv_mut(&mut self, _unv: &mut Unv, _depth: u64) {}
+
+    /// Visits an expression and each of its sub-expressions, where the expression lies at the
+    /// supplied depth.
+    fn visit_exp_mut(&mut self, exp: &mut Exp, depth: u64) {
+        let mut stack = vec![(exp, depth)];
+        while let Some((exp, depth)) = stack.pop() {
+            match exp {
+                Exp::Var(var) => self.visit_var_mut(var, depth),
+                Exp::App(app) => {
+                    self.visit_app_mut(app, depth);
+                    stack.push((&mut app.snd, depth));
+                    stack.push((&mut app.fst, depth));
+                }
+                Exp::Abs(abs) => {
+                    self.visit_abs_mut(abs, depth);
+                    stack.push((&mut abs.exp, depth.saturating_add(1)));
+                    stack.push((&mut abs.typ, depth)); // type is outside the scope of the binder
+                }
+                Exp::Prd(prd) => {
+                    self.visit_prd_mut(prd, depth);
+                    stack.push((&mut prd.exp, depth.saturating_add(1)));
+                    stack.push((&mut prd.typ, depth));
+                }
+                Exp::Sum(sum) => {
+                    self.visit_sum_mut(sum, depth);
+                    stack.push((&mut sum.exp, depth.saturating_add(1)));
+                    stack.push((&mut sum.typ, depth));
+                }
+                Exp::Unv(unv) => self.visit_unv_mut(unv, depth),
+            }
+        }
+    }
+}
+
+/// Traversal that consumes an [expression][Exp] and rebuilds it, possibly as a different variant.
+///
+/// Sub-expressions are folded after their constituents, left to right, so that each method but
+/// [Fold::fold_exp] receives a single variant whose constituents are already folded, and rebuilds
+/// it unchanged by default. A [SystemErr] aborts the traversal, such as when an index overflows.
+pub trait Fold {
+    /// Folds a [variable][Var].
+    fn fold_var(&mut self, var: Var, _depth: u64) -> Result<Exp, SystemErr> {
+        Ok(Exp::Var(var))
+    }
+
+    /// Folds an [application][App].
+    fn fold_app(&mut self, app: App, _depth: u64) -> Result<Exp, SystemErr> {
+        Ok(Exp::App(app))
+    }
+
+    /// Folds a [λ-abstraction][Abs].
+    fn fold_abs(&mut self, abs: Abs, _depth: u64) -> Result<Exp, SystemErr> {
+        Ok(Exp::Abs(abs))
+    }
+
+    /// Folds a [Π-type][Prd].
+    fn fold_prd(&mut self, prd: Prd, _depth: u64) -> Result<Exp, SystemErr> {
+        Ok(Exp::Prd(prd))
+    }
+
+    /// Folds a [Σ-type][Sum].
+    fn fold_sum(&mut self, sum: Sum, _depth: u64) -> Result<Exp, SystemErr> {
+        Ok(Exp::Sum(sum))
+    }
+
+    /// Folds a [universe][Unv].
+    fn fold_unv(&mut self, unv: Unv, _depth: u64) -> Result<Exp, SystemErr> {
+        Ok(Exp::Unv(unv))
+    }
+
+    /// Folds an expression and each of its sub-expressions, where the expression lies at the
+    /// supplied depth.
+    fn fold_exp(&mut self, exp: Exp, depth: u64) -> Result<Exp, SystemErr> {
+        let mut tasks = vec![Task::Fold(exp, depth)];
+        let mut res: Vec<Exp> = vec![];
+        while let Some(task) = tasks.pop() {
+            let (mut exp, depth) = match task {
+                Task::Fold(exp, depth) => (exp, depth),
+                Task::Build(kind, depth) => {
+                    // the constituents of an application stand in for the type and body of a binder
+                    let exp = Box::new(res.pop().unwrap_or_default());
+                    let typ = Box::new(res.pop().unwrap_or_default());
+                    res.push(match kind {
+                        Kind::App => self.fold_app(App { fst: typ, snd: exp }, depth)?,
+                        Kind::Abs(sym) => self.fold_abs(Abs { sym, typ, exp }, depth)?,
+                        Kind::Prd(sym) => self.fold_prd(Prd { sym, typ, exp }, depth)?,
+                        Kind::Sum(sym) => self.fold_sum(Sum { sym, typ, exp }, depth)?,
+                    });
+                    continue;
+                }
+            };
+            let (kind, fst, snd, inner) = match &mut exp {
+                Exp::Var(var) => {
+                    let var = std::mem::replace(var, Var::Sym(Sym::new("")));
+                    res.push(self.fold_var(var, depth)?);
+                    continue;
+                }
+                Exp::Unv(unv) => {
+                    res.push(self.fold_unv(*unv, depth)?);
+                    continue;
+                }
+                Exp::App(App { fst, snd }) => (Kind::App, fst, snd, depth),
+                Exp::Abs(Abs { sym, typ, exp }) => {
+                    (Kind::Abs(take(sym)), typ, exp, depth.saturating_add(1))
+                }
+                Exp::Prd(Prd { sym, typ, exp }) => {
+                    (Kind::Prd(take(sym)), typ, exp, depth.saturating_add(1))
+                }
+                Exp::Sum(Sum { sym, typ, exp }) => {
+                    (Kind::Sum(take(sym)), typ, exp, depth.saturating_add(1))
+                }
+            };
+            let (fst, snd) = (std::mem::take(&mut **fst), std::mem::take(&mut **snd));
+            tasks.push(Task::Build(kind, depth));
+            tasks.push(Task::Fold(snd, inner));
+            tasks.push(Task::Fold(fst, depth)); // type is outside the scope of any binder
+        }
+        Ok(res.pop().unwrap_or_default())
+    }
+}
+
+/// Number of an expression, and the step from it to one of its constituents.
+type Edge = (usize, Step);
+
+/// Sub-expression reached by a [Walk], numbered in pre-order from `0` for the outermost expression.
+pub(crate) struct Site<'a> {
+    /// Sub-expression itself.
+    pub(crate) exp: &'a Exp,
+    /// Number of the sub-expression.
+    pub(crate) id: usize,
+    /// Number of the expression that it is a constituent of, and the step that reaches it.
+    pub(crate) parent: Option<Edge>,
+    /// Number of the binder that a bound variable refers to, or [None] if it refers to a binder
+    /// outside of the outermost expression, or the sub-expression is not a bound variable.
+    pub(crate) binder: Option<usize>,
+}
+
+/// Iterator over the sub-expressions of an expression in pre-order, with the type of a binder
+/// before its body, which links each bound variable to the binder that it refers to.
+pub(crate) struct Walk<'a> {
+    /// Numbers of the binders whose scope encloses the last sub-expression, innermost last.
+    scope: Vec<usize>,
+    /// Sub-expressions yet to be reached, each with its parent and the length of the scope
+    /// outside of the parent.
+    stack: Vec<(&'a Exp, Option<Edge>, usize)>,
+    /// Number of the next sub-expression.
+    next: usize,
+}
+
+impl<'a> Walk<'a> {
+    /// Create a new walk over an expression.
+    pub(crate) fn new(exp: &'a Exp) -> Self {
+        Self {
+            scope: vec![],
+            stack: vec![(exp, None, 0)],
+            next: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = Site<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (exp, parent, len) = self.stack.pop()?;
+        self.scope.truncate(len);
+        if let Some((parent, Step::Exp)) = parent {
+            self.scope.push(parent); // body is inside the scope of the binder
+        }
+        let len = self.scope.len();
+        let id = self.next;
+        self.next += 1;
+
+        let mut binder = None;
+        match exp {
+            Exp::Var(Var::Idx(idx)) => binder = idx.position(len).map(|pos| self.scope[pos]),
+            Exp::Var(Var::Sym(_)) | Exp::Unv(_) => {}
+            Exp::App(App { fst, snd }) => {
+                self.stack.push((snd, Some((id, Step::Snd)), len));
+                self.stack.push((fst, Some((id, Step::Fst)), len));
+            }
+            Exp::Abs(Abs { typ, exp, .. })
+            | Exp::Prd(Prd { typ, exp, .. })
+            | Exp::Sum(Sum { typ, exp, .. }) => {
+                self.stack.push((exp, Some((id, Step::Exp)), len));
+                self.stack.push((typ, Some((id, Step::Typ)), len));
+            }
+        }
+        Some(Site {
+            exp,
+            id,
+            parent,
+            binder,
+        })
+    }
+}
+
+/// Variant of an expression whose constituents are being folded, with the symbol of a binder.
+enum Kind {
+    App,
+    Abs(Sym),
+    Prd(Sym),
+    Sum(Sym),
+}
+
+/// Pending step in folding an expression.
+enum Task {
+    /// Fold an expression at the supplied depth.
+    Fold(Exp, u64),
+    /// Pop the folded constituents of an expression of the supplied kind, and fold the expression
+    /// that they make up.
+    Build(Kind, u64),
+}
+
+/// Moves the symbol of a binder out of its place.
+fn take(sym: &mut Sym) -> Sym {
+    std::mem::replace(sym, Sym::new(""))
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::ast::Idx;
+    use crate::enc::core::par::{decode, Core};
+    use crate::enc::Codec;
+
+    fn encode(exp: &Exp) -> String {
+        Core::with_show_indices(true).encode(exp)
+    }
+
+    /// Nests an expression within a chain of λ-abstractions, each applied to the next.
+    fn deep(depth: usize) -> Exp {
+        (0..depth).fold(decode("x"), |exp, _| {
+            Exp::Abs(Abs {
+                sym: Sym::new("y"),
+                typ: Box::new(Exp::Unv(Unv::new())),
+                exp: Box::new(Exp::App(App::new(exp, Exp::Var(Var::Sym(Sym::new("f")))))),
+            })
+        })
+    }
+
+    /// Collects the variables within an expression, with their depths.
+    #[derive(Default)]
+    struct Vars(Vec<(String, u64)>);
+
+    impl Visitor for Vars {
+        fn visit_var(&mut self, var: &Var, depth: u64) {
+            self.0.push((var.to_string(), depth));
+        }
+    }
+
+    /// Counts the λ-abstractions within an expression, and finds the deepest.
+    #[derive(Default)]
+    struct Lambdas(usize, u64);
+
+    impl Visitor for Lambdas {
+        fn visit_abs(&mut self, _abs: &Abs, depth: u64) {
+            self.0 += 1;
+            self.1 = self.1.max(depth);
+        }
+    }
+
+    /// Renames the free variables within an expression.
+    struct Rename(&'static str);
+
+    impl VisitorMut for Rename {
+        fn visit_var_mut(&mut self, var: &mut Var, _depth: u64) {
+            if let Var::Sym(sym) = var {
+                *sym = Sym::new(&format!("{}{}", self.0, sym));
+            }
+        }
+    }
+
+    /// Substitutes an expression for a free variable, lifting its bound variables past the
+    /// binders in between.
+    struct Subst(Sym, Exp);
+
+    impl Fold for Subst {
+        fn fold_var(&mut self, var: Var, depth: u64) -> Result<Exp, SystemErr> {
+            match var {
+                Var::Sym(sym) if sym == self.0 => {
+                    let mut res = self.1.clone();
+                    res.lift(depth)?;
+                    Ok(res)
+                }
+                var => Ok(Exp::Var(var)),
+            }
+        }
+    }
+
+    /// Fails upon reaching any universe.
+    struct Fail;
+
+    impl Fold for Fail {
+        fn fold_unv(&mut self, unv: Unv, _depth: u64) -> Result<Exp, SystemErr> {
+            Err(SystemErr::MaxLimitUnv(unv.level))
+        }
+    }
+
+    #[test]
+    fn test_visitor() {
+        let exp = decode("λx : □ . f (Πy : x . y) x");
+        let mut vars = Vars::default();
+        vars.visit_exp(&exp, 0);
+        let res: Vec<_> = vars
+            .0
+            .iter()
+            .map(|(var, depth)| (var.as_str(), *depth))
+            .collect();
+        assert_eq!(res, vec![("f", 1), ("0", 1), ("0", 2), ("0", 1)]);
+
+        let mut lambdas = Lambdas::default();
+        lambdas.visit_exp(&decode("λx : □ . f (λy : x . y) (Πz : □ . λw : z . w)"), 0);
+        assert_eq!((lambdas.0, lambdas.1), (3, 2));
+    }
+
+    #[test]
+    fn test_visitor_mut() {
+        let mut exp = decode("λx : □ . f (λy : x . g y) x");
+        Rename("my_").visit_exp_mut(&mut exp, 0);
+        assert_eq!(encode(&exp), "λx : □ . my_f (λy : 0 . my_g 0) 0");
+    }
+
+    #[test]
+    fn test_fold() -> Result<(), SystemErr> {
+        let exp = decode("λx : □ . f (λy : x . f)");
+        let var = Exp::Var(Var::Idx(Idx::new(&Sym::new("z")))); // bound outside of the expression
+        let res = Subst(Sym::new("f"), var).fold_exp(exp.clone(), 0)?;
+        assert_eq!(encode(&res), "λx : □ . 1 λy : 0 . 2");
+        let res = Subst(Sym::new("g"), decode("□")).fold_exp(exp.clone(), 0)?;
+        assert_eq!(res, exp);
+        assert_eq!(
+            Fail.fold_exp(exp, 0),
+            Err(SystemErr::MaxLimitUnv(Unv::new().level))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_walk() {
+        let exp = decode("λx : □ . f (Πy : x . y) x");
+        let res: Vec<_> = Walk::new(&exp)
+            .map(|site| (site.id, site.parent, site.binder))
+            .collect();
+        let exp = vec![
+            (0, None, None),
+            (1, Some((0, Step::Typ)), None),
+            (2, Some((0, Step::Exp)), None),
+            (3, Some((2, Step::Fst)), None),
+            (4, Some((3, Step::Fst)), None),
+            (5, Some((3, Step::Snd)), None),
+            (6, Some((5, Step::Typ)), Some(0)),
+            (7, Some((5, Step::Exp)), Some(5)),
+            (8, Some((2, Step::Snd)), Some(0)),
+        ];
+        assert_eq!(res, exp);
+
+        let exp = Exp::Var(Var::Idx(Idx::new(&Sym::new("x")))); // bound outside of the expression
+        let res: Vec<_> = Walk::new(&exp).map(|site| site.binder).collect();
+        assert_eq!(res, vec![None]);
+    }
+
+    #[test]
+    fn test_deep() -> Result<(), SystemErr> {
+        let mut exp = deep(1_000_000);
+        let mut lambdas = Lambdas::default();
+        lambdas.visit_exp(&exp, 0);
+        assert_eq!((lambdas.0, lambdas.1), (1_000_000, 999_999));
+
+        Rename("my_").visit_exp_mut(&mut exp, 0);
+        let mut vars = Vars::default();
+        vars.visit_exp(&exp, 0);
+        assert_eq!(vars.0.len(), 1_000_001);
+        assert!(vars.0.iter().all(|(var, _)| var.starts_with("my_")));
+        assert_eq!(vars.0[0], ("my_x".to_string(), 1_000_000));
+
+        let res = Subst(Sym::new("my_x"), decode("□")).fold_exp(exp, 0)?;
+        assert_eq!(Fail.fold_exp(res, 0), Err(SystemErr::MaxLimitUnv(0)));
+        Ok(())
+    }
+}
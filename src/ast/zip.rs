@@ -0,0 +1,440 @@
+//! Paths to sub-expressions, and a zipper for navigating between them.
+
+use super::{Abs, App, Exp, Prd, Sum, Sym, Unv};
+use crate::err::PathErr;
+use crate::fmt::Formatted;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Step from an [expression][Exp] to one of its constituents.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[non_exhaustive]
+pub enum Step {
+    /// Operation of an [application][App].
+    Fst,
+    /// Argument of an [application][App].
+    Snd,
+    /// Type of the variable of a [binder][super::Binder].
+    Typ,
+    /// Body of a [binder][super::Binder], which lies within its scope.
+    Exp,
+}
+
+/// Path from an [expression][Exp] to one of its sub-expressions, as a sequence of [steps][Step].
+///
+/// A path is serialized as its steps, each preceded by a slash; the empty path is serialized as a
+/// single slash. For example, the path to `y` within `λx : □ . f y` is `/exp/snd`.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[non_exhaustive]
+pub struct Path {
+    /// Steps taken from the outermost expression, in order.
+    pub steps: Vec<Step>,
+}
+
+/// Zipper over an [expression][Exp], which focuses on one of its sub-expressions.
+///
+/// The zipper holds the focus apart from the expressions enclosing it, so that moving the focus
+/// and replacing it take constant time. The focus is not re-indexed when moved, so its bound
+/// variables may refer to the binders enclosing it; see [Zipper::depth].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Zipper {
+    /// Sub-expression in focus.
+    focus: Exp,
+    /// Expressions enclosing the focus, innermost last, each with the focus (or the expression
+    /// enclosing it) taken out at the accompanying step.
+    parents: Vec<(Exp, Step)>,
+}
+
+impl Step {
+    /// Returns the position of the constituent reached by this step, which is `0` for [Step::Fst]
+    /// and [Step::Typ] and `1` for [Step::Snd] and [Step::Exp].
+    pub fn pos(&self) -> usize {
+        match self {
+            Step::Fst | Step::Typ => 0,
+            Step::Snd | Step::Exp => 1,
+        }
+    }
+
+    /// Returns the step to the constituent of an expression at the supplied position.
+    pub fn from_pos(exp: &Exp, pos: usize) -> Option<Self> {
+        match (exp, pos) {
+            (Exp::App(_), 0) => Some(Step::Fst),
+            (Exp::App(_), 1) => Some(Step::Snd),
+            (Exp::Abs(_) | Exp::Prd(_) | Exp::Sum(_), 0) => Some(Step::Typ),
+            (Exp::Abs(_) | Exp::Prd(_) | Exp::Sum(_), 1) => Some(Step::Exp),
+            _ => None,
+        }
+    }
+}
+
+impl Path {
+    /// Create a new instance of an empty path, which leads to the outermost expression.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of binders whose scope the path enters.
+    pub fn binders(&self) -> u64 {
+        self.steps.iter().filter(|step| **step == Step::Exp).count() as u64
+    }
+}
+
+impl From<Vec<Step>> for Path {
+    fn from(steps: Vec<Step>) -> Self {
+        Self { steps }
+    }
+}
+
+impl Exp {
+    /// Returns the constituent of this expression reached by a step.
+    pub fn child(&self, step: Step) -> Option<&Exp> {
+        match (self, step) {
+            (Exp::App(App { fst, .. }), Step::Fst) => Some(fst),
+            (Exp::App(App { snd, .. }), Step::Snd) => Some(snd),
+            (
+                Exp::Abs(Abs { typ, .. }) | Exp::Prd(Prd { typ, .. }) | Exp::Sum(Sum { typ, .. }),
+                Step::Typ,
+            ) => Some(typ),
+            (
+                Exp::Abs(Abs { exp, .. }) | Exp::Prd(Prd { exp, .. }) | Exp::Sum(Sum { exp, .. }),
+                Step::Exp,
+            ) => Some(exp),
+            _ => None,
+        }
+    }
+
+    /// Returns the constituent of this expression reached by a step, for modification.
+    pub fn child_mut(&mut self, step: Step) -> Option<&mut Exp> {
+        match (self, step) {
+            (Exp::App(App { fst, .. }), Step::Fst) => Some(fst),
+            (Exp::App(App { snd, .. }), Step::Snd) => Some(snd),
+            (
+                Exp::Abs(Abs { typ, .. }) | Exp::Prd(Prd { typ, .. }) | Exp::Sum(Sum { typ, .. }),
+                Step::Typ,
+            ) => Some(typ),
+            (
+                Exp::Abs(Abs { exp, .. }) | Exp::Prd(Prd { exp, .. }) | Exp::Sum(Sum { exp, .. }),
+                Step::Exp,
+            ) => Some(exp),
+            _ => None,
+        }
+    }
+
+    /// Returns the sub-expression at the end of a path.
+    pub fn get(&self, path: &Path) -> Option<&Exp> {
+        path.steps
+            .iter()
+            .try_fold(self, |exp, step| exp.child(*step))
+    }
+
+    /// Returns the sub-expression at the end of a path, for modification.
+    pub fn get_mut(&mut self, path: &Path) -> Option<&mut Exp> {
+        path.steps
+            .iter()
+            .try_fold(self, |exp, step| exp.child_mut(*step))
+    }
+
+    /// Replaces the sub-expression at the end of a path, and returns the one it replaces.
+    ///
+    /// The supplied expression is relative to the scope of this expression, so the variables
+    /// within it that are bound outside of it are lifted past the binders that the path enters. The
+    /// returned expression is left relative to its own scope.
+    pub fn replace_at(&mut self, path: &Path, mut exp: Exp) -> Result<Exp, PathErr> {
+        exp.lift(path.binders())?;
+        let mut cur = self;
+        for (pos, step) in path.steps.iter().enumerate() {
+            cur = cur.child_mut(*step).ok_or_else(|| {
+                PathErr::InvalidStep(Path::from(path.steps[..pos].to_vec()), *step)
+            })?;
+        }
+        Ok(std::mem::replace(cur, exp))
+    }
+}
+
+impl Zipper {
+    /// Create a new instance of a zipper, focused on the whole expression.
+    pub fn new(exp: Exp) -> Self {
+        Self {
+            focus: exp,
+            parents: vec![],
+        }
+    }
+
+    /// Create a new instance of a zipper, focused on the sub-expression at the end of a path.
+    pub fn at(exp: Exp, path: &Path) -> Result<Self, PathErr> {
+        let mut res = Self::new(exp);
+        for step in &path.steps {
+            if !res.down(*step) {
+                return Err(PathErr::InvalidStep(res.path(), *step));
+            }
+        }
+        Ok(res)
+    }
+
+    /// Returns the sub-expression in focus.
+    pub fn focus(&self) -> &Exp {
+        &self.focus
+    }
+
+    /// Returns the sub-expression in focus, for modification.
+    pub fn focus_mut(&mut self) -> &mut Exp {
+        &mut self.focus
+    }
+
+    /// Returns the path from the whole expression to the focus.
+    pub fn path(&self) -> Path {
+        Path::from(
+            self.parents
+                .iter()
+                .map(|(_, step)| *step)
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Returns the number of binders whose scope encloses the focus.
+    ///
+    /// A bound variable within the focus with an index at or above this number refers to a binder
+    /// outside of the whole expression.
+    pub fn depth(&self) -> u64 {
+        self.parents
+            .iter()
+            .filter(|(_, step)| *step == Step::Exp)
+            .count() as u64
+    }
+
+    /// Returns the symbols of the binders whose scope encloses the focus, innermost last.
+    pub fn scope(&self) -> Vec<&Sym> {
+        self.parents
+            .iter()
+            .filter_map(|(exp, step)| match (exp, step) {
+                (Exp::Abs(Abs { sym, .. }), Step::Exp)
+                | (Exp::Prd(Prd { sym, .. }), Step::Exp)
+                | (Exp::Sum(Sum { sym, .. }), Step::Exp) => Some(sym),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Moves the focus to the expression enclosing it, and returns whether it moved.
+    pub fn up(&mut self) -> bool {
+        match self.parents.pop() {
+            Some((mut exp, step)) => {
+                let hole = exp
+                    .child_mut(step)
+                    .expect("step was taken from this expression");
+                std::mem::swap(hole, &mut self.focus);
+                self.focus = exp;
+                true
+            }
+            None => false, // already at the top
+        }
+    }
+
+    /// Moves the focus to the constituent reached by a step, and returns whether it moved.
+    pub fn down(&mut self, step: Step) -> bool {
+        let exp = match self.focus.child_mut(step) {
+            Some(exp) => std::mem::replace(exp, Exp::Unv(Unv::new())), // leave a hole behind
+            None => return false,
+        };
+        let parent = std::mem::replace(&mut self.focus, exp);
+        self.parents.push((parent, step));
+        true
+    }
+
+    /// Moves the focus to the constituent before it within the enclosing expression, and returns
+    /// whether it moved.
+    pub fn left(&mut self) -> bool {
+        self.sibling(0)
+    }
+
+    /// Moves the focus to the constituent after it within the enclosing expression, and returns
+    /// whether it moved.
+    pub fn right(&mut self) -> bool {
+        self.sibling(1)
+    }
+
+    /// Moves the focus to the constituent at the supplied position within the enclosing
+    /// expression, unless it is already there.
+    fn sibling(&mut self, pos: usize) -> bool {
+        match self.parents.last() {
+            Some((exp, step)) if step.pos() != pos => {
+                let step = Step::from_pos(exp, pos).expect("sibling of a constituent exists");
+                self.up() && self.down(step)
+            }
+            _ => false,
+        }
+    }
+
+    /// Moves the focus to the whole expression.
+    pub fn top(&mut self) {
+        while self.up() {}
+    }
+
+    /// Replaces the sub-expression in focus, and returns the one it replaces.
+    ///
+    /// Neither expression is re-indexed, so the supplied expression must be relative to the scope
+    /// of the focus.
+    pub fn replace(&mut self, exp: Exp) -> Exp {
+        std::mem::replace(&mut self.focus, exp)
+    }
+
+    /// Returns the whole expression, with any replacements made.
+    pub fn into_exp(mut self) -> Exp {
+        self.top();
+        self.focus
+    }
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Formatted {
+        match self {
+            Step::Fst => write!(f, "fst"),
+            Step::Snd => write!(f, "snd"),
+            Step::Typ => write!(f, "typ"),
+            Step::Exp => write!(f, "exp"),
+        }
+    }
+}
+
+impl Display for Path {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Formatted {
+        if self.steps.is_empty() {
+            return write!(f, "/");
+        }
+        for step in &self.steps {
+            write!(f, "/{}", step)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Step {
+    type Err = PathErr;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val {
+            "fst" => Ok(Step::Fst),
+            "snd" => Ok(Step::Snd),
+            "typ" => Ok(Step::Typ),
+            "exp" => Ok(Step::Exp),
+            _ => Err(PathErr::InvalidPath(val.to_string())),
+        }
+    }
+}
+
+impl FromStr for Path {
+    type Err = PathErr;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val.strip_prefix('/') {
+            Some("") => Ok(Self::new()),
+            Some(steps) => steps
+                .split('/')
+                .map(|step| {
+                    step.parse()
+                        .map_err(|_| PathErr::InvalidPath(val.to_string()))
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(Path::from),
+            None => Err(PathErr::InvalidPath(val.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::enc::core::par::{decode, Core};
+    use crate::enc::Codec;
+
+    fn encode(exp: &Exp) -> String {
+        Core::new().encode(exp)
+    }
+
+    #[test]
+    fn test_path() {
+        let path: Path = "/exp/snd/typ".parse().unwrap();
+        assert_eq!(path.steps, vec![Step::Exp, Step::Snd, Step::Typ]);
+        assert_eq!(path.binders(), 1);
+        assert_eq!(path.to_string(), "/exp/snd/typ");
+        assert_eq!("/".parse::<Path>().unwrap(), Path::new());
+        assert_eq!(Path::new().to_string(), "/");
+        for val in ["", "exp", "/exp/", "/bogus", "//"] {
+            assert_eq!(
+                val.parse::<Path>(),
+                Err(PathErr::InvalidPath(val.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_get() {
+        let exp = decode("λx : □ . f (λy : x . y)");
+        let path: Path = "/exp/snd/typ".parse().unwrap();
+        assert_eq!(encode(exp.get(&path).unwrap()), "x");
+        assert_eq!(exp.get(&"/exp/snd/exp/fst".parse().unwrap()), None);
+        assert_eq!(exp.get(&Path::new()), Some(&exp));
+    }
+
+    #[test]
+    fn test_replace_at() {
+        let mut exp = decode("λx : □ . λy : □ . f");
+        let path: Path = "/exp/exp".parse().unwrap();
+        assert_eq!(encode(&exp.replace_at(&path, decode("g")).unwrap()), "f");
+        assert_eq!(encode(&exp), "λx : □ . λy : □ . g");
+
+        // variable bound just outside of the expression is lifted past the binders in between
+        let mut exp = decode("λx : □ . x");
+        let var = exp.get(&"/exp".parse().unwrap()).unwrap().clone();
+        let mut outer = decode("λw : □ . λy : □ . λz : □ . z");
+        let inner = outer.get_mut(&"/exp".parse().unwrap()).unwrap();
+        inner.replace_at(&path, var).unwrap();
+        assert_eq!(encode(&outer), "λw : □ . λy : □ . λz : □ . w");
+
+        let err = exp
+            .replace_at(&"/exp/fst".parse().unwrap(), decode("□"))
+            .unwrap_err();
+        assert_eq!(
+            err,
+            PathErr::InvalidStep("/exp".parse().unwrap(), Step::Fst)
+        );
+        exp.replace_at(&Path::new(), decode("□")).unwrap();
+        assert_eq!(exp, decode("□"));
+    }
+
+    #[test]
+    fn test_zipper() {
+        let exp = decode("λx : □ . f (λy : x . y)");
+        let mut zip = Zipper::new(exp.clone());
+        assert!(!zip.up());
+        assert!(!zip.left());
+        assert!(!zip.down(Step::Fst));
+        assert!(zip.down(Step::Exp));
+        assert!(zip.down(Step::Fst));
+        assert_eq!(encode(zip.focus()), "f");
+        assert!(!zip.left());
+        assert!(zip.right());
+        assert!(!zip.right());
+        assert!(zip.down(Step::Typ));
+        assert_eq!(zip.path().to_string(), "/exp/snd/typ");
+        assert_eq!(zip.depth(), 1);
+        assert_eq!(zip.scope(), vec![&Sym::new("x")]);
+        assert!(zip.right());
+        assert_eq!(zip.depth(), 2);
+        assert_eq!(zip.scope(), vec![&Sym::new("x"), &Sym::new("y")]);
+        assert_eq!(zip.clone().into_exp(), exp);
+
+        zip.replace(decode("g"));
+        zip.top();
+        assert_eq!(zip.path(), Path::new());
+        assert_eq!(encode(&zip.into_exp()), "λx : □ . f λy : x . g");
+
+        let zip = Zipper::at(exp.clone(), &"/exp/snd/exp".parse().unwrap()).unwrap();
+        assert_eq!(zip.focus(), exp.get(&zip.path()).unwrap());
+        assert_eq!(
+            Zipper::at(exp, &"/typ/typ".parse().unwrap()),
+            Err(PathErr::InvalidStep("/typ".parse().unwrap(), Step::Typ))
+        );
+    }
+}
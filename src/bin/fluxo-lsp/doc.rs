@@ -0,0 +1,495 @@
+//! Analysis of source documents, for the queries supported by the server.
+
+use fluxo::ast::{Abs, Ctx, Exp, Path, Prd, Step, Sum, Sym, Var};
+use fluxo::enc::core::loc::Loc;
+use fluxo::enc::core::par::Core;
+use fluxo::enc::Codec;
+use fluxo::err::{DecodeErr, TypeErr};
+use lsp_types::{
+    Diagnostic, DiagnosticSeverity, DocumentSymbol, MarkupContent, MarkupKind, Position, Range,
+    SymbolKind, TextEdit,
+};
+
+/// Target width for formatted documents, and for expressions shown in hovers.
+const WIDTH: usize = 80;
+
+/// Source document, along with the result of decoding it.
+pub struct Doc {
+    /// Text of the document.
+    pub text: String,
+    /// Expression contained in the document and its location, or the error decoding it.
+    pub res: Result<(Exp, Loc), DecodeErr>,
+}
+
+/// Target of a definition request.
+#[derive(Debug, Eq, PartialEq)]
+pub enum Target {
+    /// Binder within the document, at the supplied range.
+    Local(Range),
+    /// Free variable, which is defined elsewhere.
+    Free(Sym),
+}
+
+/// Pending step in collecting the symbols for the binders within an expression.
+enum Task<'a> {
+    /// Visit an expression, at the supplied location.
+    Visit(&'a Exp, &'a Loc),
+    /// Pop the symbols collected within the supplied number of constituents of an expression, and
+    /// push those for the expression.
+    Build(&'a Exp, &'a Loc, usize),
+}
+
+impl Doc {
+    /// Create a new document, and decode its text.
+    pub fn new(text: String) -> Self {
+        let res = Core::new().decode_loc(&text);
+        Self { text, res }
+    }
+
+    /// Returns the [expression][Exp] contained in the document, if it could be decoded.
+    pub fn exp(&self) -> Option<&Exp> {
+        self.res.as_ref().ok().map(|(exp, _)| exp)
+    }
+
+    /// Returns the diagnostics for the document, type checking it within the supplied context.
+    pub fn diagnostics(&self, ctx: &Ctx) -> Vec<Diagnostic> {
+        let (range, msg) = match &self.res {
+            Err(err) => {
+                let (sloc, eloc) = match err {
+                    DecodeErr::EndOfStream(sloc, _) => (*sloc, *sloc),
+                    DecodeErr::InvalidToken(sloc) => (*sloc, self.next_char(*sloc)),
+                    DecodeErr::UnexpectedToken(_, sloc, eloc, _)
+                    | DecodeErr::UnexpectedText(_, sloc, eloc, _) => (*sloc, *eloc),
+                    DecodeErr::InvalidValue(..)
+                    | DecodeErr::Truncated(_)
+                    | DecodeErr::InvalidTag(..)
+                    | DecodeErr::InvalidData(..)
+                    | DecodeErr::SystemErr(_) => (0, self.text.len()),
+                };
+                (self.range(sloc, eloc), err.to_string())
+            }
+            Ok((exp, loc)) => match ctx.infer(exp) {
+                Ok(_) => return vec![],
+                Err(err) => {
+                    let loc = blame(exp, &err)
+                        .and_then(|path| loc.get(&path))
+                        .unwrap_or(loc);
+                    (self.range(loc.sloc, loc.eloc), err.to_string())
+                }
+            },
+        };
+        vec![Diagnostic {
+            range,
+            severity: Some(DiagnosticSeverity::ERROR),
+            source: Some("fluxo".to_string()),
+            message: msg,
+            ..Default::default()
+        }]
+    }
+
+    /// Returns the type of the innermost expression at the supplied position, and its range.
+    pub fn hover(&self, ctx: &Ctx, pos: Position) -> Option<(MarkupContent, Range)> {
+        let (exp, loc) = self.res.as_ref().ok()?;
+        let mut ctx = ctx.clone();
+        let path = loc.find(self.offset(pos));
+        let mut cur = exp;
+        for &step in &path.steps {
+            if let (Some((sym, typ)), Step::Exp) = (binder(cur), step) {
+                ctx.bind(sym.clone(), typ.clone()); // descend into the body of the binder
+            }
+            cur = cur.child(step)?;
+        }
+        let typ = ctx.infer(cur).ok()?;
+        let loc = loc.get(&path)?;
+        let val = format!(
+            "```fluxo\n{}\n```",
+            Core::new().with_width(WIDTH).encode(&typ)
+        );
+        let res = MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: val,
+        };
+        Some((res, self.range(loc.sloc, loc.eloc)))
+    }
+
+    /// Returns the definition of the variable at the supplied position.
+    pub fn definition(&self, pos: Position) -> Option<Target> {
+        let (exp, loc) = self.res.as_ref().ok()?;
+        let mut binders = vec![];
+        let mut cur = (exp, loc);
+        for step in loc.find(self.offset(pos)).steps {
+            if let (Some(_), Step::Exp) = (binder(cur.0), step) {
+                binders.push(cur.1); // descend into the body of the binder
+            }
+            cur = (cur.0.child(step)?, cur.1.child(step)?);
+        }
+        match cur.0 {
+            Exp::Var(Var::Idx(idx)) => {
+                let pos = binders
+                    .len()
+                    .checked_sub(usize::try_from(idx.val).ok()? + 1)?;
+                let (sloc, eloc) = binders[pos].sym?;
+                Some(Target::Local(self.range(sloc, eloc)))
+            }
+            Exp::Var(Var::Sym(sym)) => Some(Target::Free(sym.clone())),
+            _ => None,
+        }
+    }
+
+    /// Returns the symbols in the document: the definition named after the document, which
+    /// contains each of the binders within it.
+    pub fn symbols(&self, name: &str, ctx: &Ctx) -> Vec<DocumentSymbol> {
+        let Ok((exp, loc)) = &self.res else {
+            return vec![];
+        };
+        let kind = match exp {
+            Exp::Abs(_) => SymbolKind::FUNCTION,
+            _ => SymbolKind::CONSTANT,
+        };
+        let detail = ctx.infer(exp).ok().map(|typ| Core::new().encode(&typ));
+        vec![self.symbol(
+            name,
+            detail,
+            kind,
+            loc,
+            (loc.sloc, loc.sloc),
+            self.binders(exp, loc),
+        )]
+    }
+
+    /// Returns the edits that format the document, if it could be decoded.
+    pub fn format(&self) -> Option<Vec<TextEdit>> {
+        let can = Core::new().with_width(WIDTH).format(&self.text).ok()?;
+        if can == self.text {
+            return Some(vec![]);
+        }
+        Some(vec![TextEdit {
+            range: self.range(0, self.text.len()),
+            new_text: can,
+        }])
+    }
+
+    /// Returns the symbols for the outermost binders within an expression, each containing the
+    /// symbols for the outermost binders within its type and body.
+    fn binders(&self, exp: &Exp, loc: &Loc) -> Vec<DocumentSymbol> {
+        let mut tasks = vec![Task::Visit(exp, loc)];
+        let mut res: Vec<Vec<DocumentSymbol>> = vec![];
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Visit(exp, loc) => {
+                    let args: Vec<_> = steps(exp)
+                        .iter()
+                        .filter_map(|&step| Some((exp.child(step)?, loc.child(step)?)))
+                        .collect();
+                    tasks.push(Task::Build(exp, loc, args.len()));
+                    tasks.extend(
+                        args.into_iter()
+                            .rev()
+                            .map(|(exp, loc)| Task::Visit(exp, loc)),
+                    );
+                }
+                Task::Build(exp, loc, num) => {
+                    let args = res
+                        .split_off(res.len() - num)
+                        .into_iter()
+                        .flatten()
+                        .collect();
+                    res.push(match (binder(exp), loc.sym, loc.child(Step::Typ)) {
+                        (Some((sym, _)), Some(sloc), Some(typ)) => {
+                            let kind = match exp {
+                                Exp::Abs(_) => SymbolKind::VARIABLE,
+                                _ => SymbolKind::TYPE_PARAMETER,
+                            };
+                            let detail = Some(self.text[typ.sloc..typ.eloc].to_string());
+                            vec![self.symbol(&sym.val, detail, kind, loc, sloc, args)]
+                        }
+                        _ => args,
+                    });
+                }
+            }
+        }
+        res.pop().unwrap_or_default()
+    }
+
+    /// Create a new symbol.
+    #[allow(deprecated)] // the deprecated field has no default, and must be supplied
+    fn symbol(
+        &self,
+        name: &str,
+        detail: Option<String>,
+        kind: SymbolKind,
+        loc: &Loc,
+        (sloc, eloc): (usize, usize),
+        children: Vec<DocumentSymbol>,
+    ) -> DocumentSymbol {
+        DocumentSymbol {
+            name: name.to_string(),
+            detail,
+            kind,
+            tags: None,
+            deprecated: None,
+            range: self.range(loc.sloc, loc.eloc),
+            selection_range: self.range(sloc, eloc),
+            children: Some(children),
+        }
+    }
+
+    /// Returns the location just past the character at the supplied location.
+    fn next_char(&self, loc: usize) -> usize {
+        self.text[loc..]
+            .chars()
+            .next()
+            .map_or(loc, |c| loc + c.len_utf8())
+    }
+
+    /// Converts a location within the text to a position, in lines and UTF-16 code units.
+    pub fn position(&self, loc: usize) -> Position {
+        let head = &self.text[..loc];
+        let line = head.matches('\n').count();
+        let col = head[head.rfind('\n').map_or(0, |pos| pos + 1)..]
+            .encode_utf16()
+            .count();
+        Position::new(line as u32, col as u32)
+    }
+
+    /// Converts a position, in lines and UTF-16 code units, to a location within the text.
+    pub fn offset(&self, pos: Position) -> usize {
+        let start = self
+            .text
+            .split_inclusive('\n')
+            .take(pos.line as usize)
+            .map(str::len)
+            .sum::<usize>();
+        let mut col = 0;
+        for (loc, c) in self.text[start..].char_indices() {
+            if col >= pos.character as usize || c == '\n' {
+                return start + loc;
+            }
+            col += c.len_utf16();
+        }
+        self.text.len()
+    }
+
+    /// Converts a pair of locations within the text to a range.
+    pub fn range(&self, sloc: usize, eloc: usize) -> Range {
+        Range::new(self.position(sloc), self.position(eloc))
+    }
+}
+
+/// Returns the symbol and type of a binder.
+fn binder(exp: &Exp) -> Option<(&Sym, &Exp)> {
+    match exp {
+        Exp::Abs(Abs { sym, typ, .. })
+        | Exp::Prd(Prd { sym, typ, .. })
+        | Exp::Sum(Sum { sym, typ, .. }) => Some((sym, typ)),
+        _ => None,
+    }
+}
+
+/// Returns the steps that reach the constituents of an expression, in order.
+fn steps(exp: &Exp) -> &'static [Step] {
+    match exp {
+        Exp::App(_) => &[Step::Fst, Step::Snd],
+        Exp::Abs(_) | Exp::Prd(_) | Exp::Sum(_) => &[Step::Typ, Step::Exp],
+        _ => &[], // variables and universes have no constituents
+    }
+}
+
+/// Returns the number of sub-expressions within an expression, including itself.
+fn size(exp: &Exp) -> usize {
+    let mut res = 0;
+    let mut stack = vec![exp];
+    while let Some(exp) = stack.pop() {
+        res += 1;
+        stack.extend(steps(exp).iter().filter_map(|&step| exp.child(step)));
+    }
+    res
+}
+
+/// Returns the path to the first expression, in pre-order, that caused a type error.
+///
+/// An expression is compared in full with the one reported only if they are of the same size, and
+/// no two sub-expressions of the same size overlap, so the search takes linear time.
+fn blame(exp: &Exp, err: &TypeErr) -> Option<Path> {
+    // each node holds a sub-expression, and the position of its parent with the step from there
+    let mut nodes: Vec<(&Exp, Option<(usize, Step)>)> = vec![];
+    let mut stack = vec![(exp, None)];
+    while let Some((exp, parent)) = stack.pop() {
+        let pos = nodes.len();
+        nodes.push((exp, parent));
+        for &step in steps(exp).iter().rev() {
+            stack.extend(exp.child(step).map(|arg| (arg, Some((pos, step)))));
+        }
+    }
+    let mut sizes = vec![1; nodes.len()];
+    for (pos, (_, parent)) in nodes.iter().enumerate().rev() {
+        if let Some((parent, _)) = parent {
+            sizes[*parent] += sizes[pos]; // constituents follow their parent in pre-order
+        }
+    }
+
+    let found = |(pos, exp): (usize, &Exp)| match err {
+        TypeErr::Undeclared(sym) => matches!(exp, Exp::Var(Var::Sym(can)) if can == sym),
+        TypeErr::Unbound(idx) => matches!(exp, Exp::Var(Var::Idx(can)) if can == idx),
+        TypeErr::NotType(can, _) | TypeErr::NotFunction(can, _) | TypeErr::Mismatch(can, ..) => {
+            sizes[pos] == size(can) && exp == can.as_ref()
+        }
+        TypeErr::SystemErr(_) => false,
+    };
+    let mut pos = (0..nodes.len()).find(|&pos| found((pos, nodes[pos].0)))?;
+    let mut res = vec![];
+    while let (_, Some((parent, step))) = nodes[pos] {
+        res.push(step);
+        pos = parent;
+    }
+    res.reverse();
+    Some(Path::from(res))
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use fluxo::ast::{Idx, Unv};
+
+    #[test]
+    fn test_position() {
+        let doc = Doc::new("λa : □ .\n  λ𝑥 : a . x".to_string());
+        for loc in [0, 2, 5, 12, 14, 16, 20, 21] {
+            assert_eq!(doc.offset(doc.position(loc)), loc);
+        }
+        assert_eq!(doc.position(16), Position::new(1, 3));
+        assert_eq!(doc.position(20), Position::new(1, 5)); // astral character is 2 code units
+        assert_eq!(doc.offset(Position::new(0, 99)), 11);
+        assert_eq!(doc.offset(Position::new(9, 0)), doc.text.len());
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let ctx = Ctx::new();
+        let doc = Doc::new("λa : □ .\n  λx : a . y x".to_string());
+        let res = doc.diagnostics(&ctx);
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].message, "undeclared variable: y");
+        assert_eq!(
+            res[0].range,
+            Range::new(Position::new(1, 11), Position::new(1, 12))
+        );
+
+        let doc = Doc::new("λa : □ .\n  λx : a :".to_string());
+        let res = doc.diagnostics(&ctx);
+        assert_eq!(
+            res[0].range,
+            Range::new(Position::new(1, 9), Position::new(1, 10))
+        );
+
+        let mut ctx = Ctx::new();
+        ctx.declare(Sym::new("y"), Exp::Unv(Unv::new()));
+        let doc = Doc::new("λa : □ .\n  λx : a . x y".to_string());
+        assert!(doc.diagnostics(&ctx)[0]
+            .message
+            .starts_with("expected a function"));
+    }
+
+    #[test]
+    fn test_diagnostics_deep() {
+        let num = 100_000;
+        let mut ctx = Ctx::new();
+        let typ = Core::new().decode(&"Πa : □ . □".to_string()).unwrap();
+        ctx.declare(Sym::new("f"), typ);
+        let val = format!("λx : □ . {}y x{}", "f (".repeat(num), ")".repeat(num));
+        let doc = Doc::new(val);
+        let res = doc.diagnostics(&ctx);
+        assert_eq!(res[0].message, "undeclared variable: y");
+        let sloc = doc.text.find('y').unwrap();
+        assert_eq!(res[0].range, doc.range(sloc, sloc + 1));
+
+        let res = doc.symbols("foo", &ctx);
+        let names: Vec<_> = res[0]
+            .children
+            .iter()
+            .flatten()
+            .map(|sym| &sym.name)
+            .collect();
+        assert_eq!(names, vec!["x"]);
+    }
+
+    #[test]
+    fn test_blame() {
+        let ctx = Ctx::new();
+        let exp = Core::new()
+            .decode(&"λa : □ . λb : a . b (b b)".to_string())
+            .unwrap();
+        let err = ctx.infer(&exp).unwrap_err();
+        let path = blame(&exp, &err).unwrap();
+        assert_eq!(path.to_string(), "/exp/exp/fst");
+        let var = Exp::Var(Var::Idx(Idx::new(&Sym::new("b"))));
+        assert_eq!(exp.get(&path), Some(&var));
+    }
+
+    #[test]
+    fn test_hover() {
+        let doc = Doc::new("λa : □ . λx : a . x".to_string());
+        let (res, range) = doc.hover(&Ctx::new(), Position::new(0, 18)).unwrap();
+        assert_eq!(res.value, "```fluxo\na\n```");
+        assert_eq!(
+            range,
+            Range::new(Position::new(0, 18), Position::new(0, 19))
+        );
+        let (res, _) = doc.hover(&Ctx::new(), Position::new(0, 0)).unwrap();
+        assert_eq!(res.value, "```fluxo\nΠa : □ . Πx : a . a\n```");
+    }
+
+    #[test]
+    fn test_definition() {
+        let doc = Doc::new("λa : □ . λx : a . f x a".to_string());
+        let range = |sloc, eloc| Range::new(Position::new(0, sloc), Position::new(0, eloc));
+        assert_eq!(
+            doc.definition(Position::new(0, 14)),
+            Some(Target::Local(range(1, 2)))
+        );
+        assert_eq!(
+            doc.definition(Position::new(0, 20)),
+            Some(Target::Local(range(10, 11)))
+        );
+        assert_eq!(
+            doc.definition(Position::new(0, 22)),
+            Some(Target::Local(range(1, 2)))
+        );
+        assert_eq!(
+            doc.definition(Position::new(0, 18)),
+            Some(Target::Free(Sym::new("f")))
+        );
+        assert_eq!(doc.definition(Position::new(0, 5)), None);
+    }
+
+    #[test]
+    fn test_symbols() {
+        let doc = Doc::new("λa : □ . λx : Πy : a . a . x".to_string());
+        let res = doc.symbols("foo", &Ctx::new());
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].name, "foo");
+        assert_eq!(
+            res[0].detail.as_deref(),
+            Some("Πa : □ . Πx : Πy : a . a . Πy : a . a")
+        );
+        let names = |syms: &Vec<DocumentSymbol>| -> Vec<String> {
+            syms.iter().map(|sym| sym.name.clone()).collect()
+        };
+        let res = res[0].children.as_ref().unwrap();
+        assert_eq!(names(res), vec!["a"]);
+        let res = res[0].children.as_ref().unwrap();
+        assert_eq!(names(res), vec!["x"]);
+        assert_eq!(res[0].detail.as_deref(), Some("Πy : a . a"));
+        assert_eq!(names(res[0].children.as_ref().unwrap()), vec!["y"]);
+    }
+
+    #[test]
+    fn test_format() {
+        let doc = Doc::new("λa : □ .   a".to_string());
+        let res = doc.format().unwrap();
+        assert_eq!(res[0].new_text, "λa : □ . a\n");
+        assert_eq!(Doc::new("λa : □ . a\n".to_string()).format(), Some(vec![]));
+        assert_eq!(Doc::new("λa".to_string()).format(), None);
+    }
+}
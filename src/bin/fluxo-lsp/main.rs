@@ -0,0 +1,269 @@
+//! Language server for the core language, which communicates over the standard input and output
+//! streams using the Language Server Protocol (LSP).
+//!
+//! Each document holds a single expression. Free variables within it are definitions that are
+//! looked up in the same directory, each in a file named after the variable with the extension
+//! `.fx`, either as currently opened by the client or as stored on disk.
+
+mod doc;
+
+use doc::{Doc, Target};
+use fluxo::ast::{Ctx, Exp, Sym};
+use fluxo::enc::core::par::Core;
+use fluxo::enc::Codec;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+    Notification as LspNotification, PublishDiagnostics,
+};
+use lsp_types::request::{
+    DocumentSymbolRequest, Formatting, GotoDefinition, HoverRequest, Request as LspRequest,
+};
+use lsp_types::{
+    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    DocumentFormattingParams, DocumentSymbolParams, DocumentSymbolResponse, GotoDefinitionParams,
+    GotoDefinitionResponse, Hover, HoverContents, HoverParams, HoverProviderCapability, Location,
+    OneOf, PublishDiagnosticsParams, ServerCapabilities, TextDocumentSyncCapability,
+    TextDocumentSyncKind, TextEdit, Url,
+};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Extension of source files in the core language.
+const EXT: &str = "fx";
+
+/// State of the server.
+struct Server {
+    /// Documents currently opened by the client.
+    docs: HashMap<Url, Doc>,
+}
+
+impl Server {
+    /// Create a new instance of the server, with no open documents.
+    fn new() -> Self {
+        Self {
+            docs: HashMap::new(),
+        }
+    }
+
+    /// Handles a request, and returns the response to it.
+    fn request(&mut self, req: Request) -> Response {
+        match req.method.as_str() {
+            HoverRequest::METHOD => self.dispatch::<HoverRequest, _>(req, Self::hover),
+            GotoDefinition::METHOD => self.dispatch::<GotoDefinition, _>(req, Self::definition),
+            DocumentSymbolRequest::METHOD => {
+                self.dispatch::<DocumentSymbolRequest, _>(req, Self::symbols)
+            }
+            Formatting::METHOD => self.dispatch::<Formatting, _>(req, Self::format),
+            _ => Response::new_err(
+                req.id,
+                ErrorCode::MethodNotFound as i32,
+                format!("unsupported method: {}", req.method),
+            ),
+        }
+    }
+
+    /// Handles a request of type `R` with the supplied function.
+    fn dispatch<R, F>(&mut self, req: Request, func: F) -> Response
+    where
+        R: LspRequest,
+        F: FnOnce(&mut Self, R::Params) -> R::Result,
+    {
+        match serde_json::from_value(req.params) {
+            Ok(params) => Response::new_ok(req.id, func(self, params)),
+            Err(err) => Response::new_err(req.id, ErrorCode::InvalidParams as i32, err.to_string()),
+        }
+    }
+
+    /// Handles a notification, and returns the notifications to be sent in reply.
+    fn notify(&mut self, not: Notification) -> Vec<Notification> {
+        match not.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                if let Ok(params) = serde_json::from_value::<DidOpenTextDocumentParams>(not.params)
+                {
+                    let doc = params.text_document;
+                    self.docs.insert(doc.uri, Doc::new(doc.text));
+                }
+            }
+            DidChangeTextDocument::METHOD => {
+                if let Ok(params) =
+                    serde_json::from_value::<DidChangeTextDocumentParams>(not.params)
+                {
+                    if let Some(change) = params.content_changes.into_iter().last() {
+                        let doc = params.text_document;
+                        self.docs.insert(doc.uri, Doc::new(change.text)); // full sync
+                    }
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                if let Ok(params) = serde_json::from_value::<DidCloseTextDocumentParams>(not.params)
+                {
+                    let uri = params.text_document.uri;
+                    self.docs.remove(&uri);
+                    return vec![publish(uri, vec![])];
+                }
+            }
+            _ => return vec![], // nothing else affects the documents
+        }
+        // documents may define variables used by each other, so they are all checked again
+        let mut res: Vec<_> = self
+            .docs
+            .iter()
+            .map(|(uri, doc)| publish(uri.clone(), doc.diagnostics(&self.context(uri, doc))))
+            .collect();
+        res.sort_by_key(|not| not.params.to_string());
+        res
+    }
+
+    /// Handles a hover request.
+    fn hover(&mut self, params: HoverParams) -> Option<Hover> {
+        let pos = params.text_document_position_params;
+        let doc = self.docs.get(&pos.text_document.uri)?;
+        let ctx = self.context(&pos.text_document.uri, doc);
+        doc.hover(&ctx, pos.position).map(|(res, range)| Hover {
+            contents: HoverContents::Markup(res),
+            range: Some(range),
+        })
+    }
+
+    /// Handles a definition request.
+    fn definition(&mut self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let pos = params.text_document_position_params;
+        let uri = pos.text_document.uri;
+        let res = match self.docs.get(&uri)?.definition(pos.position)? {
+            Target::Local(range) => Location::new(uri, range),
+            Target::Free(sym) => {
+                let path = sibling(&uri, &sym)?;
+                let uri = Url::from_file_path(&path).ok()?;
+                let range = match self.docs.get(&uri) {
+                    Some(doc) => match &doc.res {
+                        Ok((_, loc)) => doc.range(loc.sloc, loc.eloc),
+                        Err(_) => Default::default(), // start of a document that does not parse
+                    },
+                    None if path.exists() => Default::default(),
+                    None => return None,
+                };
+                Location::new(uri, range)
+            }
+        };
+        Some(GotoDefinitionResponse::Scalar(res))
+    }
+
+    /// Handles a document symbol request.
+    fn symbols(&mut self, params: DocumentSymbolParams) -> Option<DocumentSymbolResponse> {
+        let uri = params.text_document.uri;
+        let doc = self.docs.get(&uri)?;
+        let name = uri
+            .to_file_path()
+            .ok()
+            .and_then(|path| Some(path.file_stem()?.to_str()?.to_string()))
+            .unwrap_or_default();
+        let ctx = self.context(&uri, doc);
+        Some(DocumentSymbolResponse::Nested(doc.symbols(&name, &ctx)))
+    }
+
+    /// Handles a formatting request.
+    fn format(&mut self, params: DocumentFormattingParams) -> Option<Vec<TextEdit>> {
+        self.docs.get(&params.text_document.uri)?.format()
+    }
+
+    /// Returns the context for a document, which defines each free variable within it (and
+    /// within the definitions it depends on) that is defined by a file in the same directory.
+    fn context(&self, uri: &Url, doc: &Doc) -> Ctx {
+        let mut ctx = Ctx::new();
+        if let Some(exp) = doc.exp() {
+            let mut seen = HashSet::new();
+            self.resolve(uri, exp, &mut ctx, &mut seen);
+        }
+        ctx
+    }
+
+    /// Defines the free variables within an expression, after their own dependencies.
+    ///
+    /// Each variable is considered only once, so that circular definitions are left undefined
+    /// rather than resolved endlessly; the type checker then reports them as undeclared.
+    fn resolve(&self, uri: &Url, exp: &Exp, ctx: &mut Ctx, seen: &mut HashSet<Sym>) {
+        for sym in exp.free_syms().into_iter().cloned() {
+            if !seen.insert(sym.clone()) {
+                continue;
+            }
+            let Some(path) = sibling(uri, &sym) else {
+                continue;
+            };
+            let val = match Url::from_file_path(&path)
+                .ok()
+                .and_then(|uri| self.docs.get(&uri))
+            {
+                Some(doc) => doc.exp().cloned(),
+                None => std::fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|text| Core::new().decode(&text).ok()),
+            };
+            if let Some(val) = val {
+                self.resolve(uri, &val, ctx, seen);
+                if let Ok(typ) = ctx.infer(&val) {
+                    ctx.define(sym, typ, val);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the path of the file that defines a variable, in the same directory as a document.
+fn sibling(uri: &Url, sym: &Sym) -> Option<PathBuf> {
+    let path = uri.to_file_path().ok()?;
+    Some(path.parent()?.join(format!("{}.{}", sym, EXT)))
+}
+
+/// Creates a notification that publishes the diagnostics for a document.
+fn publish(uri: Url, diagnostics: Vec<lsp_types::Diagnostic>) -> Notification {
+    let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+    Notification::new(PublishDiagnostics::METHOD.to_string(), params)
+}
+
+/// Runs the server until the client shuts it down.
+fn serve() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (conn, io_threads) = Connection::stdio();
+    let caps = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    };
+    conn.initialize(serde_json::to_value(caps)?)?;
+
+    let mut server = Server::new();
+    for msg in &conn.receiver {
+        match msg {
+            Message::Request(req) => {
+                if conn.handle_shutdown(&req)? {
+                    break;
+                }
+                conn.sender.send(Message::Response(server.request(req)))?;
+            }
+            Message::Notification(not) => {
+                for res in server.notify(not) {
+                    conn.sender.send(Message::Notification(res))?;
+                }
+            }
+            Message::Response(_) => {} // no requests are sent to the client
+        }
+    }
+    drop(conn);
+    io_threads.join()?;
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match serve() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::from(5)
+        }
+    }
+}
@@ -0,0 +1,772 @@
+//! The `edit` command, which edits an expression structurally in the terminal.
+//!
+//! The editor operates on the syntax tree rather than on text. A cursor focuses a subterm, which
+//! is highlighted along with its type and the bound variables in scope, and edits replace the
+//! focused subterm as a whole. Saving writes the expression in its canonical form, so comments
+//! within the file are not preserved.
+
+use crate::{Fail, Input};
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use fluxo::ast::{Abs, App, Ctx, Exp, Idx, Path, Prd, Step, Sum, Sym, Unv, Var};
+use fluxo::enc::core::par::Core;
+use fluxo::enc::Codec;
+use fluxo::err::SystemErr;
+use std::fmt::{Display, Formatter};
+use std::io::{self, Write};
+
+/// Target width for saved expressions.
+const WIDTH: usize = 80;
+
+/// Help text for the keys available in the editor.
+const HELP: &str = "h/j/k/l move, \\ λ, p Π, s Σ, a apply, r replace, n rename, x unwrap, \
+u undo, U redo, w save, q quit, Q discard and quit";
+
+/// Key pressed within the editor.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Key {
+    /// Printable character.
+    Char(char),
+    /// Printable character, pressed along with the control key.
+    Ctrl(char),
+    Enter,
+    Esc,
+    Backspace,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Edit that takes its argument from a prompt.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Cmd {
+    /// Wrap the focus in a λ-abstraction.
+    Abs,
+    /// Wrap the focus in a Π-type.
+    Prd,
+    /// Wrap the focus in a Σ-type.
+    Sum,
+    /// Apply the focus to an argument.
+    App,
+    /// Replace the focus with an expression.
+    Replace,
+    /// Rename the binder in focus.
+    Rename,
+}
+
+impl Cmd {
+    /// Returns the label shown in front of the prompt.
+    fn label(&self) -> &'static str {
+        match self {
+            Cmd::Abs => "λ",
+            Cmd::Prd => "Π",
+            Cmd::Sum => "Σ",
+            Cmd::App => "apply to:",
+            Cmd::Replace => "replace with:",
+            Cmd::Rename => "rename to:",
+        }
+    }
+}
+
+/// Mode of the editor, which determines how keys are interpreted.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Mode {
+    /// Keys move the cursor or start edits.
+    Normal,
+    /// Keys are typed into the argument of an edit.
+    Prompt(Cmd, String),
+}
+
+/// Contents of the screen.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Frame {
+    /// Expression being edited.
+    text: String,
+    /// Locations of the first byte and just past the last byte of the focus within the text.
+    focus: (usize, usize),
+    /// Bound variables in scope of the focus, followed by its type.
+    info: Vec<String>,
+    /// Status line, showing the prompt or the latest message.
+    status: String,
+}
+
+impl Frame {
+    /// Returns the text of the focus.
+    fn focused(&self) -> &str {
+        &self.text[self.focus.0..self.focus.1]
+    }
+}
+
+impl Display for Frame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}\n", self.text)?;
+        for line in &self.info {
+            writeln!(f, "{}", line)?;
+        }
+        write!(f, "\n{}", self.status)
+    }
+}
+
+/// State of the editor.
+struct Editor {
+    /// Path of the file being edited.
+    path: String,
+    /// Expression being edited.
+    exp: Exp,
+    /// Path from the expression to the focus.
+    focus: Path,
+    /// Earlier states, with the most recent last.
+    undo: Vec<(Exp, Path)>,
+    /// Undone states, with the most recently undone last.
+    redo: Vec<(Exp, Path)>,
+    mode: Mode,
+    /// Message shown in the status line until the next key is pressed.
+    msg: Option<String>,
+    /// Whether the expression has changed since it was last saved.
+    dirty: bool,
+    /// Whether the editor has been asked to quit.
+    quit: bool,
+    /// Target width for the expression on screen.
+    width: usize,
+}
+
+impl Editor {
+    /// Create a new instance of the editor, focused on the whole expression.
+    fn new(path: String, exp: Exp) -> Self {
+        Self {
+            path,
+            exp,
+            focus: Path::new(),
+            undo: vec![],
+            redo: vec![],
+            mode: Mode::Normal,
+            msg: None,
+            dirty: false,
+            quit: false,
+            width: WIDTH,
+        }
+    }
+
+    /// Handles a key press.
+    fn key(&mut self, key: Key) {
+        self.msg = None;
+        match std::mem::replace(&mut self.mode, Mode::Normal) {
+            Mode::Normal => self.command(key),
+            Mode::Prompt(cmd, mut buf) => match key {
+                Key::Enter => {
+                    if let Err(msg) = self.apply(cmd, &buf) {
+                        self.msg = Some(msg);
+                        self.mode = Mode::Prompt(cmd, buf); // keep the input, so it can be fixed
+                    }
+                }
+                Key::Esc => {} // abandon the edit
+                Key::Backspace => {
+                    buf.pop();
+                    self.mode = Mode::Prompt(cmd, buf);
+                }
+                Key::Char(c) => {
+                    buf.push(c);
+                    self.mode = Mode::Prompt(cmd, buf);
+                }
+                _ => self.mode = Mode::Prompt(cmd, buf),
+            },
+        }
+    }
+
+    /// Handles a key press in normal mode.
+    fn command(&mut self, key: Key) {
+        let prompt = |cmd| Mode::Prompt(cmd, String::new());
+        match key {
+            Key::Char('h') | Key::Left => self.step(0),
+            Key::Char('l') | Key::Right => self.step(1),
+            Key::Char('k') | Key::Up => {
+                self.focus.steps.pop();
+            }
+            Key::Char('j') | Key::Down => {
+                if let Some(step) = Step::from_pos(self.node(), 0) {
+                    self.focus.steps.push(step);
+                } // otherwise no constituents to move into
+            }
+            Key::Char('\\' | 'λ') => self.mode = prompt(Cmd::Abs),
+            Key::Char('p' | 'Π') => self.mode = prompt(Cmd::Prd),
+            Key::Char('s' | 'Σ') => self.mode = prompt(Cmd::Sum),
+            Key::Char('a') => self.mode = prompt(Cmd::App),
+            Key::Char('r') => self.mode = prompt(Cmd::Replace),
+            Key::Char('n') => match self.node() {
+                Exp::Abs(_) | Exp::Prd(_) | Exp::Sum(_) => self.mode = prompt(Cmd::Rename),
+                _ => self.msg = Some("not a binder".to_string()),
+            },
+            Key::Char('x') => {
+                if let Err(msg) = self.edit(unwrap) {
+                    self.msg = Some(msg);
+                }
+            }
+            Key::Char('u') => self.restore(true),
+            Key::Char('U') | Key::Ctrl('r') => self.restore(false),
+            Key::Char('w') => self.save(),
+            Key::Char('q') | Key::Ctrl('c') => {
+                if self.dirty {
+                    self.msg = Some("unsaved changes, save with w or discard with Q".to_string());
+                } else {
+                    self.quit = true;
+                }
+            }
+            Key::Char('Q') => self.quit = true,
+            Key::Char('?') => self.msg = Some(HELP.to_string()),
+            Key::Char(c) => self.msg = Some(format!("unknown key: {}, see ?", c)),
+            _ => {} // nothing to do
+        }
+    }
+
+    /// Moves the focus to the sibling at the supplied position.
+    fn step(&mut self, pos: usize) {
+        if let Some(last) = self.focus.steps.pop() {
+            let parent = self.node();
+            self.focus
+                .steps
+                .push(Step::from_pos(parent, pos).unwrap_or(last));
+        }
+    }
+
+    /// Applies an edit, with the argument typed into its prompt.
+    fn apply(&mut self, cmd: Cmd, arg: &str) -> Result<(), String> {
+        match cmd {
+            Cmd::Abs | Cmd::Prd | Cmd::Sum => {
+                let (name, typ) = arg
+                    .split_once(':')
+                    .ok_or("expected a binding of the form: x : T")?;
+                let sym = self.sym(name.trim())?;
+                let typ = self.decode(typ)?;
+                self.edit(|exp| wrap(cmd, sym, typ, exp).map_err(|err| err.to_string()))
+            }
+            Cmd::App => {
+                let arg = self.decode(arg)?;
+                self.edit(|exp| Ok(Exp::App(App::new(exp.clone(), arg))))
+            }
+            Cmd::Replace => {
+                let val = self.decode(arg)?;
+                self.edit(|_| Ok(val))
+            }
+            Cmd::Rename => {
+                let sym = self.sym(arg.trim())?;
+                self.edit(|exp| {
+                    let mut exp = exp.clone();
+                    match &mut exp {
+                        Exp::Abs(Abs { sym: can, .. })
+                        | Exp::Prd(Prd { sym: can, .. })
+                        | Exp::Sum(Sum { sym: can, .. }) => *can = sym,
+                        _ => return Err("not a binder".to_string()),
+                    }
+                    Ok(exp)
+                })
+            }
+        }
+    }
+
+    /// Replaces the focus with the result of the supplied function, and records the change.
+    fn edit<F>(&mut self, func: F) -> Result<(), String>
+    where
+        F: FnOnce(&Exp) -> Result<Exp, String>,
+    {
+        let val = func(self.node())?;
+        self.undo.push((self.exp.clone(), self.focus.clone()));
+        self.redo.clear();
+        *self.node_mut() = val;
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Restores the most recent state recorded for undo, or for redo.
+    fn restore(&mut self, undo: bool) {
+        let (from, to) = if undo {
+            (&mut self.undo, &mut self.redo)
+        } else {
+            (&mut self.redo, &mut self.undo)
+        };
+        match from.pop() {
+            Some((exp, focus)) => {
+                to.push((std::mem::replace(&mut self.exp, exp), self.focus.clone()));
+                self.focus = focus;
+                self.dirty = true;
+            }
+            None => self.msg = Some(format!("nothing to {}", if undo { "undo" } else { "redo" })),
+        }
+    }
+
+    /// Writes the expression to the file being edited.
+    fn save(&mut self) {
+        let val = Core::new().with_width(WIDTH).encode(&self.exp) + "\n";
+        match std::fs::write(&self.path, val) {
+            Ok(()) => {
+                self.dirty = false;
+                self.msg = Some(format!("saved {}", self.path));
+            }
+            Err(err) => self.msg = Some(format!("{}: {}", self.path, err)),
+        }
+    }
+
+    /// Returns the focus.
+    fn node(&self) -> &Exp {
+        self.exp
+            .get(&self.focus)
+            .expect("focus is within the expression")
+    }
+
+    /// Returns the focus, for modification.
+    fn node_mut(&mut self) -> &mut Exp {
+        self.exp
+            .get_mut(&self.focus)
+            .expect("focus is within the expression")
+    }
+
+    /// Returns the typing context of the focus, which declares the bound variables in scope.
+    fn ctx(&self) -> Ctx {
+        let mut ctx = Ctx::new();
+        let mut exp = &self.exp;
+        for &step in &self.focus.steps {
+            if let Exp::Abs(Abs { sym, typ, .. })
+            | Exp::Prd(Prd { sym, typ, .. })
+            | Exp::Sum(Sum { sym, typ, .. }) = exp
+            {
+                if step == Step::Exp {
+                    ctx.bind(sym.clone(), (**typ).clone()); // descending into the body of a binder
+                }
+            }
+            exp = exp.child(step).expect("focus is within the expression");
+        }
+        ctx
+    }
+
+    /// Checks that a name is a valid symbol in the core language.
+    fn sym(&self, name: &str) -> Result<Sym, String> {
+        match &Core::new().decode(&name.to_string()) {
+            Ok(Exp::Var(Var::Sym(sym))) => Ok(sym.clone()),
+            _ => Err(format!("invalid name: {}", name)),
+        }
+    }
+
+    /// Decodes an input to an [expression][Exp], whose variables refer to the binders in scope of
+    /// the focus where their symbols match.
+    fn decode(&self, val: &str) -> Result<Exp, String> {
+        let mut exp = Core::new()
+            .decode(&val.to_string())
+            .map_err(|err| err.to_string())?;
+        for (num, decl) in self.ctx().idxs().iter().rev().enumerate() {
+            let idx = (0..num).try_fold(Idx::new(&decl.sym), |idx, _| idx.inc());
+            exp.index(&decl.sym, &idx.map_err(|err| err.to_string())?)
+                .map_err(|err| err.to_string())?;
+        }
+        Ok(exp)
+    }
+
+    /// Returns the contents of the screen.
+    fn frame(&self) -> Frame {
+        let codec = Core::new().with_width(self.width);
+        let text = codec.encode(&self.exp);
+        let focus = codec
+            .decode_loc(&text)
+            .ok()
+            .and_then(|(_, loc)| loc.get(&self.focus).map(|loc| (loc.sloc, loc.eloc)))
+            .unwrap_or((0, text.len()));
+        let ctx = self.ctx();
+        let mut info: Vec<_> = ctx
+            .idxs()
+            .iter()
+            .map(|decl| format!("{} : {}", decl.sym, codec.encode(&decl.typ)))
+            .collect();
+        info.push(match ctx.infer(self.node()) {
+            Ok(typ) => format!("type: {}", codec.encode(&typ)),
+            Err(err) => format!("error: {}", err),
+        });
+        let status = match (&self.msg, &self.mode) {
+            (Some(msg), _) => msg.clone(),
+            (None, Mode::Prompt(cmd, buf)) => format!("{} {}", cmd.label(), buf),
+            (None, Mode::Normal) => {
+                format!("{}{}", self.path, if self.dirty { " [+]" } else { "" })
+            }
+        };
+        Frame {
+            text,
+            focus,
+            info,
+            status,
+        }
+    }
+}
+
+/// Wraps an expression in a binder, which binds the free variables within it that match its symbol.
+fn wrap(cmd: Cmd, sym: Sym, typ: Exp, exp: &Exp) -> Result<Exp, SystemErr> {
+    let mut exp = exp.clone();
+    exp.lift(1)?; // the new binder lies between the expression and the binders outside it
+    Ok(match cmd {
+        Cmd::Abs => Exp::Abs(Abs::new(sym, typ, exp)?),
+        Cmd::Prd => Exp::Prd(Prd::new(sym, typ, exp)?),
+        _ => Exp::Sum(Sum::new(sym, typ, exp)?),
+    })
+}
+
+/// Removes the outermost constructor of an expression, keeping the function of an application, or
+/// the body of a binder whose variable is not used.
+fn unwrap(exp: &Exp) -> Result<Exp, String> {
+    match exp {
+        Exp::App(App { fst, .. }) => Ok((**fst).clone()),
+        Exp::Abs(Abs { exp, .. }) | Exp::Prd(Prd { exp, .. }) | Exp::Sum(Sum { exp, .. }) => {
+            let mut res = (**exp).clone();
+            match res.strengthen(0) {
+                Ok(()) => Ok(res),
+                Err(SystemErr::UsedIdx(_)) => Err("bound variable is used in the body".to_string()),
+                Err(err) => Err(err.to_string()),
+            }
+        }
+        _ => Err("nothing to unwrap".to_string()),
+    }
+}
+
+/// Parses a key script, in which characters stand for themselves and special keys are written as
+/// `<enter>`, `<esc>`, `<bs>`, `<up>`, `<down>`, `<left>`, `<right>`, `<lt>` (for `<`), or
+/// `<c-x>` (for the character `x` pressed along with the control key).
+fn keys(script: &str) -> Result<Vec<Key>, Fail> {
+    let mut res = vec![];
+    let mut rest = script;
+    while let Some(c) = rest.chars().next() {
+        if c != '<' {
+            res.push(Key::Char(c));
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        let (name, tail) = rest[1..]
+            .split_once('>')
+            .ok_or_else(|| Fail::Usage(format!("unterminated key in script: {}", rest)))?;
+        res.push(match name {
+            "enter" => Key::Enter,
+            "esc" => Key::Esc,
+            "bs" => Key::Backspace,
+            "up" => Key::Up,
+            "down" => Key::Down,
+            "left" => Key::Left,
+            "right" => Key::Right,
+            "lt" => Key::Char('<'),
+            _ => match name
+                .strip_prefix("c-")
+                .map(|c| c.chars().collect::<Vec<_>>())
+            {
+                Some(chars) if chars.len() == 1 => Key::Ctrl(chars[0]),
+                _ => return Err(Fail::Usage(format!("unknown key in script: <{}>", name))),
+            },
+        });
+        rest = tail;
+    }
+    Ok(res)
+}
+
+/// Converts a terminal key event to a key.
+fn key(event: KeyEvent) -> Option<Key> {
+    match event.code {
+        KeyCode::Char(c) if event.modifiers.contains(KeyModifiers::CONTROL) => Some(Key::Ctrl(c)),
+        KeyCode::Char(c) => Some(Key::Char(c)),
+        KeyCode::Enter => Some(Key::Enter),
+        KeyCode::Esc => Some(Key::Esc),
+        KeyCode::Backspace => Some(Key::Backspace),
+        KeyCode::Up => Some(Key::Up),
+        KeyCode::Down => Some(Key::Down),
+        KeyCode::Left => Some(Key::Left),
+        KeyCode::Right => Some(Key::Right),
+        _ => None,
+    }
+}
+
+/// Writes text to the terminal, which requires explicit carriage returns in raw mode.
+fn print(out: &mut impl Write, val: &str) -> io::Result<()> {
+    for (pos, line) in val.split('\n').enumerate() {
+        if pos > 0 {
+            queue!(out, Print("\r\n"))?;
+        }
+        queue!(out, Print(line))?;
+    }
+    Ok(())
+}
+
+/// Draws the contents of the screen, with the focus highlighted.
+fn draw(out: &mut impl Write, frame: &Frame) -> io::Result<()> {
+    let (sloc, eloc) = frame.focus;
+    queue!(out, Clear(ClearType::All), MoveTo(0, 0))?;
+    print(out, &frame.text[..sloc])?;
+    queue!(out, SetAttribute(Attribute::Reverse))?;
+    print(out, frame.focused())?;
+    queue!(out, SetAttribute(Attribute::Reset))?;
+    print(out, &frame.text[eloc..])?;
+    print(out, "\n\n")?;
+    for line in &frame.info {
+        print(out, line)?;
+        print(out, "\n")?;
+    }
+    let (_, rows) = terminal::size()?;
+    queue!(out, MoveTo(0, rows.saturating_sub(1)), Print(&frame.status))?;
+    out.flush()
+}
+
+/// Terminal in raw mode and showing the alternate screen, for the duration of a session.
+///
+/// The terminal is restored when this is dropped, and also by a panic hook before the panic is
+/// reported, so that neither an error nor a panic leaves it unusable.
+struct Screen;
+
+impl Screen {
+    /// Switches the terminal to raw mode and the alternate screen.
+    fn enter(out: &mut impl Write) -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let screen = Self; // restores raw mode if the alternate screen cannot be entered
+        execute!(out, EnterAlternateScreen, Hide)?;
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let _ = restore();
+            hook(info);
+        }));
+        Ok(screen)
+    }
+
+    /// Restores the terminal, reporting any error in doing so.
+    fn leave(self) -> io::Result<()> {
+        std::mem::forget(self);
+        restore()
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = restore(); // nothing left to report the error to
+    }
+}
+
+/// Switches the terminal back from the alternate screen and out of raw mode.
+fn restore() -> io::Result<()> {
+    execute!(io::stdout(), Show, LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()
+}
+
+/// Runs the editor in the terminal until it is asked to quit.
+fn session(out: &mut impl Write, editor: &mut Editor) -> io::Result<()> {
+    while !editor.quit {
+        editor.width = usize::from(terminal::size()?.0);
+        draw(out, &editor.frame())?;
+        if let Event::Key(event) = event::read()? {
+            if event.kind == KeyEventKind::Press {
+                if let Some(key) = key(event) {
+                    editor.key(key);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Edits a file, either in the terminal or by applying a key script and printing the final screen.
+///
+/// A file that does not exist yet starts out holding a universe, to be replaced.
+pub fn run(args: &[String]) -> Result<(), Fail> {
+    let mut script = None;
+    let mut paths = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--script" => match args.next() {
+                Some(val) => script = Some(keys(val)?),
+                None => return Err(Fail::Usage("option --script expects keys".to_string())),
+            },
+            opt if opt.starts_with("--") => {
+                return Err(Fail::Usage(format!("unknown option: {}", opt)))
+            }
+            _ => paths.push(arg.clone()),
+        }
+    }
+    let [path] = paths.as_slice() else {
+        return Err(Fail::Usage("expected a single file".to_string()));
+    };
+
+    let exp = if std::path::Path::new(path).exists() {
+        Input {
+            path: Some(path.clone()),
+        }
+        .decode()?
+    } else {
+        Exp::Unv(Unv::new())
+    };
+    let mut editor = Editor::new(path.clone(), exp);
+    match script {
+        Some(keys) => {
+            for key in keys {
+                if editor.quit {
+                    break;
+                }
+                editor.key(key);
+            }
+            println!("{}", editor.frame());
+            Ok(())
+        }
+        None => {
+            let io_err = |err| Fail::Io("<terminal>".to_string(), err);
+            let mut out = io::stdout();
+            let screen = Screen::enter(&mut out).map_err(io_err)?;
+            let res = session(&mut out, &mut editor);
+            screen.leave().map_err(io_err)?;
+            res.map_err(io_err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn editor(val: &str) -> Editor {
+        Editor::new(String::new(), Core::new().decode(&val.to_string()).unwrap())
+    }
+
+    fn play(editor: &mut Editor, script: &str) -> Frame {
+        for key in keys(script).unwrap() {
+            editor.key(key);
+        }
+        editor.frame()
+    }
+
+    #[test]
+    fn test_keys() {
+        assert_eq!(
+            keys("a<enter><c-r><lt>λ").unwrap(),
+            vec![
+                Key::Char('a'),
+                Key::Enter,
+                Key::Ctrl('r'),
+                Key::Char('<'),
+                Key::Char('λ')
+            ]
+        );
+        assert!(matches!(keys("<bogus>"), Err(Fail::Usage(_))));
+        assert!(matches!(keys("<enter"), Err(Fail::Usage(_))));
+    }
+
+    #[test]
+    fn test_move() {
+        let mut editor = editor("λa : □ . λx : a . f x");
+        assert_eq!(play(&mut editor, "").focused(), "λa : □ . λx : a . f x");
+        assert_eq!(play(&mut editor, "j").focused(), "□");
+        assert_eq!(play(&mut editor, "l").focused(), "λx : a . f x");
+        assert_eq!(play(&mut editor, "jl<down>").focused(), "f");
+        assert_eq!(play(&mut editor, "<right>").focused(), "x");
+        let frame = play(&mut editor, "j");
+        assert_eq!(frame.focused(), "x"); // no constituents
+        assert_eq!(frame.status, "");
+        assert_eq!(play(&mut editor, "kh").focused(), "a");
+        assert_eq!(
+            play(&mut editor, "kkkkk").focused(),
+            "λa : □ . λx : a . f x"
+        );
+    }
+
+    #[test]
+    fn test_info() {
+        let mut editor = editor("λa : □ . λx : a . x");
+        let frame = play(&mut editor, "");
+        assert_eq!(frame.info, vec!["type: Πa : □ . Πx : a . a"]);
+        let frame = play(&mut editor, "jljl");
+        assert_eq!(frame.focused(), "x");
+        assert_eq!(frame.info, vec!["a : □", "x : a", "type: a"]);
+        let frame = play(&mut editor, "r y<enter>");
+        assert_eq!(
+            frame.info,
+            vec!["a : □", "x : a", "error: undeclared variable: y"]
+        );
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut editor = editor("x");
+        let frame = play(&mut editor, "\\x : a<enter>");
+        assert_eq!(frame.text, "λx : a . x");
+        let frame = play(&mut editor, "\\a : □<enter>");
+        assert_eq!(frame.text, "λa : □ . λx : a . x");
+        assert_eq!(frame.info, vec!["type: Πa : □ . Πx : a . a"]);
+        assert_eq!(frame.status, " [+]");
+        let frame = play(&mut editor, "jljlaa<enter>");
+        assert_eq!(frame.text, "λa : □ . λx : a . x a");
+        assert_eq!(frame.focused(), "x a");
+        let frame = play(&mut editor, "jlrx<enter>");
+        assert_eq!(frame.text, "λa : □ . λx : a . x x");
+        let frame = play(&mut editor, "kkny<enter>");
+        assert_eq!(frame.text, "λa : □ . λy : a . y y");
+        let frame = play(&mut editor, "kpb : □<enter>");
+        assert_eq!(frame.text, "Πb : □ . λa : □ . λy : a . y y");
+        assert_eq!(frame.focused(), frame.text);
+    }
+
+    #[test]
+    fn test_edit_err() {
+        let mut editor = editor("λa : □ . a");
+        let frame = play(&mut editor, "\\a □<enter>");
+        assert_eq!(frame.status, "expected a binding of the form: x : T");
+        assert_eq!(play(&mut editor, "<right>").status, "λ a □");
+        let frame = play(&mut editor, "<bs><bs>: □)<enter>");
+        assert!(
+            frame.status.starts_with("unexpected token"),
+            "{}",
+            frame.status
+        );
+        let frame = play(&mut editor, "<esc>x");
+        assert_eq!(frame.status, "bound variable is used in the body");
+        let frame = play(&mut editor, "jn");
+        assert_eq!(frame.status, "not a binder");
+        assert_eq!(frame.text, "λa : □ . a");
+        play(&mut editor, "q");
+        assert!(editor.quit); // failed edits leave nothing to save
+    }
+
+    #[test]
+    fn test_unwrap() {
+        let mut editor = editor("λa : □ . λx : □ . f a");
+        let frame = play(&mut editor, "jlx");
+        assert_eq!(frame.text, "λa : □ . f a");
+        let frame = play(&mut editor, "x");
+        assert_eq!(frame.text, "λa : □ . f");
+        let frame = play(&mut editor, "kx");
+        assert_eq!(frame.text, "f");
+    }
+
+    #[test]
+    fn test_undo() {
+        let mut editor = editor("f");
+        play(&mut editor, "ax<enter>ay<enter>");
+        assert_eq!(play(&mut editor, "u").text, "f x");
+        assert_eq!(play(&mut editor, "u").text, "f");
+        assert_eq!(play(&mut editor, "u").status, "nothing to undo");
+        assert_eq!(play(&mut editor, "U").text, "f x");
+        assert_eq!(play(&mut editor, "<c-r>").text, "f x y");
+        assert_eq!(play(&mut editor, "<c-r>").status, "nothing to redo");
+        assert_eq!(play(&mut editor, "uaz<enter><c-r>").text, "f x z");
+    }
+
+    #[test]
+    fn test_save() {
+        let dir = std::env::temp_dir().join(format!("fluxo-edit-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("id.fx");
+        let mut editor = Editor::new(path.display().to_string(), Exp::Unv(Unv::new()));
+        let frame = play(&mut editor, "rλa : □ . λx : a . x<enter>w");
+        assert_eq!(frame.status, format!("saved {}", path.display()));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "λa : □ . λx : a . x\n"
+        );
+        play(&mut editor, "af<enter>q");
+        assert!(!editor.quit);
+        play(&mut editor, "uq");
+        assert!(!editor.quit); // undoing is a change too
+        play(&mut editor, "Q");
+        assert!(editor.quit);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,46 @@
+//! The `check` and `eval` commands, which type check and normalize expressions.
+
+use crate::{parse_num, Fail, Input};
+use fluxo::ast::Ctx;
+use fluxo::enc::core::par::Core;
+use fluxo::enc::Codec;
+
+/// Default target width for printed expressions.
+const WIDTH: usize = 80;
+
+/// Type checks each input within an empty context, and prints either its type or, when `eval` is
+/// set, its normal form.
+///
+/// Only well-typed expressions are normalized, which guarantees that normalization terminates.
+pub fn run(args: &[String], eval: bool) -> Result<(), Fail> {
+    let mut width = WIDTH;
+    let mut paths = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--width" => width = parse_num(arg, args.next())?,
+            opt if opt.starts_with("--") => {
+                return Err(Fail::Usage(format!("unknown option: {}", opt)))
+            }
+            _ => paths.push(arg.clone()),
+        }
+    }
+
+    let ctx = Ctx::new();
+    let codec = Core::new().with_width(width);
+    for input in Input::from_args(&paths) {
+        let exp = input.decode()?;
+        let typ = ctx
+            .infer(&exp)
+            .map_err(|err| Fail::Type(input.name(), err))?;
+        if eval {
+            let val = ctx
+                .normalize(&exp)
+                .map_err(|err| Fail::System(input.name(), err))?;
+            println!("{}", codec.encode(&val));
+        } else {
+            println!("{}", codec.encode(&typ));
+        }
+    }
+    Ok(())
+}
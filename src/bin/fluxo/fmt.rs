@@ -0,0 +1,172 @@
+//! The `fmt` command, which reprints source files canonically.
+
+use crate::{parse_num, Fail, Input};
+use fluxo::enc::core::par::Core;
+
+/// Default target width for formatted source files.
+const WIDTH: usize = 80;
+
+/// Number of unchanged lines shown around each change in a diff.
+const CONTEXT: usize = 3;
+
+/// Formats each input, or checks that it is already formatted when `--check` is supplied.
+///
+/// Files are rewritten in place, while the standard input stream is formatted to the standard
+/// output stream. In check mode, nothing is written; instead, a diff is printed for each input
+/// that is not formatted, and the command fails if there are any.
+pub fn run(args: &[String]) -> Result<(), Fail> {
+    let mut check = false;
+    let mut width = WIDTH;
+    let mut paths = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--check" => check = true,
+            "--width" => width = parse_num(arg, args.next())?,
+            opt if opt.starts_with("--") => {
+                return Err(Fail::Usage(format!("unknown option: {}", opt)))
+            }
+            _ => paths.push(arg.clone()),
+        }
+    }
+
+    let codec = Core::new().with_width(width);
+    let mut pass = true;
+    for input in Input::from_args(&paths) {
+        let val = input.read()?;
+        let can = codec
+            .format(&val)
+            .map_err(|err| Fail::Decode(input.name(), err))?;
+        if check {
+            if val != can {
+                print!("{}", diff(&input.name(), &val, &can));
+                pass = false;
+            }
+        } else {
+            match &input.path {
+                Some(path) if val != can => {
+                    std::fs::write(path, &can).map_err(|err| Fail::Io(input.name(), err))?
+                }
+                Some(_) => {} // already formatted
+                None => print!("{}", can),
+            }
+        }
+    }
+    if pass {
+        Ok(())
+    } else {
+        Err(Fail::Check)
+    }
+}
+
+/// Line in a diff.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Line<'a> {
+    /// Line present in both the old and new text.
+    Same(&'a str),
+    /// Line present only in the old text.
+    Del(&'a str),
+    /// Line present only in the new text.
+    Ins(&'a str),
+}
+
+/// Computes a unified diff between the old and new text, using the longest common subsequence of
+/// their lines.
+fn diff(name: &str, old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(Line::Same(old[i]));
+            (i, j) = (i + 1, j + 1);
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(Line::Del(old[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Ins(new[j]));
+            j += 1;
+        }
+    }
+
+    let mut res = format!("--- {}\n+++ {}\n", name, name);
+    let mut pos = 0;
+    while let Some(beg) = (pos..lines.len()).find(|&k| !matches!(lines[k], Line::Same(_))) {
+        // extend the hunk until the changes are separated by more than twice the context
+        let beg = beg.saturating_sub(CONTEXT).max(pos);
+        let mut end = beg;
+        let mut same = 0;
+        for (k, line) in lines.iter().enumerate().skip(beg) {
+            match line {
+                Line::Same(_) if same == 2 * CONTEXT => break,
+                Line::Same(_) => same += 1,
+                _ => {
+                    same = 0;
+                    end = k + 1;
+                }
+            }
+        }
+        let end = (end + CONTEXT).min(lines.len());
+        let hunk = &lines[beg..end];
+        let (olen, nlen) = hunk.iter().fold((0, 0), |(o, n), line| match line {
+            Line::Same(_) => (o + 1, n + 1),
+            Line::Del(_) => (o + 1, n),
+            Line::Ins(_) => (o, n + 1),
+        });
+        let (ostart, nstart) = lines[..beg].iter().fold((1, 1), |(o, n), line| match line {
+            Line::Same(_) => (o + 1, n + 1),
+            Line::Del(_) => (o + 1, n),
+            Line::Ins(_) => (o, n + 1),
+        });
+        res.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            ostart, olen, nstart, nlen
+        ));
+        for line in hunk {
+            match line {
+                Line::Same(val) => res.push_str(&format!(" {}\n", val)),
+                Line::Del(val) => res.push_str(&format!("-{}\n", val)),
+                Line::Ins(val) => res.push_str(&format!("+{}\n", val)),
+            }
+        }
+        pos = end;
+    }
+    res
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+        let new = "a\nb\nx\nd\ne\nf\ng\nh\ni\nj\nk\nl\ny\n";
+        assert_eq!(
+            diff("foo", old, new),
+            "--- foo\n+++ foo\n\
+             @@ -1,6 +1,6 @@\n a\n b\n-c\n+x\n d\n e\n f\n\
+             @@ -10,3 +10,4 @@\n j\n k\n l\n+y\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_same() {
+        assert_eq!(diff("foo", "a\nb\n", "a\nb\n"), "--- foo\n+++ foo\n");
+    }
+}
@@ -0,0 +1,183 @@
+//! Command-line application for working with the core language.
+//!
+//! The application exits with status 0 on success, 1 when a check fails, 2 when it is invoked
+//! incorrectly, 3 when an input contains a syntax error, 4 when an input contains a type error,
+//! and 5 when a system error (including a failure to read or write a file) is encountered.
+
+mod edit;
+mod eval;
+mod fmt;
+mod parse;
+mod repl;
+
+use fluxo::ast::Exp;
+use fluxo::enc::core::par::Core;
+use fluxo::enc::Codec;
+use fluxo::err::{DecodeErr, SystemErr, TypeErr};
+use std::fmt::{Display, Formatter};
+use std::io::{self, Read};
+use std::process::ExitCode;
+
+/// Usage information for the application.
+const USAGE: &str = "\
+usage: fluxo <command> [<args>]
+
+commands:
+    parse [--indices] [<file>...]              print the syntax tree of each expression
+    check [--width <n>] [<file>...]            type check each expression and print its type
+    eval [--width <n>] [<file>...]             type check each expression and print its normal form
+    fmt [--check] [--width <n>] [<file>...]    format source files
+    repl                                       start an interactive session
+    edit [--script <keys>] <file>              edit an expression structurally in the terminal
+
+Each command other than edit reads from stdin if no files are supplied.";
+
+/// Error that terminates the application.
+#[derive(Debug)]
+enum Fail {
+    /// A check performed by a command did not pass.
+    Check,
+    /// The application was invoked with invalid arguments.
+    Usage(String),
+    /// An input could not be decoded.
+    Decode(String, DecodeErr),
+    /// An input is not well-typed.
+    Type(String, TypeErr),
+    /// An input could not be processed due to a limitation of the system.
+    System(String, SystemErr),
+    /// An input or output could not be read or written.
+    Io(String, io::Error),
+}
+
+impl Fail {
+    /// Returns the exit status associated with this error.
+    fn code(&self) -> u8 {
+        match self {
+            Fail::Check => 1,
+            Fail::Usage(_) => 2,
+            Fail::Decode(_, DecodeErr::SystemErr(_)) => 5,
+            Fail::Decode(..) => 3,
+            Fail::Type(_, TypeErr::SystemErr(_)) => 5,
+            Fail::Type(..) => 4,
+            Fail::System(..) => 5,
+            Fail::Io(..) => 5,
+        }
+    }
+}
+
+impl Display for Fail {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Fail::Check => write!(f, "check failed"),
+            Fail::Usage(msg) => write!(f, "{}\n\n{}", msg, USAGE),
+            Fail::Decode(name, err) => write!(f, "{}: {}", name, err),
+            Fail::Type(name, err) => write!(f, "{}: {}", name, err),
+            Fail::System(name, err) => write!(f, "{}: {}", name, err),
+            Fail::Io(name, err) => write!(f, "{}: {}", name, err),
+        }
+    }
+}
+
+/// Input to a command, which is either a file or the standard input stream.
+struct Input {
+    /// Path of the file, or none for the standard input stream.
+    path: Option<String>,
+}
+
+impl Input {
+    /// Returns the inputs named by the supplied arguments, or the standard input stream if none.
+    fn from_args(paths: &[String]) -> Vec<Self> {
+        if paths.is_empty() {
+            vec![Self { path: None }]
+        } else {
+            paths
+                .iter()
+                .map(|path| Self {
+                    path: Some(path.clone()).filter(|path| path != "-"),
+                })
+                .collect()
+        }
+    }
+
+    /// Returns the name of the input, for use in messages.
+    fn name(&self) -> String {
+        self.path.clone().unwrap_or_else(|| "<stdin>".to_string())
+    }
+
+    /// Reads the input in full.
+    fn read(&self) -> Result<String, Fail> {
+        let res = match &self.path {
+            Some(path) => std::fs::read_to_string(path),
+            None => {
+                let mut val = String::new();
+                io::stdin().read_to_string(&mut val).map(|_| val)
+            }
+        };
+        res.map_err(|err| Fail::Io(self.name(), err))
+    }
+
+    /// Reads the input in full, and decodes it to an [expression][Exp].
+    fn decode(&self) -> Result<Exp, Fail> {
+        let val = self.read()?;
+        Core::new()
+            .decode(&val)
+            .map_err(|err| Fail::Decode(self.name(), err))
+    }
+}
+
+/// Parses the value of a numeric option.
+fn parse_num(opt: &str, val: Option<&String>) -> Result<usize, Fail> {
+    val.and_then(|val| val.parse().ok())
+        .ok_or_else(|| Fail::Usage(format!("option {} expects a number", opt)))
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let res = match args.split_first() {
+        Some((cmd, args)) => match cmd.as_str() {
+            "parse" => parse::run(args),
+            "check" => eval::run(args, false),
+            "eval" => eval::run(args, true),
+            "fmt" => fmt::run(args),
+            "repl" => repl::run(args),
+            "edit" => edit::run(args),
+            "help" | "--help" | "-h" => {
+                println!("{}", USAGE);
+                Ok(())
+            }
+            _ => Err(Fail::Usage(format!("unknown command: {}", cmd))),
+        },
+        None => Err(Fail::Usage("no command supplied".to_string())),
+    };
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Fail::Check) => ExitCode::from(Fail::Check.code()),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::from(err.code())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use fluxo::ast::Sym;
+
+    #[test]
+    fn test_code() {
+        let name = || "foo".to_string();
+        let sys = || SystemErr::MaxLimitIdx(u64::MAX);
+        assert_eq!(Fail::Check.code(), 1);
+        assert_eq!(Fail::Usage(name()).code(), 2);
+        assert_eq!(Fail::Decode(name(), DecodeErr::InvalidToken(0)).code(), 3);
+        assert_eq!(Fail::Decode(name(), DecodeErr::SystemErr(sys())).code(), 5);
+        assert_eq!(
+            Fail::Type(name(), TypeErr::Undeclared(Sym::new("foo"))).code(),
+            4
+        );
+        assert_eq!(Fail::Type(name(), TypeErr::SystemErr(sys())).code(), 5);
+        assert_eq!(Fail::System(name(), sys()).code(), 5);
+    }
+}
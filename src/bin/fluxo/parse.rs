@@ -0,0 +1,33 @@
+//! The `parse` command, which prints the syntax tree of expressions.
+
+use crate::{Fail, Input};
+use fluxo::enc::core::par::Core;
+use fluxo::enc::Codec;
+
+/// Decodes each input, and prints its syntax tree.
+///
+/// When `--indices` is supplied, the expression is printed in the core language instead, with
+/// bound variables shown as De Bruijn indices.
+pub fn run(args: &[String]) -> Result<(), Fail> {
+    let mut indices = false;
+    let mut paths = vec![];
+    for arg in args {
+        match arg.as_str() {
+            "--indices" => indices = true,
+            opt if opt.starts_with("--") => {
+                return Err(Fail::Usage(format!("unknown option: {}", opt)))
+            }
+            _ => paths.push(arg.clone()),
+        }
+    }
+
+    for input in Input::from_args(&paths) {
+        let exp = input.decode()?;
+        if indices {
+            println!("{}", Core::with_show_indices(true).encode(&exp));
+        } else {
+            println!("{:#?}", exp);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,341 @@
+//! The `repl` command, which evaluates expressions interactively.
+
+use crate::{Fail, Input};
+use fluxo::ast::{Ctx, Exp, Sym};
+use fluxo::enc::core::par::Core;
+use fluxo::enc::Codec;
+use fluxo::err::{DecodeErr, TypeErr};
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Target width for printed expressions.
+const WIDTH: usize = 80;
+
+/// Name of the file that holds the history of the session, within the home directory.
+const HISTORY: &str = ".fluxo_history";
+
+/// Help text for the commands available in a session.
+const HELP: &str = "\
+<exp>                   type check an expression and print its normal form
+:type <exp>             type check an expression and print its type
+:normalize <exp>        type check an expression and print its normal form
+:def <name> <exp>       type check an expression and define a variable with its value
+:decl <name> <exp>      declare a variable with a type, without a value
+:load <file>            define a variable named after a file with the expression it contains
+:indices                toggle showing bound variables as De Bruijn indices
+:help                   print this help text
+:quit                   end the session
+
+Input that ends in the middle of an expression continues on the next line.";
+
+/// Reply to an input within a session.
+#[derive(Debug, Eq, PartialEq)]
+enum Reply {
+    /// Text to be printed.
+    Out(String),
+    /// Input is incomplete, and continues on the next line.
+    More,
+    /// Session has ended.
+    Quit,
+}
+
+/// State of an interactive session.
+struct Session {
+    /// Context holding the variables declared and defined during the session.
+    ctx: Ctx,
+    /// Show bound variables as De Bruijn indices rather than their original symbols.
+    show_indices: bool,
+}
+
+impl Session {
+    /// Create a new session, with an empty context.
+    fn new() -> Self {
+        Self {
+            ctx: Ctx::new(),
+            show_indices: false,
+        }
+    }
+
+    /// Returns the codec used to decode inputs and print results.
+    fn codec(&self) -> Core {
+        Core::with_show_indices(self.show_indices).with_width(WIDTH)
+    }
+
+    /// Handles an input, which is either a command or an expression.
+    fn handle(&mut self, input: &str) -> Result<Reply, Fail> {
+        let input = input.trim();
+        let (cmd, arg) = match input.strip_prefix(':') {
+            Some(cmd) => cmd.split_once(char::is_whitespace).unwrap_or((cmd, "")),
+            None if input.is_empty() => return Ok(Reply::Out(String::new())),
+            None => ("normalize", input),
+        };
+        self.run(cmd, arg.trim())
+    }
+
+    /// Runs a command with the supplied argument.
+    fn run(&mut self, cmd: &str, arg: &str) -> Result<Reply, Fail> {
+        match cmd {
+            "type" | "t" => {
+                let Some(exp) = self.exp(arg)? else {
+                    return Ok(Reply::More);
+                };
+                let typ = self.infer(&exp)?;
+                Ok(Reply::Out(self.codec().encode(&typ)))
+            }
+            "normalize" | "n" => {
+                let Some(exp) = self.exp(arg)? else {
+                    return Ok(Reply::More);
+                };
+                self.infer(&exp)?;
+                Ok(Reply::Out(self.codec().encode(&self.normalize(&exp)?)))
+            }
+            "def" | "decl" => {
+                let (name, val) = arg.split_once(char::is_whitespace).unwrap_or((arg, ""));
+                let sym = self.sym(name)?;
+                let Some(exp) = self.exp(val)? else {
+                    return Ok(Reply::More);
+                };
+                let typ = self.infer(&exp)?;
+                let res = if cmd == "def" {
+                    self.ctx.define(sym.clone(), typ.clone(), exp);
+                    typ
+                } else {
+                    self.sort(&exp, typ)?; // declared type must itself be a type
+                    self.ctx.declare(sym.clone(), exp.clone());
+                    exp
+                };
+                Ok(Reply::Out(format!(
+                    "{} : {}",
+                    sym,
+                    self.codec().encode(&res)
+                )))
+            }
+            "load" | "l" => self.load(arg),
+            "indices" => {
+                self.show_indices = !self.show_indices;
+                Ok(Reply::Out(format!(
+                    "showing indices: {}",
+                    self.show_indices
+                )))
+            }
+            "help" | "h" | "?" => Ok(Reply::Out(HELP.to_string())),
+            "quit" | "q" => Ok(Reply::Quit),
+            _ => Err(Fail::Usage(format!("unknown command: :{}", cmd))),
+        }
+    }
+
+    /// Defines a variable named after the stem of a file, with the expression it contains.
+    fn load(&mut self, path: &str) -> Result<Reply, Fail> {
+        let input = Input {
+            path: Some(path.to_string()),
+        };
+        let name = Path::new(path)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        let sym = self.sym(name)?;
+        let exp = input.decode()?;
+        let typ = self
+            .ctx
+            .infer(&exp)
+            .map_err(|err| Fail::Type(input.name(), err))?;
+        let res = format!("{} : {}", sym, self.codec().encode(&typ));
+        self.ctx.define(sym, typ, exp);
+        Ok(Reply::Out(res))
+    }
+
+    /// Checks that a name is a valid symbol in the core language.
+    fn sym(&self, name: &str) -> Result<Sym, Fail> {
+        if !Sym::is_valid(name) {
+            return Err(Fail::Usage(format!("invalid name: {}", name)));
+        }
+        Ok(Sym::new(name))
+    }
+
+    /// Decodes an expression entered within the session, or returns none if the expression is
+    /// incomplete, and continues on the next line.
+    fn exp(&self, val: &str) -> Result<Option<Exp>, Fail> {
+        match self.decode(val) {
+            Ok(exp) => Ok(Some(exp)),
+            Err(Fail::Decode(_, DecodeErr::EndOfStream(..))) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Decodes an input to an [expression][Exp].
+    fn decode(&self, val: &str) -> Result<Exp, Fail> {
+        self.codec()
+            .decode(&val.to_string())
+            .map_err(|err| Fail::Decode("<input>".to_string(), err))
+    }
+
+    /// Infers the type of an expression within the context of the session.
+    fn infer(&self, exp: &Exp) -> Result<Exp, Fail> {
+        self.ctx
+            .infer(exp)
+            .map_err(|err| Fail::Type("<input>".to_string(), err))
+    }
+
+    /// Checks that the type of an expression is a universe, so that the expression is a type.
+    fn sort(&self, exp: &Exp, typ: Exp) -> Result<(), Fail> {
+        match self.normalize(&typ)? {
+            Exp::Unv(_) => Ok(()),
+            typ => Err(Fail::Type(
+                "<input>".to_string(),
+                TypeErr::NotType(Box::new(exp.clone()), Box::new(typ)),
+            )),
+        }
+    }
+
+    /// Normalizes an expression within the context of the session.
+    fn normalize(&self, exp: &Exp) -> Result<Exp, Fail> {
+        self.ctx
+            .normalize(exp)
+            .map_err(|err| Fail::System("<input>".to_string(), err))
+    }
+}
+
+/// Returns the path of the file that holds the history of the session.
+fn history() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| Path::new(&home).join(HISTORY))
+}
+
+/// Runs an interactive session until it is ended, or the input stream is closed.
+pub fn run(args: &[String]) -> Result<(), Fail> {
+    if let Some(arg) = args.first() {
+        return Err(Fail::Usage(format!("unexpected argument: {}", arg)));
+    }
+    let io_err = |err: ReadlineError| Fail::Io("<terminal>".to_string(), io::Error::other(err));
+    let mut editor = DefaultEditor::new().map_err(io_err)?;
+    let history = history();
+    if let Some(path) = &history {
+        let _ = editor.load_history(path); // the first session has no history yet
+    }
+
+    let mut session = Session::new();
+    let mut buf = String::new();
+    loop {
+        let prompt = if buf.is_empty() { "λ> " } else { ".. " };
+        let line = match editor.readline(prompt) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => {
+                buf.clear(); // abandon the current input
+                continue;
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(io_err(err)),
+        };
+        if !buf.is_empty() && line.trim().is_empty() {
+            eprintln!("error: incomplete input discarded");
+            buf.clear();
+            continue;
+        }
+        buf.push_str(&line);
+        buf.push('\n');
+        match session.handle(&buf) {
+            Ok(Reply::More) => continue,
+            Ok(Reply::Out(out)) => {
+                if !out.is_empty() {
+                    println!("{}", out);
+                }
+            }
+            Ok(Reply::Quit) => break,
+            Err(Fail::Usage(msg)) => eprintln!("error: {}, see :help", msg),
+            Err(err) => eprintln!("error: {}", err),
+        }
+        let _ = editor.add_history_entry(buf.trim_end());
+        buf.clear();
+    }
+    if let Some(path) = &history {
+        editor.save_history(path).map_err(io_err)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn out(session: &mut Session, input: &str) -> String {
+        match session.handle(input) {
+            Ok(Reply::Out(out)) => out,
+            res => panic!("unexpected reply for {}: {:?}", input, res),
+        }
+    }
+
+    #[test]
+    fn test_session() {
+        let mut session = Session::new();
+        assert_eq!(out(&mut session, ":decl b □"), "b : □");
+        assert_eq!(
+            out(&mut session, ":def id λa : □ . λx : a . x"),
+            "id : Πa : □ . Πx : a . a"
+        );
+        assert_eq!(out(&mut session, ":type id b"), "Πx : b . b");
+        assert_eq!(out(&mut session, "id b"), "λx : b . x");
+        assert_eq!(out(&mut session, ":indices"), "showing indices: true");
+        assert_eq!(out(&mut session, ":normalize id"), "λa : □ . λx : 0 . 0");
+        assert_eq!(session.handle(":quit").unwrap(), Reply::Quit);
+    }
+
+    #[test]
+    fn test_session_more() {
+        let mut session = Session::new();
+        assert_eq!(session.handle("λa : □ .").unwrap(), Reply::More);
+        assert_eq!(session.handle(":type λa : □ .\n").unwrap(), Reply::More);
+        assert_eq!(out(&mut session, "λa : □ .\n  a"), "λa : □ . a");
+        assert_eq!(session.handle(":def id λa : □ .").unwrap(), Reply::More);
+        assert!(matches!(session.handle(":def"), Err(Fail::Usage(_))));
+        assert!(matches!(session.handle(":decl"), Err(Fail::Usage(_))));
+        assert!(matches!(
+            session.handle(":def λa : □ ."),
+            Err(Fail::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn test_session_err() {
+        let mut session = Session::new();
+        assert!(matches!(session.handle("foo"), Err(Fail::Type(..))));
+        assert!(matches!(session.handle("λ)"), Err(Fail::Decode(..))));
+        assert!(matches!(session.handle(":def λ □"), Err(Fail::Usage(_))));
+        assert!(matches!(session.handle(":def 0 □"), Err(Fail::Usage(_))));
+        assert!(matches!(session.handle(":def (x) □"), Err(Fail::Usage(_))));
+        assert!(matches!(
+            session.handle(":decl y λa : □ . a"),
+            Err(Fail::Type(..))
+        ));
+        assert!(matches!(session.handle(":bogus"), Err(Fail::Usage(_))));
+        assert!(matches!(
+            session.handle(":load /nonexistent/foo"),
+            Err(Fail::Io(..))
+        ));
+    }
+
+    #[test]
+    fn test_session_load() {
+        let dir = std::env::temp_dir().join(format!("fluxo-repl-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("twice.fx");
+        std::fs::write(&path, "λa : □ . λf : Πx : a . a . λx : a . f (f x)\n").unwrap();
+        let part = dir.join("part.fx");
+        std::fs::write(&part, "λa : □ .\n").unwrap();
+        let mut session = Session::new();
+        assert_eq!(
+            out(&mut session, &format!(":load {}", path.display())),
+            "twice : Πa : □ . Πf : Πx : a . a . Πx : a . a"
+        );
+        assert_eq!(
+            out(&mut session, ":type twice"),
+            "Πa : □ . Πf : Πx : a . a . Πx : a . a"
+        );
+        assert!(matches!(
+            session.handle(&format!(":load {}", part.display())),
+            Err(Fail::Decode(_, DecodeErr::EndOfStream(..)))
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
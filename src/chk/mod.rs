@@ -0,0 +1,12 @@
+//! Type checking and normalization of [expressions][crate::ast::Exp], within a [typing
+//! context][crate::ast::Ctx].
+//!
+//! The type system is a dependently typed λ-calculus with a predicative, cumulative hierarchy of
+//! [universes][crate::ast::Unv]: each universe belongs to the universe one level above it, and the
+//! [Π-types][crate::ast::Prd] and [Σ-types][crate::ast::Sum] formed from types in any two
+//! universes belong to the higher of the two. Types are compared after normalization, up to
+//! α-equivalence. Since the calculus is strongly normalizing, every well-typed expression has a
+//! normal form.
+
+mod nrm;
+mod typ;
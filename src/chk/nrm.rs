@@ -0,0 +1,149 @@
+//! Normalization of expressions, by β-reduction and unfolding of definitions.
+
+use crate::ast::{Abs, App, Ctx, Exp, Prd, Sum, Var};
+use crate::err::SystemErr;
+
+impl Ctx {
+    /// Reduces an expression to weak head normal form.
+    ///
+    /// The head of the expression is reduced until it is neither an application of a
+    /// λ-abstraction, nor a free variable that is defined within this context. Nothing beneath
+    /// the head of the expression is reduced.
+    pub fn whnf(&self, exp: &Exp) -> Result<Exp, SystemErr> {
+        self.reduce(exp.clone())
+    }
+
+    /// Reduces an expression to normal form.
+    ///
+    /// The expression is reduced to [weak head normal form][Ctx::whnf], and then each of its
+    /// constituents is normalized in turn. This terminates for every well-typed expression, but
+    /// may not terminate otherwise.
+    pub fn normalize(&self, exp: &Exp) -> Result<Exp, SystemErr> {
+        let mut res = self.reduce(exp.clone())?;
+        let mut stack = vec![(&mut res, true)]; // along with whether it is in weak head normal form
+        while let Some((exp, whnf)) = stack.pop() {
+            if !whnf {
+                *exp = self.reduce(std::mem::take(exp))?;
+            }
+            match exp {
+                Exp::App(App { fst, snd }) => {
+                    stack.push((snd, false));
+                    stack.push((fst, true)); // head of the application is already reduced
+                }
+                Exp::Abs(Abs { typ, exp, .. })
+                | Exp::Prd(Prd { typ, exp, .. })
+                | Exp::Sum(Sum { typ, exp, .. }) => {
+                    stack.push((exp, false));
+                    stack.push((typ, false));
+                }
+                Exp::Var(_) | Exp::Unv(_) => {} // variables and constants are already normal
+            }
+        }
+        Ok(res)
+    }
+
+    /// Reduces an expression to weak head normal form, taking ownership of it.
+    ///
+    /// The applications along the spine of the expression are unwound onto a stack of arguments,
+    /// which are substituted into the λ-abstractions that reach the head, and the remaining
+    /// arguments are applied to the head once it can be reduced no further.
+    fn reduce(&self, mut head: Exp) -> Result<Exp, SystemErr> {
+        let mut args = vec![]; // innermost argument last
+        loop {
+            match &mut head {
+                Exp::App(app) => {
+                    args.push(std::mem::take(&mut *app.snd));
+                    head = std::mem::take(&mut *app.fst);
+                }
+                Exp::Abs(abs) if !args.is_empty() => {
+                    let arg = args.pop().expect("checked to be non-empty");
+                    abs.exp.instantiate(&arg)?; // β-reduction
+                    head = std::mem::take(&mut *abs.exp);
+                }
+                Exp::Var(Var::Sym(sym)) => match self.lookup_sym(sym).and_then(|d| d.val.clone()) {
+                    Some(val) => head = val, // δ-reduction
+                    None => break,
+                },
+                _ => break,
+            }
+        }
+        Ok(args
+            .into_iter()
+            .rev()
+            .fold(head, |fst, snd| Exp::App(App::new(fst, snd))))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::ast::{Abs, App, Ctx, Exp, Sym, Unv, Var};
+    use crate::enc::core::par::{decode, Core};
+    use crate::enc::Codec;
+
+    fn normalize(ctx: &Ctx, val: &str) -> String {
+        Core::new().encode(&ctx.normalize(&decode(val)).unwrap())
+    }
+
+    #[test]
+    fn test_normalize() {
+        let ctx = Ctx::new();
+        assert_eq!(normalize(&ctx, "(λa : □ . λx : a . x) b y"), "y");
+        assert_eq!(
+            normalize(&ctx, "λy : □ . (λx : □ . λy : □ . x) y"),
+            "λy : □ . λy1 : □ . y"
+        );
+        assert_eq!(normalize(&ctx, "f ((λx : □ . x x) g)"), "f (g g)");
+        assert_eq!(normalize(&ctx, "Πx : (λa : □ . a) b . x"), "Πx : b . x");
+    }
+
+    #[test]
+    fn test_whnf() {
+        let ctx = Ctx::new();
+        let exp = decode("(λx : □ . f ((λy : □ . y) x)) z");
+        assert_eq!(
+            Core::new().encode(&ctx.whnf(&exp).unwrap()),
+            "f ((λy : □ . y) z)"
+        );
+    }
+
+    #[test]
+    fn test_normalize_definition() {
+        let mut ctx = Ctx::new();
+        let id = decode("λa : □ . λx : a . x");
+        let typ = decode("Πa : □ . Πx : a . a");
+        ctx.define(Sym::new("id"), typ, id);
+        ctx.declare(Sym::new("b"), Exp::Unv(Unv::new()));
+        assert_eq!(normalize(&ctx, "id b"), "λx : b . x");
+        assert_eq!(normalize(&ctx, "id"), "λa : □ . λx : a . x");
+    }
+
+    #[test]
+    fn test_normalize_deep() {
+        let depth = 100_000;
+        let mut ctx = Ctx::new();
+        let id = decode("λa : □ . λx : a . x");
+        let typ = decode("Πa : □ . Πx : a . a");
+        ctx.define(Sym::new("id"), typ, id);
+        let var = |val: &str| Exp::Var(Var::Sym(Sym::new(val)));
+        let id = |exp| Exp::App(App::new(Exp::App(App::new(var("id"), var("b"))), exp));
+        let spine = (0..depth).fold(var("f"), |exp, _| Exp::App(App::new(exp, id(var("y")))));
+        let res = (0..depth).fold(var("f"), |exp, _| Exp::App(App::new(exp, var("y"))));
+        assert_eq!(ctx.normalize(&spine), Ok(res));
+
+        let nested = (0..depth).fold(id(var("y")), |exp, _| Exp::App(App::new(var("f"), exp)));
+        let res = (0..depth).fold(var("y"), |exp, _| Exp::App(App::new(var("f"), exp)));
+        assert_eq!(ctx.normalize(&nested), Ok(res));
+
+        let binder = |exp| {
+            Exp::Abs(Abs {
+                sym: Sym::new("x"),
+                typ: Box::new(Exp::Unv(Unv::new())),
+                exp: Box::new(exp),
+            })
+        };
+        let binders = (0..depth).fold(id(var("y")), |exp, _| binder(exp));
+        let res = (0..depth).fold(var("y"), |exp, _| binder(exp));
+        assert_eq!(ctx.normalize(&binders), Ok(res));
+    }
+}
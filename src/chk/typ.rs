@@ -0,0 +1,279 @@
+//! Type inference and checking.
+
+use crate::ast::{Abs, App, Binder, Ctx, Exp, Prd, Sum, Unv, Var};
+use crate::err::{SystemErr, TypeErr};
+
+impl Ctx {
+    /// Infers the type of an expression.
+    pub fn infer(&self, exp: &Exp) -> Result<Exp, TypeErr> {
+        self.clone().infer_exp(exp)
+    }
+
+    /// Checks that an expression has the supplied type.
+    ///
+    /// The inferred type of the expression is accepted if it is convertible to the supplied type,
+    /// or if it is a universe (or a Π-type into a universe) contained by the supplied type.
+    pub fn check(&self, exp: &Exp, typ: &Exp) -> Result<(), TypeErr> {
+        let res = self.infer(exp)?;
+        if self.is_sub(&res, typ)? {
+            Ok(())
+        } else {
+            Err(TypeErr::Mismatch(
+                Box::new(exp.clone()),
+                Box::new(typ.clone()),
+                Box::new(res),
+            ))
+        }
+    }
+
+    /// Infers the type of an expression, declaring bound variables while descending into binders.
+    ///
+    /// The steps that remain once the type of a constituent has been inferred are kept on a
+    /// stack, and each is resumed with the type inferred last.
+    fn infer_exp(&mut self, exp: &Exp) -> Result<Exp, TypeErr> {
+        let mut stack = vec![Step::Infer(exp)];
+        let mut res = Exp::default();
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Infer(Exp::Var(Var::Sym(sym))) => {
+                    res = self
+                        .lookup_sym(sym)
+                        .map(|decl| decl.typ.clone())
+                        .ok_or_else(|| TypeErr::Undeclared(sym.clone()))?;
+                }
+                Step::Infer(Exp::Var(Var::Idx(idx))) => {
+                    res = self
+                        .lookup_idx(idx)
+                        .map(|decl| decl.typ.clone())
+                        .ok_or_else(|| TypeErr::Unbound(idx.clone()))?;
+                    res.lift(idx.inc()?.val)?; // refer to the same binders from the current scope
+                }
+                Step::Infer(Exp::App(app)) => {
+                    stack.push(Step::Fst(app));
+                    stack.push(Step::Infer(&app.fst));
+                }
+                Step::Infer(exp @ Exp::Abs(Abs { typ, .. }))
+                | Step::Infer(exp @ Exp::Prd(Prd { typ, .. }))
+                | Step::Infer(exp @ Exp::Sum(Sum { typ, .. })) => {
+                    stack.push(Step::Typ(exp));
+                    stack.push(Step::Infer(typ));
+                }
+                Step::Infer(Exp::Unv(unv)) => res = Exp::Unv(unv.inc()?),
+                Step::Fst(app) => match &mut self.whnf(&res)? {
+                    Exp::Prd(prd) => {
+                        let prd = Prd {
+                            sym: prd.sym.clone(),
+                            typ: std::mem::take(&mut prd.typ),
+                            exp: std::mem::take(&mut prd.exp),
+                        };
+                        stack.push(Step::Snd(app, prd));
+                        stack.push(Step::Infer(&app.snd));
+                    }
+                    typ => {
+                        let typ = Box::new(std::mem::take(typ));
+                        return Err(TypeErr::NotFunction(app.fst.clone(), typ));
+                    }
+                },
+                Step::Snd(app, mut prd) => {
+                    if !self.is_sub(&res, &prd.typ)? {
+                        let typ = Box::new(std::mem::take(&mut *prd.typ));
+                        let arg = Box::new(std::mem::take(&mut res));
+                        return Err(TypeErr::Mismatch(app.snd.clone(), typ, arg));
+                    }
+                    prd.exp.instantiate(&app.snd)?; // type of the result depends on the argument
+                    res = std::mem::take(&mut *prd.exp);
+                }
+                Step::Typ(exp) => {
+                    let obj = binder(exp);
+                    let lhs = self.sort(obj.typ(), &res)?;
+                    self.bind(obj.sym().clone(), obj.typ().clone());
+                    stack.push(Step::Exp(exp, lhs));
+                    stack.push(Step::Infer(obj.exp()));
+                }
+                Step::Exp(Exp::Abs(abs), _) => {
+                    self.unbind();
+                    res = Exp::Prd(Prd {
+                        sym: abs.sym.clone(),
+                        typ: abs.typ.clone(),
+                        exp: Box::new(std::mem::take(&mut res)),
+                    });
+                }
+                Step::Exp(exp, lhs) => {
+                    let rhs = self.sort(binder(exp).exp(), &res)?;
+                    self.unbind();
+                    res = Exp::Unv(Unv::max(lhs, rhs));
+                }
+            }
+        }
+        Ok(res)
+    }
+
+    /// Checks that the type of an expression is a universe, and returns that universe.
+    fn sort(&self, exp: &Exp, typ: &Exp) -> Result<Unv, TypeErr> {
+        match self.whnf(typ)? {
+            Exp::Unv(unv) => Ok(unv),
+            typ => Err(TypeErr::NotType(Box::new(exp.clone()), Box::new(typ))),
+        }
+    }
+
+    /// Checks whether the first type is contained by the second, once both are normalized.
+    fn is_sub(&self, lhs: &Exp, rhs: &Exp) -> Result<bool, SystemErr> {
+        Ok(is_sub(&self.normalize(lhs)?, &self.normalize(rhs)?))
+    }
+}
+
+/// Checks whether the first normalized type is contained by the second.
+///
+/// Universes are cumulative, so a universe is contained by any universe at a higher level, and
+/// this extends to the result types of Π-types with α-equivalent parameter types.
+fn is_sub(mut lhs: &Exp, mut rhs: &Exp) -> bool {
+    loop {
+        match (lhs, rhs) {
+            (Exp::Unv(lhs), Exp::Unv(rhs)) => return lhs <= rhs,
+            (Exp::Prd(lprd), Exp::Prd(rprd)) if lprd.typ.alpha_eq(&rprd.typ) => {
+                (lhs, rhs) = (&lprd.exp, &rprd.exp);
+            }
+            (Exp::Prd(_), Exp::Prd(_)) => return false,
+            _ => return lhs.alpha_eq(rhs),
+        }
+    }
+}
+
+/// Step in the [inference][Ctx::infer] of the type of an expression.
+enum Step<'a> {
+    /// Infer the type of an expression.
+    Infer(&'a Exp),
+    /// Check that the inferred type of the function of an application is a Π-type.
+    Fst(&'a App),
+    /// Check that the inferred type of the argument of an application matches the type of the
+    /// parameter of the function.
+    Snd(&'a App, Prd),
+    /// Check that the inferred type of the type of a binder is a universe, and bind it.
+    Typ(&'a Exp),
+    /// Unbind a binder once the type of its body is inferred, along with the universe that the
+    /// type of the binder belongs to.
+    Exp(&'a Exp, Unv),
+}
+
+/// Returns the binder of an expression that is known to be one.
+fn binder(exp: &Exp) -> &dyn Binder {
+    match exp {
+        Exp::Abs(abs) => abs,
+        Exp::Prd(prd) => prd,
+        Exp::Sum(sum) => sum,
+        Exp::Var(_) | Exp::App(_) | Exp::Unv(_) => unreachable!("not a binder"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::ast::{Idx, Sym};
+    use crate::enc::core::par::{decode, Core};
+    use crate::enc::Codec;
+
+    fn infer(ctx: &Ctx, val: &str) -> Result<String, TypeErr> {
+        ctx.infer(&decode(val)).map(|typ| Core::new().encode(&typ))
+    }
+
+    #[test]
+    fn test_infer() {
+        let mut ctx = Ctx::new();
+        ctx.declare(Sym::new("b"), Exp::Unv(Unv::new()));
+        ctx.declare(Sym::new("y"), Exp::Var(Var::Sym(Sym::new("b"))));
+        assert_eq!(
+            infer(&ctx, "λa : □ . λx : a . x"),
+            Ok("Πa : □ . Πx : a . a".to_string())
+        );
+        assert_eq!(
+            infer(&ctx, "(λa : □ . λx : a . x) b"),
+            Ok("Πx : b . b".to_string())
+        );
+        assert_eq!(
+            infer(&ctx, "(λa : □ . λx : a . x) b y"),
+            Ok("b".to_string())
+        );
+        assert_eq!(infer(&ctx, "Πa : □ . Σx : a . a"), Ok("□₁".to_string()));
+        assert_eq!(
+            infer(&ctx, "λf : Πx : b . b . λx : b . f (f x)"),
+            Ok("Πf : Πx : b . b . Πx : b . b".to_string())
+        );
+    }
+
+    #[test]
+    fn test_infer_unv() {
+        let ctx = Ctx::new();
+        let unv = |level| Exp::Unv(Unv { level });
+        assert_eq!(ctx.infer(&decode("□")), Ok(unv(1)));
+        assert_eq!(ctx.infer(&decode("Πa : □ . a")), Ok(unv(1)));
+        assert_eq!(ctx.infer(&decode("λa : □ . a")), Ok(decode("Πa : □ . □")));
+        assert!(ctx.check(&decode("□"), &unv(2)).is_ok()); // cumulative
+        assert!(ctx.check(&unv(2), &decode("□")).is_err());
+
+        assert_eq!(infer(&ctx, "□"), Ok("□₁".to_string())); // levels are printed
+        assert_eq!(infer(&ctx, "λa : □₁ . a"), Ok("Πa : □₁ . □₁".to_string()));
+        let err = ctx.check(&decode("□₁"), &decode("□₁")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "type mismatch for: □₁, expected: □₁, found: □₂"
+        );
+    }
+
+    #[test]
+    fn test_infer_err() {
+        let mut ctx = Ctx::new();
+        ctx.declare(Sym::new("b"), Exp::Unv(Unv::new()));
+        ctx.declare(Sym::new("y"), Exp::Var(Var::Sym(Sym::new("b"))));
+        assert_eq!(infer(&ctx, "z"), Err(TypeErr::Undeclared(Sym::new("z"))));
+        assert!(matches!(infer(&ctx, "y b"), Err(TypeErr::NotFunction(..))));
+        assert!(matches!(
+            infer(&ctx, "λx : y . x"),
+            Err(TypeErr::NotType(..))
+        ));
+        assert_eq!(
+            infer(&ctx, "(λx : □ . x) y"),
+            Err(TypeErr::Mismatch(
+                Box::new(decode("y")),
+                Box::new(decode("□")),
+                Box::new(decode("b"))
+            ))
+        );
+        assert!(matches!(
+            infer(&ctx, "(λa : □ . a) □"),
+            Err(TypeErr::Mismatch(..))
+        )); // predicative
+    }
+
+    #[test]
+    fn test_infer_deep() {
+        let depth = 100_000;
+        let mut ctx = Ctx::new();
+        ctx.declare(Sym::new("b"), Exp::Unv(Unv::new()));
+        ctx.declare(Sym::new("y"), Exp::Var(Var::Sym(Sym::new("b"))));
+        ctx.declare(Sym::new("id"), decode("Πa : □ . Πx : a . a"));
+        let unv = || Box::new(Exp::Unv(Unv::new()));
+        let sym = || Sym::new("x");
+        let var = |val: &str| Exp::Var(Var::Sym(Sym::new(val)));
+
+        let exp = (0..depth).fold(var("y"), |exp, _| {
+            let (sym, typ, exp) = (sym(), unv(), Box::new(exp));
+            Exp::Abs(Abs { sym, typ, exp })
+        });
+        let res = (0..depth).fold(var("b"), |exp, _| {
+            let (sym, typ, exp) = (sym(), unv(), Box::new(exp));
+            Exp::Prd(Prd { sym, typ, exp })
+        });
+        assert_eq!(ctx.infer(&exp), Ok(res));
+
+        let exp = (0..depth).fold(Exp::Var(Var::Idx(Idx::new(&sym()))), |exp, _| {
+            let (sym, typ, exp) = (sym(), unv(), Box::new(exp));
+            Exp::Sum(Sum { sym, typ, exp })
+        });
+        assert_eq!(ctx.infer(&exp), Ok(Exp::Unv(Unv { level: 1 })));
+
+        let id = || Exp::App(App::new(var("id"), var("b")));
+        let exp = (0..depth).fold(var("y"), |exp, _| Exp::App(App::new(id(), exp)));
+        assert_eq!(ctx.infer(&exp), Ok(var("b")));
+    }
+}
@@ -0,0 +1,439 @@
+//! Compact binary encoding of the Abstract Syntax Tree (AST), for caching expressions on disk.
+//!
+//! An encoded expression starts with the [magic][MAGIC] bytes and the [version][VERSION] of the
+//! encoding, followed by a table of the distinct symbols within the expression, and then the
+//! expression itself in pre-order. Every number, including the length of a symbol and the position
+//! of a symbol within the table, is encoded as an unsigned LEB128 varint. Each sub-expression
+//! starts with a tag byte:
+//!
+//! | Tag | Sub-expression   | Followed by                                     |
+//! |-----|------------------|-------------------------------------------------|
+//! | 0   | free variable    | symbol                                          |
+//! | 1   | bound variable   | index, symbol                                   |
+//! | 2   | application      | first and second sub-expressions                |
+//! | 3   | λ-abstraction    | symbol, type and body sub-expressions           |
+//! | 4   | Π-type           | symbol, type and body sub-expressions           |
+//! | 5   | Σ-type           | symbol, type and body sub-expressions           |
+//! | 6   | universe         | level                                           |
+
+use super::Codec;
+use crate::ast::{Abs, App, Exp, Idx, Prd, Sum, Sym, Unv, Var};
+use crate::err::DecodeErr;
+use std::collections::HashMap;
+
+/// Magic bytes that every encoded expression starts with.
+pub const MAGIC: [u8; 4] = *b"FLXO";
+
+/// Version of the encoding, which follows the [magic][MAGIC] bytes.
+pub const VERSION: u8 = 1;
+
+/// Codec for the binary encoding of the AST.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Binary;
+
+/// Tag for a [free variable][Var::Sym].
+const TAG_SYM: u8 = 0;
+/// Tag for a [bound variable][Var::Idx].
+const TAG_IDX: u8 = 1;
+/// Tag for an [application][App].
+const TAG_APP: u8 = 2;
+/// Tag for a [λ-abstraction][Abs].
+const TAG_ABS: u8 = 3;
+/// Tag for a [Π-type][Prd].
+const TAG_PRD: u8 = 4;
+/// Tag for a [Σ-type][Sum].
+const TAG_SUM: u8 = 5;
+/// Tag for a [universe][Unv].
+const TAG_UNV: u8 = 6;
+
+/// Pending step in decoding an expression.
+enum Task {
+    /// Decode the sub-expression that starts at the current position, and push it.
+    Exp,
+    /// Pop the decoded constituents of an expression with the supplied tag and symbol, and push
+    /// the expression.
+    Build(u8, usize),
+    /// Bring the binder with the supplied symbol into scope.
+    Bind(usize),
+    /// Remove the innermost binder from scope.
+    Unbind,
+}
+
+/// Table of the distinct symbols within an expression being encoded.
+#[derive(Default)]
+struct Table<'a> {
+    /// Symbols, in the order that they were first found.
+    syms: Vec<&'a str>,
+    /// Position of each symbol within the table.
+    ids: HashMap<&'a str, u64>,
+}
+
+/// Reader over encoded bytes, which keeps track of its position within them.
+struct Reader<'a> {
+    /// Encoded bytes.
+    val: &'a [u8],
+    /// Position of the next byte to be read.
+    loc: usize,
+}
+
+impl Binary {
+    /// Create a new instance of the codec.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Codec<Vec<u8>> for Binary {
+    fn encode(&self, exp: &Exp) -> Vec<u8> {
+        let mut syms = Table::default();
+        let mut sym = |sym, body: &mut Vec<u8>| write_num(body, syms.id(sym));
+
+        let mut body = vec![];
+        let mut stack = vec![exp];
+        while let Some(exp) = stack.pop() {
+            match exp {
+                Exp::Var(Var::Sym(var)) => {
+                    body.push(TAG_SYM);
+                    sym(var, &mut body);
+                }
+                Exp::Var(Var::Idx(idx)) => {
+                    body.push(TAG_IDX);
+                    write_num(&mut body, idx.val);
+                    sym(&idx.sym, &mut body);
+                }
+                Exp::App(app) => {
+                    body.push(TAG_APP);
+                    stack.extend([&*app.snd, &*app.fst]);
+                }
+                Exp::Abs(Abs {
+                    sym: var,
+                    typ,
+                    exp: sub,
+                })
+                | Exp::Prd(Prd {
+                    sym: var,
+                    typ,
+                    exp: sub,
+                })
+                | Exp::Sum(Sum {
+                    sym: var,
+                    typ,
+                    exp: sub,
+                }) => {
+                    body.push(match exp {
+                        Exp::Abs(_) => TAG_ABS,
+                        Exp::Prd(_) => TAG_PRD,
+                        _ => TAG_SUM,
+                    });
+                    sym(var, &mut body);
+                    stack.extend([&**sub, &**typ]);
+                }
+                Exp::Unv(unv) => {
+                    body.push(TAG_UNV);
+                    write_num(&mut body, unv.level);
+                }
+            }
+        }
+
+        let mut res = MAGIC.to_vec();
+        res.push(VERSION);
+        write_num(&mut res, syms.syms.len() as u64);
+        for sym in syms.syms {
+            write_num(&mut res, sym.len() as u64);
+            res.extend(sym.as_bytes());
+        }
+        res.extend(body);
+        res
+    }
+
+    /// Decode bytes to an [expression][Exp], which must also be [well-scoped][Exp::validate].
+    ///
+    /// Every byte is checked, so that untrusted input can be decoded, but the position reported
+    /// by an error is only ever that of the first problem found.
+    fn decode(&self, val: &Vec<u8>) -> Result<Exp, DecodeErr> {
+        let mut reader = Reader { val, loc: 0 };
+        for byte in MAGIC {
+            if reader.byte()? != byte {
+                return Err(reader.invalid(1, "magic bytes of the encoding"));
+            }
+        }
+        if reader.byte()? != VERSION {
+            return Err(reader.invalid(1, &format!("version {} of the encoding", VERSION)));
+        }
+
+        let len = reader.len()?;
+        let mut syms = Vec::with_capacity(len.min(reader.rest()));
+        for _ in 0..len {
+            let len = reader.len()?;
+            let val = reader.bytes(len)?;
+            match std::str::from_utf8(val) {
+                Ok(val) if Sym::is_valid(val) => syms.push(Sym::new(val)),
+                _ => return Err(reader.invalid(len, "a symbol")),
+            }
+        }
+
+        let mut tasks = vec![Task::Exp];
+        let mut scope: Vec<usize> = vec![];
+        let mut res: Vec<Exp> = vec![];
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Exp => {}
+                Task::Build(tag, id) => {
+                    let snd = Box::new(res.pop().unwrap_or(Exp::Unv(Unv::new())));
+                    let fst = Box::new(res.pop().unwrap_or(Exp::Unv(Unv::new())));
+                    let sym = || syms[id].clone();
+                    res.push(match tag {
+                        TAG_ABS => Exp::Abs(Abs {
+                            sym: sym(),
+                            typ: fst,
+                            exp: snd,
+                        }),
+                        TAG_PRD => Exp::Prd(Prd {
+                            sym: sym(),
+                            typ: fst,
+                            exp: snd,
+                        }),
+                        TAG_SUM => Exp::Sum(Sum {
+                            sym: sym(),
+                            typ: fst,
+                            exp: snd,
+                        }),
+                        _ => Exp::App(App { fst, snd }),
+                    });
+                    continue;
+                }
+                Task::Bind(id) => {
+                    scope.push(id);
+                    continue;
+                }
+                Task::Unbind => {
+                    scope.pop();
+                    continue;
+                }
+            }
+
+            let sloc = reader.loc;
+            let tag = reader.byte()?;
+            let sym = |reader: &mut Reader| {
+                let sloc = reader.loc;
+                match reader.len()? {
+                    id if id < syms.len() => Ok(id),
+                    _ => Err(DecodeErr::InvalidData(
+                        sloc,
+                        "a symbol within the table".to_string(),
+                    )),
+                }
+            };
+            match tag {
+                TAG_SYM => res.push(Exp::Var(Var::Sym(syms[sym(&mut reader)?].clone()))),
+                TAG_IDX => {
+                    let val = reader.num()?;
+                    let id = sym(&mut reader)?;
+                    let idx = Idx {
+                        val,
+                        sym: syms[id].clone(),
+                    };
+                    match idx.position(scope.len()).map(|pos| scope[pos]) {
+                        Some(binder) if syms[binder] == syms[id] => {}
+                        Some(_) => {
+                            return Err(DecodeErr::InvalidData(
+                                sloc,
+                                "the symbol of the binder that the index refers to".to_string(),
+                            ))
+                        }
+                        None => {
+                            return Err(DecodeErr::InvalidData(
+                                sloc,
+                                "an index that refers to an enclosing binder".to_string(),
+                            ))
+                        }
+                    }
+                    res.push(Exp::Var(Var::Idx(idx)));
+                }
+                TAG_APP => tasks.extend([Task::Build(tag, 0), Task::Exp, Task::Exp]),
+                TAG_ABS | TAG_PRD | TAG_SUM => {
+                    let id = sym(&mut reader)?;
+                    tasks.extend([
+                        Task::Build(tag, id),
+                        Task::Unbind,
+                        Task::Exp,
+                        Task::Bind(id),
+                        Task::Exp,
+                    ]);
+                }
+                TAG_UNV => res.push(Exp::Unv(Unv {
+                    level: reader.num()?,
+                })),
+                _ => return Err(DecodeErr::InvalidTag(tag, sloc)),
+            }
+        }
+
+        if reader.rest() > 0 {
+            return Err(DecodeErr::InvalidData(
+                reader.loc,
+                "the end of the encoding".to_string(),
+            ));
+        }
+        res.pop().ok_or(DecodeErr::Truncated(reader.loc))
+    }
+}
+
+impl<'a> Table<'a> {
+    /// Returns the position of a symbol within the table, adding it if it is not there yet.
+    fn id(&mut self, sym: &'a Sym) -> u64 {
+        let syms = &mut self.syms;
+        *self.ids.entry(sym.val.as_str()).or_insert_with(|| {
+            syms.push(sym.val.as_str());
+            syms.len() as u64 - 1
+        })
+    }
+}
+
+impl Reader<'_> {
+    /// Returns the number of bytes that are yet to be read.
+    fn rest(&self) -> usize {
+        self.val.len() - self.loc
+    }
+
+    /// Reads the next byte.
+    fn byte(&mut self) -> Result<u8, DecodeErr> {
+        let res = *self
+            .val
+            .get(self.loc)
+            .ok_or(DecodeErr::Truncated(self.loc))?;
+        self.loc += 1;
+        Ok(res)
+    }
+
+    /// Reads the supplied number of bytes.
+    fn bytes(&mut self, len: usize) -> Result<&[u8], DecodeErr> {
+        if len > self.rest() {
+            return Err(DecodeErr::Truncated(self.val.len()));
+        }
+        self.loc += len;
+        Ok(&self.val[self.loc - len..self.loc])
+    }
+
+    /// Reads a number, encoded as an unsigned LEB128 varint.
+    fn num(&mut self) -> Result<u64, DecodeErr> {
+        let sloc = self.loc;
+        let mut res: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            let val = u64::from(byte & 0x7f);
+            if shift == 63 && val > 1 {
+                break; // overflows
+            }
+            res |= val << shift;
+            if byte & 0x80 == 0 {
+                return Ok(res);
+            }
+        }
+        Err(DecodeErr::InvalidData(sloc, "a 64-bit number".to_string()))
+    }
+
+    /// Reads a number that is the length of, or position within, something held in memory.
+    fn len(&mut self) -> Result<usize, DecodeErr> {
+        let sloc = self.loc;
+        usize::try_from(self.num()?)
+            .map_err(|_| DecodeErr::InvalidData(sloc, "a length that fits in memory".to_string()))
+    }
+
+    /// Returns an error for the supplied number of bytes just read, which are not as expected.
+    fn invalid(&self, len: usize, expected: &str) -> DecodeErr {
+        DecodeErr::InvalidData(self.loc - len, expected.to_string())
+    }
+}
+
+/// Writes a number, encoded as an unsigned LEB128 varint.
+fn write_num(res: &mut Vec<u8>, mut val: u64) {
+    while val >= 0x80 {
+        res.push((val & 0x7f) as u8 | 0x80);
+        val >>= 7;
+    }
+    res.push(val as u8);
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::enc::core::par::{binders, decode, SAMPLES};
+
+    #[test]
+    fn test_encode() {
+        let res = Binary.encode(&decode("λx : □ . f x"));
+        let mut exp = b"FLXO\x01\x02\x01x\x01f".to_vec(); // header and symbol table
+        exp.extend([TAG_ABS, 0, TAG_UNV, 0, TAG_APP, TAG_SYM, 1, TAG_IDX, 0, 0]);
+        assert_eq!(res, exp);
+    }
+
+    #[test]
+    fn test_decode_encode() {
+        for val in SAMPLES {
+            let exp = decode(val);
+            assert_eq!(Binary.decode(&Binary.encode(&exp)), Ok(exp));
+        }
+        let exp = Exp::Unv(Unv { level: u64::MAX });
+        assert_eq!(Binary.decode(&Binary.encode(&exp)), Ok(exp));
+    }
+
+    #[test]
+    fn test_encode_deep() {
+        let exp = binders(100_000, Exp::Var(Var::Idx(Idx::new(&Sym::new("x")))));
+        assert_eq!(Binary.decode(&Binary.encode(&exp)), Ok(exp));
+    }
+
+    #[test]
+    fn test_decode_err() {
+        let head = |val: &[u8]| [b"FLXO\x01\x01\x01x".as_slice(), val].concat();
+        let err = |val: &[u8]| Binary.decode(&head(val)).unwrap_err();
+        let data = |sloc, expected: &str| DecodeErr::InvalidData(sloc, expected.to_string());
+        assert_eq!(err(&[]), DecodeErr::Truncated(8));
+        assert_eq!(err(&[TAG_APP, TAG_UNV, 0]), DecodeErr::Truncated(11));
+        assert_eq!(err(&[7]), DecodeErr::InvalidTag(7, 8));
+        assert_eq!(err(&[TAG_SYM, 1]), data(9, "a symbol within the table"));
+        assert_eq!(err(&[TAG_UNV, 0, 0]), data(10, "the end of the encoding"));
+        let mut num = [0xff; 10];
+        num[9] = 0x02; // one more than the maximum
+        assert_eq!(
+            err(&[&[TAG_UNV], &num[..]].concat()),
+            data(9, "a 64-bit number")
+        );
+        assert_eq!(
+            err(&[TAG_IDX, 0, 0]),
+            data(8, "an index that refers to an enclosing binder")
+        );
+        assert_eq!(
+            Binary.decode(&b"FLXP\x01\x00\x06\x00".to_vec()),
+            Err(data(3, "magic bytes of the encoding"))
+        );
+        assert_eq!(
+            Binary.decode(&b"FLXO\x02\x00\x06\x00".to_vec()),
+            Err(data(4, "version 1 of the encoding"))
+        );
+        assert_eq!(
+            Binary.decode(&b"FLXO\x01\x01\x02\xff\xfe\x06\x00".to_vec()),
+            Err(data(7, "a symbol"))
+        );
+        assert_eq!(
+            Binary.decode(&b"FLXO\x01\x01\x02x \x06\x00".to_vec()),
+            Err(data(7, "a symbol"))
+        );
+    }
+
+    #[test]
+    fn test_decode_untrusted() {
+        let val = Binary.encode(&decode(SAMPLES[0]));
+        for len in 0..val.len() {
+            assert!(Binary.decode(&val[..len].to_vec()).is_err());
+        }
+        for pos in 0..val.len() {
+            for byte in [0x00, 0x01, 0x07, 0x7f, 0x80, 0xff] {
+                let mut val = val.clone();
+                val[pos] = byte;
+                let _ = Binary.decode(&val); // must not panic
+            }
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! Source formatting for the core language.
+
+use super::lex::Lexer;
+use super::par::Core;
+use crate::enc::Codec;
+use crate::err::DecodeErr;
+
+impl Core {
+    /// Reprints source input in the core language canonically, preserving its comments.
+    ///
+    /// The expression is encoded with this codec, so that its width determines where lines are
+    /// broken. Comments that precede the expression are kept on their own lines before it, and
+    /// comments that follow it are kept after it. Comments placed within the expression are moved
+    /// before it, in order, since the expression itself has nowhere to hold them.
+    pub fn format(&self, val: &str) -> Result<String, DecodeErr> {
+        let exp = self.decode(&val.to_string())?;
+        let eloc = Lexer::new(val)
+            .filter_map(|res| res.ok())
+            .last()
+            .map(|(_, _, eloc)| eloc)
+            .unwrap_or_default();
+        let (trail, lead): (Vec<_>, Vec<_>) = Lexer::comments(val)
+            .into_iter()
+            .partition(|comment| comment.loc >= eloc);
+        let mut res = String::new();
+        for comment in lead {
+            res.push_str(&comment.val);
+            res.push('\n');
+        }
+        res.push_str(&self.reset().encode(&exp));
+        res.push('\n');
+        for comment in trail {
+            res.push_str(&comment.val);
+            res.push('\n');
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let val = "-- identity\nλfoo : □ . -- body\n  foo    -- done\n-- end";
+        let can = "-- identity\n-- body\nλfoo : □ . foo\n-- done\n-- end\n";
+        assert_eq!(Core::new().format(val), Ok(can.to_string()));
+        assert_eq!(Core::new().format(can), Ok(can.to_string()));
+    }
+
+    #[test]
+    fn test_format_width() {
+        let val = "λbar : float . λmoo : char . (foo bar) moo";
+        let can = "λbar : float .\nλmoo : char .\n  foo bar moo\n";
+        assert_eq!(Core::new().with_width(20).format(val), Ok(can.to_string()));
+    }
+
+    #[test]
+    fn test_format_err() {
+        assert!(matches!(
+            Core::new().format("-- nothing\n"),
+            Err(DecodeErr::EndOfStream(..))
+        ));
+    }
+}
@@ -8,6 +8,7 @@ use std::fmt::{Display, Formatter};
 /// Tokens available to the grammar of the core language.
 #[derive(Logos, Clone, Debug, Eq, Hash, PartialEq)]
 #[logos(skip r"[ \t\n\f]+")]
+#[logos(skip r"--[^\n]*")]
 pub enum Tok {
     /// Identifier token.
     ///
@@ -15,6 +16,12 @@ pub enum Tok {
     /// more lowercase alphabets, decimal numbers or underscores. An identifier cannot be empty.
     #[regex("[a-z][a-z0-9_]*", |lex| lex.slice().parse().ok())]
     Ident(String),
+    /// Number token, which is a non-negative decimal that fits within 64 bits.
+    ///
+    /// A number stands for a bound variable by its De Bruijn index, as rendered by the
+    /// [codec][crate::enc::core::par::Core] when `show_indices` is set.
+    #[regex("[0-9]+", |lex| lex.slice().parse().ok())]
+    Num(u64),
     /// Left parenthesis token.
     #[token("(")]
     LParen,
@@ -36,15 +43,19 @@ pub enum Tok {
     /// Uppercase Greek letter *sigma* token.
     #[token("Σ")]
     Sigma,
-    /// Box character token.
-    #[token("□")]
-    Box,
+    /// Box character token, which denotes a universe.
+    ///
+    /// The level of the universe is written in subscript decimal digits after the box, such as
+    /// `□₁`, and is 0 when there are none. The level must fit within 64 bits.
+    #[regex("□[₀-₉]*", |lex| parse_level(lex.slice()))]
+    Box(u64),
 }
 
 impl Display for Tok {
     fn fmt(&self, f: &mut Formatter<'_>) -> Formatted {
         match self {
             Tok::Ident(ident) => write!(f, "{}", ident),
+            Tok::Num(num) => write!(f, "{}", num),
             Tok::LParen => write!(f, "("),
             Tok::RParen => write!(f, ")"),
             Tok::Dot => write!(f, "."),
@@ -52,11 +63,46 @@ impl Display for Tok {
             Tok::Lambda => write!(f, "λ"),
             Tok::Pi => write!(f, "Π"),
             Tok::Sigma => write!(f, "Σ"),
-            Tok::Box => write!(f, "□"),
+            Tok::Box(level) => write!(f, "{}", fmt_level(*level)),
         }
     }
 }
 
+/// Formats the box character for a universe at the supplied level, with the level written in
+/// subscript digits unless it is 0.
+pub fn fmt_level(level: u64) -> String {
+    let mut res = "□".to_string();
+    if level > 0 {
+        let digits = level.to_string();
+        res.extend(
+            digits
+                .chars()
+                .filter_map(|c| char::from_u32(c as u32 - '0' as u32 + '₀' as u32)),
+        );
+    }
+    res
+}
+
+/// Parses the level of a universe from its box character and subscript digits.
+fn parse_level(val: &str) -> Option<u64> {
+    val.chars().skip(1).try_fold(0u64, |res, c| {
+        res.checked_mul(10)?
+            .checked_add(u64::from(c as u32 - '₀' as u32))
+    })
+}
+
+/// Comment within the source input stream, which extends from `--` to the end of the line.
+///
+/// Comments carry no meaning and are skipped by the [lexer][Lexer], but are tracked separately so
+/// that tools which rewrite source input are able to preserve them.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Comment {
+    /// Location of the comment in the source input stream.
+    pub loc: usize,
+    /// Text of the comment, including the leading `--`.
+    pub val: String,
+}
+
 pub struct Lexer<'input> {
     /// Stream of tokens, where each token is paired with its location in the source input stream.
     token_stream: SpannedIter<'input, Tok>,
@@ -69,6 +115,21 @@ impl<'input> Lexer<'input> {
             token_stream: Tok::lexer(input).spanned(),
         }
     }
+
+    /// Returns the comments within the source input stream, in order.
+    pub fn comments(input: &str) -> Vec<Comment> {
+        let mut res = vec![];
+        let mut pos = 0;
+        while let Some(loc) = input[pos..].find("--").map(|loc| pos + loc) {
+            let len = input[loc..].find('\n').unwrap_or(input.len() - loc);
+            res.push(Comment {
+                loc,
+                val: input[loc..loc + len].trim_end().to_string(),
+            });
+            pos = loc + len;
+        }
+        res
+    }
 }
 
 impl<'input> Iterator for Lexer<'input> {
@@ -81,3 +142,61 @@ impl<'input> Iterator for Lexer<'input> {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_comments() {
+        let val = "-- head\nλfoo : □ . --- inner --\n  foo -- tail";
+        let toks: Vec<Tok> = Lexer::new(val).map(|res| res.unwrap().1).collect();
+        assert_eq!(
+            toks,
+            vec![
+                Tok::Lambda,
+                Tok::Ident("foo".to_string()),
+                Tok::Colon,
+                Tok::Box(0),
+                Tok::Dot,
+                Tok::Ident("foo".to_string()),
+            ]
+        );
+        assert_eq!(
+            Lexer::comments(val),
+            vec![
+                Comment {
+                    loc: 0,
+                    val: "-- head".to_string()
+                },
+                Comment {
+                    loc: 22,
+                    val: "--- inner --".to_string()
+                },
+                Comment {
+                    loc: 41,
+                    val: "-- tail".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_level() {
+        let toks: Vec<_> = Lexer::new("□ □₁ □₁₀ □₁₈₄₄₆₇₄₄₀₇₃₇₀₉₅₅₁₆₁₅").collect();
+        assert_eq!(
+            toks.into_iter()
+                .map(|res| res.unwrap().1)
+                .collect::<Vec<_>>(),
+            vec![Tok::Box(0), Tok::Box(1), Tok::Box(10), Tok::Box(u64::MAX)]
+        );
+        assert_eq!(
+            Lexer::new("□₁₈₄₄₆₇₄₄₀₇₃₇₀₉₅₅₁₆₁₆").next(),
+            Some(Err(DecodeErr::InvalidToken(0))) // overflows
+        );
+        assert_eq!(fmt_level(0), "□");
+        assert_eq!(fmt_level(1), "□₁");
+        assert_eq!(fmt_level(3944), "□₃₉₄₄");
+    }
+}
@@ -0,0 +1,155 @@
+//! Locations of expressions within the source input stream of the core language.
+
+use crate::ast::{Path, Step};
+
+/// Location of an [expression][crate::ast::Exp] within the source input stream, together with the
+/// locations of its constituents.
+///
+/// The constituents are ordered as `fst` and `snd` for an [application][crate::ast::App], and as
+/// `typ` and `exp` for a [binder][crate::ast::Binder], so that the location of a sub-expression is
+/// reached by the same [path][Path] as the sub-expression itself.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub struct Loc {
+    /// Location of the first byte of the expression.
+    pub sloc: usize,
+    /// Location just past the last byte of the expression.
+    pub eloc: usize,
+    /// Locations of the first byte and just past the last byte of the symbol of a binder.
+    pub sym: Option<(usize, usize)>,
+    /// Locations of the constituents of the expression.
+    pub args: Vec<Loc>,
+}
+
+impl Loc {
+    /// Create a new instance of a location.
+    pub fn new(sloc: usize, eloc: usize, args: Vec<Loc>) -> Self {
+        Self {
+            sloc,
+            eloc,
+            sym: None,
+            args,
+        }
+    }
+
+    /// Create a new instance of the location of a [binder][crate::ast::Binder].
+    pub fn binder(sloc: usize, eloc: usize, sym: (usize, usize), typ: Loc, exp: Loc) -> Self {
+        Self {
+            sloc,
+            eloc,
+            sym: Some(sym),
+            args: vec![typ, exp],
+        }
+    }
+
+    /// Checks whether the supplied location lies within this expression, including just past its
+    /// last byte.
+    pub fn contains(&self, loc: usize) -> bool {
+        self.sloc <= loc && loc <= self.eloc
+    }
+
+    /// Returns the path to the innermost sub-expression that contains the supplied location.
+    pub fn find(&self, loc: usize) -> Path {
+        let mut res = Path::new();
+        let mut cur = self;
+        while let Some((pos, arg)) = cur
+            .args
+            .iter()
+            .enumerate()
+            .find(|(_, arg)| arg.contains(loc))
+        {
+            res.steps.push(match (cur.sym, pos) {
+                (None, 0) => Step::Fst,
+                (None, _) => Step::Snd,
+                (Some(_), 0) => Step::Typ,
+                (Some(_), _) => Step::Exp,
+            });
+            cur = arg;
+        }
+        res
+    }
+
+    /// Returns the location of the constituent reached by a step, or [None] if the step cannot be
+    /// taken from this kind of expression.
+    pub fn child(&self, step: Step) -> Option<&Loc> {
+        match (self.sym, step) {
+            (None, Step::Fst | Step::Snd) | (Some(_), Step::Typ | Step::Exp) => {
+                self.args.get(step.pos())
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the location of the sub-expression at the end of the supplied path, or [None] if
+    /// the path leads to no sub-expression.
+    pub fn get(&self, path: &Path) -> Option<&Loc> {
+        path.steps
+            .iter()
+            .try_fold(self, |cur, &step| cur.child(step))
+    }
+}
+
+/// Builder of the [location][Loc] of an expression, from the spans of its sub-expressions.
+///
+/// The grammar of the core language supplies the span of each sub-expression as it completes it,
+/// which is after its constituents, so that the builder holds the locations of the completed
+/// sub-expressions that are not yet constituents of another. Decoding without locations uses a
+/// builder that discards the spans instead.
+#[derive(Debug, Default)]
+pub struct Spans {
+    /// Whether the spans supplied are kept.
+    keep: bool,
+    /// Locations of the completed sub-expressions that are not yet constituents of another.
+    res: Vec<Loc>,
+}
+
+impl Spans {
+    /// Create a new instance of the builder, which keeps the spans supplied to it only if `keep`
+    /// is set.
+    pub fn new(keep: bool) -> Self {
+        Self { keep, res: vec![] }
+    }
+
+    /// Adds the span of a variable or a universe.
+    pub(crate) fn leaf(&mut self, sloc: usize, eloc: usize) {
+        if self.keep {
+            self.res.push(Loc::new(sloc, eloc, vec![]));
+        }
+    }
+
+    /// Adds the span of an application, whose constituents are the last two completed.
+    pub(crate) fn app(&mut self, sloc: usize, eloc: usize) {
+        if self.keep {
+            let snd = self.res.pop().unwrap_or(Loc::new(eloc, eloc, vec![]));
+            let fst = self.res.pop().unwrap_or(Loc::new(sloc, sloc, vec![]));
+            self.res.push(Loc::new(sloc, eloc, vec![fst, snd]));
+        }
+    }
+
+    /// Adds the span of a binder and that of its symbol, where the type and body of the binder
+    /// are the last two completed.
+    pub(crate) fn binder(&mut self, sloc: usize, eloc: usize, sym: (usize, usize)) {
+        if self.keep {
+            let exp = self.res.pop().unwrap_or(Loc::new(eloc, eloc, vec![]));
+            let typ = self.res.pop().unwrap_or(Loc::new(sloc, sloc, vec![]));
+            self.res.push(Loc::binder(sloc, eloc, sym, typ, exp));
+        }
+    }
+
+    /// Returns the location of the last completed expression, which is the outermost once the
+    /// input is decoded, or [None] if spans are discarded.
+    pub(crate) fn finish(mut self) -> Option<Loc> {
+        self.res.pop()
+    }
+}
+
+impl Drop for Loc {
+    /// Drops the location without recursion, since locations are nested as deeply as the
+    /// [expressions][crate::ast::Exp] they belong to.
+    fn drop(&mut self) {
+        let mut stack = std::mem::take(&mut self.args);
+        while let Some(mut loc) = stack.pop() {
+            stack.append(&mut loc.args);
+        } // each location is dropped here, with no constituents left
+    }
+}
@@ -1,4 +1,6 @@
 //! Core language, which is the *canonical* encoding of the Abstract Syntax Tree (AST).
 
+mod fmt;
 pub mod lex;
+pub mod loc;
 pub mod par;
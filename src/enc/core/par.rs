@@ -1,12 +1,19 @@
 //! Parsing utilities for the core language.
 
-lalrpop_mod!(pub grammar, "/enc/core/grammar.rs");
+lalrpop_mod!(#[allow(clippy::all)] pub grammar, "/enc/core/grammar.rs");
 
-use super::lex::Lexer;
-use crate::ast::{App, Binder, Exp, Var};
+use super::lex::{fmt_level, Lexer};
+use super::loc::{Loc, Spans};
+use crate::ast::{Abs, App, Binder, Exp, Prd, Sum, Sym, Unv, Var};
 use crate::enc::Codec;
-use crate::err::DecodeErr;
+use crate::err::{DecodeErr, ScopeErr};
+use crate::fmt::Doc;
 use grammar::ExpParser;
+use std::cell::OnceCell;
+use std::collections::{HashMap, HashSet};
+
+/// Number of columns by which broken lines are indented relative to their enclosing term.
+const INDENT: usize = 2;
 
 /// Core language implementing the *canonical* encoding of the Abstract Syntax Tree (AST).
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
@@ -17,6 +24,8 @@ pub struct Core {
     rtree: bool,
     /// Show bound variables as De Bruijn indices rather than their original symbols.
     show_indices: bool,
+    /// Target width for lines in the encoded output; [usize::MAX] renders on a single line.
+    width: usize,
 }
 
 impl Core {
@@ -26,6 +35,7 @@ impl Core {
             ltree: false,
             rtree: false,
             show_indices: false,
+            width: usize::MAX,
         }
     }
 
@@ -46,55 +56,542 @@ impl Core {
         res.show_indices = show_indices;
         res
     }
+
+    /// Returns a copy of the codec that renders expressions within a target `width`.
+    ///
+    /// Lines are broken after the `.` of a binder and between the terms of an application, with
+    /// the broken lines indented, until they fit within the width wherever possible. The default
+    /// width of [usize::MAX] renders every expression on a single line.
+    pub fn with_width(self, width: usize) -> Self {
+        Self { width, ..self }
+    }
+}
+
+/// Pending step in formatting an expression as a [document][Doc].
+enum Task<'a> {
+    /// Format an expression with the supplied codec, and push the result.
+    Exp(&'a Exp, Core),
+    /// Pop the formatted type and body of a binder, and push the binder.
+    Binder {
+        /// Prefix and name of the binder, up to its type.
+        pat: String,
+        /// Whether the body is itself a binder, and so aligned with this one.
+        chain: bool,
+        /// Whether the binder is parenthesized.
+        parens: bool,
+    },
+    /// Pop the formatted head and arguments of an application, and push the application.
+    App {
+        /// Number of arguments applied to the head.
+        args: usize,
+        /// Whether the application is parenthesized.
+        parens: bool,
+    },
+    /// Bring the name chosen for a binder into scope, along with its identifier.
+    Bind(Sym, usize),
+    /// Remove the innermost binder from scope.
+    Unbind,
+}
+
+/// Names chosen for the binders enclosing the expression being formatted.
+struct Scope<'a> {
+    /// Name chosen for each enclosing binder, with the innermost binder last.
+    syms: Vec<Sym>,
+    /// Identifier of each enclosing binder, in the same order.
+    ids: Vec<usize>,
+    /// Number of binders formatted so far, which is the identifier of the next one.
+    next: usize,
+    /// Number of enclosing binders given each name.
+    counts: HashMap<String, usize>,
+    /// Names of the free variables anywhere within the outermost expression.
+    free: HashSet<&'a str>,
+    /// Outermost expression.
+    exp: &'a Exp,
+    /// Names used within the body of each binder that share its stem, by identifier.
+    uses: OnceCell<Vec<Option<Uses<'a>>>>,
+}
+
+/// Names used within an expression that share a stem, which the name of a binder with that stem
+/// must avoid when the binder encloses the expression.
+#[derive(Clone, Default)]
+struct Uses<'a> {
+    /// Names of the free variables within the expression.
+    free: HashSet<&'a str>,
+    /// Depths of the binders outside of the expression that it refers to, where the outermost
+    /// binder has depth 0.
+    bound: HashSet<usize>,
+}
+
+impl Uses<'_> {
+    /// Adds the names used within another expression, merging the smaller sets into the larger.
+    fn merge(mut self, mut other: Self) -> Self {
+        if self.free.len() < other.free.len() {
+            std::mem::swap(&mut self.free, &mut other.free);
+        }
+        if self.bound.len() < other.bound.len() {
+            std::mem::swap(&mut self.bound, &mut other.bound);
+        }
+        self.free.extend(other.free);
+        self.bound.extend(other.bound);
+        self
+    }
+}
+
+/// Names used within an expression, grouped by their stem.
+///
+/// Renaming a binder keeps its stem, so the name of a binder can only conflict with the names
+/// that share its stem.
+#[derive(Default)]
+struct Stems<'a>(HashMap<&'a str, Uses<'a>>);
+
+impl<'a> Stems<'a> {
+    /// Create a new instance holding a single name used within an expression.
+    fn new(stem: &'a str, uses: Uses<'a>) -> Self {
+        Self(HashMap::from([(stem, uses)]))
+    }
+
+    /// Adds the names used within another expression, merging the smaller groups into the larger.
+    fn merge(mut self, mut other: Self) -> Self {
+        if self.0.len() < other.0.len() {
+            std::mem::swap(&mut self.0, &mut other.0);
+        }
+        for (stem, uses) in other.0 {
+            let cur = self.0.remove(stem).unwrap_or_default();
+            self.0.insert(stem, cur.merge(uses));
+        }
+        self
+    }
+}
+
+/// Pending step in collecting the [names used][Stems] within the body of each binder.
+enum Visit<'a> {
+    /// Visit an expression.
+    Exp(&'a Exp),
+    /// Pop the names used within the constituents of an application, and push their union.
+    App,
+    /// Pop the names used within the type and body of the binder with the supplied symbol and
+    /// identifier, and push their union, less the binder itself.
+    Binder(&'a Sym, usize),
+    /// Bring the stem of a binder into scope.
+    Bind(&'a str),
+    /// Remove the innermost binder from scope.
+    Unbind,
+}
+
+impl<'a> Scope<'a> {
+    /// Create a new instance of an empty scope, for formatting the supplied expression.
+    fn new(exp: &'a Exp) -> Self {
+        let mut free = HashSet::new();
+        let mut stack = vec![exp];
+        while let Some(exp) = stack.pop() {
+            match exp {
+                Exp::Var(Var::Sym(sym)) => {
+                    free.insert(sym.val.as_str());
+                }
+                Exp::App(app) => stack.extend([&*app.snd, &*app.fst]),
+                Exp::Abs(Abs { typ, exp, .. })
+                | Exp::Prd(Prd { typ, exp, .. })
+                | Exp::Sum(Sum { typ, exp, .. }) => stack.extend([&**exp, &**typ]),
+                Exp::Var(Var::Idx(_)) | Exp::Unv(_) => {} // constants name nothing
+            }
+        }
+        Self {
+            syms: vec![],
+            ids: vec![],
+            next: 0,
+            counts: HashMap::new(),
+            free,
+            exp,
+            uses: OnceCell::new(),
+        }
+    }
+
+    /// Returns the names used within the body of the binder with the supplied identifier, that
+    /// share its stem.
+    ///
+    /// These are collected for every binder on first use, in a single pass from the innermost
+    /// expressions out, since most expressions have no binder that needs them.
+    fn uses(&self, id: usize) -> Option<&Uses<'a>> {
+        let uses = self.uses.get_or_init(|| collect(self.exp));
+        uses.get(id)?.as_ref()
+    }
+
+    /// Brings the name chosen for a binder into scope, as the innermost binder.
+    fn bind(&mut self, sym: Sym, id: usize) {
+        *self.counts.entry(sym.val.clone()).or_default() += 1;
+        self.syms.push(sym);
+        self.ids.push(id);
+    }
+
+    /// Removes the innermost binder from scope.
+    fn unbind(&mut self) {
+        self.ids.pop();
+        if let Some(sym) = self.syms.pop() {
+            if let Some(count) = self.counts.get_mut(&sym.val) {
+                *count -= 1;
+                if *count == 0 {
+                    self.counts.remove(&sym.val);
+                }
+            }
+        }
+    }
+}
+
+/// Markup for the constituents of an expression, which lets other encodings reuse the layout and
+/// parenthesization of the core language.
+pub(crate) trait Markup {
+    /// Returns the markup for a free variable, or for a bound variable that refers to nothing.
+    fn free(&self, sym: &Sym) -> String;
+    /// Returns the markup for a bound variable, which refers to the binder with the supplied
+    /// identifier.
+    fn bound(&self, sym: &Sym, id: usize) -> String;
+    /// Returns the markup for a binder with the supplied prefix, up to its type. Each binder within
+    /// an expression has a distinct identifier, assigned in order.
+    fn binder(&self, prefix: &str, sym: &Sym, id: usize) -> String;
+    /// Returns the markup for a universe.
+    fn unv(&self, unv: &Unv) -> String;
+    /// Returns the markup for punctuation, which is one of `(`, `)` or ` .`.
+    fn punct(&self, val: &str) -> String;
+    /// Returns the markup that separates the terms of an application, in addition to a line.
+    fn space(&self) -> String {
+        String::new()
+    }
+}
+
+/// Markup of the core language itself, which is plain text.
+struct Plain;
+
+impl Markup for Plain {
+    fn free(&self, sym: &Sym) -> String {
+        sym.to_string()
+    }
+
+    fn bound(&self, sym: &Sym, _: usize) -> String {
+        sym.to_string()
+    }
+
+    fn binder(&self, prefix: &str, sym: &Sym, _: usize) -> String {
+        format!("{}{} : ", prefix, sym)
+    }
+
+    fn unv(&self, unv: &Unv) -> String {
+        fmt_level(unv.level)
+    }
+
+    fn punct(&self, val: &str) -> String {
+        val.to_string()
+    }
 }
 
 impl Core {
-    /// Format a [binder][Binder] object.
-    fn fmt_binder<T: Binder>(&self, obj: &T) -> String {
-        let func = || {
-            format!(
-                "{}{} : {} . {}",
-                obj.prefix(),
-                obj.sym(),
-                self.reset().encode(obj.typ()), // not ambiguous, so start with a new branch
-                self.reset().encode(obj.exp()), // greedy, so reset, start with a new branch
-            )
-        };
-        self.fmt_parens(self.ltree, func) // parenthesize if on left sub-tree (exclusively)
+    /// Format an [expression][Exp] as a [document][Doc].
+    fn fmt_exp(&self, exp: &Exp) -> Doc {
+        self.fmt_markup(exp, &Plain)
     }
 
-    /// Format an [application][App].
-    fn fmt_app(&self, app: &App) -> String {
-        let func = || {
-            let ltree_codec = Self {
-                ltree: !self.rtree, // true || reset if current term is being parenthesized
-                ..*self
-            };
-            let rtree_codec = Self {
-                rtree: !self.rtree, // true || reset if current term is being parenthesized
-                ..*self
-            };
-            format!(
-                "{} {}",
-                ltree_codec.encode(&app.fst),
-                rtree_codec.encode(&app.snd)
-            )
+    /// Format an [expression][Exp] as a [document][Doc], with the supplied markup.
+    pub(crate) fn fmt_markup<M: Markup>(&self, exp: &Exp, markup: &M) -> Doc {
+        let mut scope = Scope::new(exp);
+        let mut tasks = vec![Task::Exp(exp, self.clone())];
+        let mut docs: Vec<Doc> = vec![];
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Exp(exp, codec) => match exp {
+                    Exp::Var(var) => docs.push(codec.fmt_var(var, &scope, markup)),
+                    Exp::App(app) => codec.fmt_app(app, &mut tasks),
+                    Exp::Abs(abs) => codec.fmt_binder(abs, &mut scope, markup, &mut tasks),
+                    Exp::Prd(prd) => codec.fmt_binder(prd, &mut scope, markup, &mut tasks),
+                    Exp::Sum(sum) => codec.fmt_binder(sum, &mut scope, markup, &mut tasks),
+                    Exp::Unv(unv) => docs.push(Doc::text(markup.unv(unv))),
+                },
+                Task::Binder { pat, chain, parens } => {
+                    let exp = docs.pop().unwrap_or(Doc::Nil);
+                    let typ = docs.pop().unwrap_or(Doc::Nil);
+                    let exp = if chain {
+                        Doc::Cat(vec![Doc::Line, exp]) // align chained binders
+                    } else {
+                        Doc::nest(INDENT, Doc::Cat(vec![Doc::Line, exp]))
+                    };
+                    let doc = Doc::group(Doc::Cat(vec![
+                        Doc::text(pat),
+                        Doc::nest(INDENT, typ),
+                        Doc::text(markup.punct(" .")),
+                        exp,
+                    ]));
+                    docs.push(fmt_parens(parens, doc, markup));
+                }
+                Task::App { args, parens } => {
+                    let args = docs.split_off(docs.len().saturating_sub(args));
+                    let head = docs.pop().unwrap_or(Doc::Nil);
+                    let space = markup.space();
+                    let args = args.into_iter().flat_map(|arg| {
+                        let space = Doc::text(space.as_str());
+                        [space, Doc::Line, arg]
+                    });
+                    let doc = Doc::group(Doc::Cat(vec![
+                        head,
+                        Doc::nest(INDENT, Doc::Cat(args.collect())),
+                    ]));
+                    docs.push(fmt_parens(parens, doc, markup));
+                }
+                Task::Bind(sym, id) => scope.bind(sym, id),
+                Task::Unbind => scope.unbind(),
+            }
+        }
+        docs.pop().unwrap_or(Doc::Nil)
+    }
+
+    /// Format a [variable][Var].
+    fn fmt_var<M: Markup>(&self, var: &Var, scope: &Scope, markup: &M) -> Doc {
+        match var {
+            Var::Sym(sym) => Doc::text(markup.free(sym)),
+            Var::Idx(idx) => {
+                if self.show_indices {
+                    Doc::text(idx.val.to_string())
+                } else {
+                    match idx.position(scope.syms.len()) {
+                        Some(pos) => Doc::text(markup.bound(&scope.syms[pos], scope.ids[pos])),
+                        None => Doc::text(markup.free(&idx.sym)), // dangling, so nothing to refer to
+                    }
+                }
+            }
+        }
+    }
+
+    /// Schedules the tasks that format a [binder][Binder] object.
+    fn fmt_binder<'a, T: Binder, M: Markup>(
+        &self,
+        obj: &'a T,
+        scope: &mut Scope,
+        markup: &M,
+        tasks: &mut Vec<Task<'a>>,
+    ) {
+        let sym = if self.show_indices && !scope.free.contains(obj.sym().val.as_str()) {
+            obj.sym().clone() // indices never refer to a binder by name, so nothing to shadow
+        } else {
+            fresh(obj.sym(), scope.next, scope)
         };
-        self.fmt_parens(self.rtree, func) // parenthesize if on right sub-tree (exclusively)
+        let id = scope.next;
+        scope.next += 1;
+        tasks.push(Task::Binder {
+            pat: markup.binder(obj.prefix(), &sym, id),
+            chain: matches!(obj.exp(), Exp::Abs(_) | Exp::Prd(_) | Exp::Sum(_)),
+            parens: self.ltree, // parenthesize if on left sub-tree (exclusively)
+        });
+        tasks.push(Task::Unbind);
+        tasks.push(Task::Exp(obj.exp(), self.reset())); // greedy, so reset, start with a new branch
+        tasks.push(Task::Bind(sym, id));
+        tasks.push(Task::Exp(obj.typ(), self.reset())); // not ambiguous, so start with a new branch
     }
 
-    /// Optionally parenthesizes an encoded [expression][Exp] formed with the supplied function, to
-    /// create the encoded version of the expression.
-    fn fmt_parens<F>(&self, parens: bool, func: F) -> String
-    where
-        F: FnOnce() -> String,
-    {
-        if parens {
-            format!("({})", func())
+    /// Schedules the tasks that format an [application][App], along with the spine of
+    /// applications beneath it.
+    ///
+    /// The head of the spine is never parenthesized on the left. Every argument is on the right
+    /// sub-tree, and every argument other than the last is also on the left sub-tree.
+    fn fmt_app<'a>(&self, app: &'a App, tasks: &mut Vec<Task<'a>>) {
+        // reset if current term is being parenthesized
+        let codec = if self.rtree {
+            self.reset()
         } else {
-            func()
+            self.clone()
+        };
+        let mut args = vec![Task::Exp(
+            &app.snd,
+            Self {
+                rtree: true,
+                ..codec
+            },
+        )];
+        let mut head = app.fst.as_ref();
+        while let Exp::App(fst) = head {
+            let arg = Self {
+                ltree: true,
+                rtree: true,
+                ..codec
+            };
+            args.push(Task::Exp(&fst.snd, arg));
+            head = &fst.fst;
+        }
+        tasks.push(Task::App {
+            args: args.len(),
+            parens: self.rtree, // parenthesize if on right sub-tree (exclusively)
+        });
+        tasks.extend(args); // the last argument is scheduled first, so formatted last
+        tasks.push(Task::Exp(
+            head,
+            Self {
+                ltree: true,
+                ..codec
+            },
+        ));
+    }
+}
+
+/// Optionally parenthesizes a [document][Doc].
+fn fmt_parens<M: Markup>(parens: bool, doc: Doc, markup: &M) -> Doc {
+    if parens {
+        Doc::Cat(vec![
+            Doc::text(markup.punct("(")),
+            doc,
+            Doc::text(markup.punct(")")),
+        ])
+    } else {
+        doc
+    }
+}
+
+/// Collects the names used within the body of each binder that share its stem, by identifier.
+fn collect(exp: &Exp) -> Vec<Option<Uses<'_>>> {
+    let mut uses = vec![];
+    let mut stems: Vec<&str> = vec![]; // stem of each enclosing binder
+    let mut done: Vec<Stems> = vec![];
+    let mut visits = vec![Visit::Exp(exp)];
+    while let Some(visit) = visits.pop() {
+        match visit {
+            Visit::Exp(exp) => match exp {
+                Exp::Var(Var::Sym(sym)) => {
+                    let free = HashSet::from([sym.val.as_str()]);
+                    let uses = Uses {
+                        free,
+                        ..Default::default()
+                    };
+                    done.push(Stems::new(stem(&sym.val), uses));
+                }
+                Exp::Var(Var::Idx(idx)) => match idx.position(stems.len()) {
+                    Some(pos) => {
+                        let bound = HashSet::from([pos]);
+                        let uses = Uses {
+                            bound,
+                            ..Default::default()
+                        };
+                        done.push(Stems::new(stems[pos], uses));
+                    }
+                    None => done.push(Stems::default()), // dangling, so refers to nothing
+                },
+                Exp::App(app) => {
+                    visits.push(Visit::App);
+                    visits.push(Visit::Exp(&app.snd));
+                    visits.push(Visit::Exp(&app.fst));
+                }
+                Exp::Abs(Abs { sym, typ, exp })
+                | Exp::Prd(Prd { sym, typ, exp })
+                | Exp::Sum(Sum { sym, typ, exp }) => {
+                    visits.push(Visit::Binder(sym, uses.len()));
+                    visits.push(Visit::Unbind);
+                    visits.push(Visit::Exp(exp));
+                    visits.push(Visit::Bind(stem(&sym.val)));
+                    visits.push(Visit::Exp(typ));
+                    uses.push(None); // identifiers are assigned in the order of formatting
+                }
+                Exp::Unv(_) => done.push(Stems::default()),
+            },
+            Visit::App => {
+                let snd = done.pop().unwrap_or_default();
+                let fst = done.pop().unwrap_or_default();
+                done.push(fst.merge(snd));
+            }
+            Visit::Binder(sym, id) => {
+                let mut exp = done.pop().unwrap_or_default();
+                let typ = done.pop().unwrap_or_default();
+                if let Some(own) = exp.0.get_mut(stem(&sym.val)) {
+                    own.bound.remove(&stems.len()); // refers to this binder itself
+                    uses[id] = Some(own.clone());
+                }
+                done.push(typ.merge(exp));
+            }
+            Visit::Bind(stem) => stems.push(stem),
+            Visit::Unbind => {
+                stems.pop();
+            }
         }
     }
+    uses
+}
+
+/// Returns the stem of a name, without any numeric suffix.
+fn stem(val: &str) -> &str {
+    val.trim_end_matches(|c: char| c.is_ascii_digit())
+}
+
+/// Chooses a name for the binder with the supplied symbol and identifier, that neither captures a
+/// free variable within its body nor shadows an enclosing binder that the body refers to.
+///
+/// The original symbol is preferred. Otherwise, any numeric suffix is replaced with the lowest
+/// number that yields an unused name. The names used within the body are only considered when the
+/// symbol names a free variable or an enclosing binder, since it cannot conflict with anything
+/// else.
+fn fresh(sym: &Sym, id: usize, scope: &Scope) -> Sym {
+    let val = sym.val.as_str();
+    if !scope.free.contains(val) && !scope.counts.contains_key(val) {
+        return sym.clone();
+    }
+    let Some(uses) = scope.uses(id) else {
+        return sym.clone(); // cannot conflict with anything
+    };
+    let bound = uses
+        .bound
+        .iter()
+        .filter_map(|&pos| scope.syms.get(pos).map(|sym| sym.val.as_str()));
+    let used: HashSet<&str> = uses.free.iter().copied().chain(bound).collect();
+    if !used.contains(val) {
+        return sym.clone();
+    }
+    (1..)
+        .map(|num| format!("{}{}", stem(val), num))
+        .find(|val| !used.contains(val.as_str()))
+        .map(|val| Sym::new(&val))
+        .unwrap_or_else(|| sym.clone())
+}
+
+/// Annotates each bound variable within a decoded expression with the symbol of the binder that it
+/// refers to, and checks that the expression is [well-scoped][Exp::validate].
+pub(crate) fn resolve(mut exp: Exp) -> Result<Exp, DecodeErr> {
+    let mut scope: Vec<Sym> = vec![];
+    let mut stack: Vec<(&mut Exp, Option<Sym>, usize)> = vec![(&mut exp, None, 0)];
+    while let Some((exp, sym, len)) = stack.pop() {
+        scope.truncate(len);
+        scope.extend(sym);
+        let len = scope.len();
+        match exp {
+            Exp::Var(Var::Idx(idx)) => {
+                if let Some(pos) = idx.position(len) {
+                    idx.sym = scope[pos].clone();
+                } // dangling, so reported below
+            }
+            Exp::App(app) => {
+                stack.push((&mut app.snd, None, len));
+                stack.push((&mut app.fst, None, len));
+            }
+            Exp::Abs(Abs { sym, typ, exp })
+            | Exp::Prd(Prd { sym, typ, exp })
+            | Exp::Sum(Sum { sym, typ, exp }) => {
+                stack.push((exp, Some(sym.clone()), len));
+                stack.push((typ, None, len));
+            }
+            Exp::Var(Var::Sym(_)) | Exp::Unv(_) => {} // constants refer to nothing
+        }
+    }
+    match exp.validate().into_iter().next() {
+        None => Ok(exp),
+        Some(ScopeErr::Dangling(path, _) | ScopeErr::Mismatch(path, ..)) => {
+            Err(DecodeErr::InvalidValue(
+                path.to_string(),
+                "an index that refers to an enclosing binder".to_string(),
+            ))
+        }
+    }
+}
+
+impl Core {
+    /// Decode source input to an [expression][Exp], together with its [location][Loc] and the
+    /// locations of its constituents.
+    pub fn decode_loc(&self, val: &str) -> Result<(Exp, Loc), DecodeErr> {
+        let mut spans = Spans::new(true);
+        let exp = ExpParser::new().parse(&mut spans, Lexer::new(val))?;
+        let loc = spans.finish().unwrap_or(Loc::new(0, val.len(), vec![])); // none left only if empty
+        Ok((resolve(exp)?, loc))
+    }
 }
 
 impl Default for Core {
@@ -105,36 +602,50 @@ impl Default for Core {
 
 impl Codec<String> for Core {
     fn encode(&self, exp: &Exp) -> String {
-        match exp {
-            Exp::Var(var) => match var {
-                Var::Sym(sym) => format!("{}", sym),
-                Var::Idx(idx) => {
-                    if self.show_indices {
-                        idx.val.to_string()
-                    } else {
-                        idx.sym.to_string()
-                    }
-                }
-            },
-            Exp::App(app) => self.fmt_app(app),
-            Exp::Abs(abs) => self.fmt_binder(abs),
-            Exp::Prd(prd) => self.fmt_binder(prd),
-            Exp::Sum(sum) => self.fmt_binder(sum),
-            Exp::Unv(unv) => format!("{}", unv),
-        }
+        self.fmt_exp(exp).render(self.width)
     }
 
+    /// Decode source input to an [expression][Exp], where bound variables may be written either
+    /// as symbols or as De Bruijn indices, which must refer to enclosing binders.
     fn decode(&self, val: &String) -> Result<Exp, DecodeErr> {
-        ExpParser::new()
-            .parse(Lexer::new(val.as_str()))
-            .map_err(|err| err.into())
+        let mut spans = Spans::new(false);
+        resolve(ExpParser::new().parse(&mut spans, Lexer::new(val.as_str()))?)
     }
 }
 
+/// Decodes source input in the core language that is known to be valid, within tests.
+#[cfg(test)]
+pub(crate) fn decode(val: &str) -> Exp {
+    Core::new().decode(&val.to_string()).unwrap()
+}
+
+/// Expressions in the core language that every codec should decode as it encodes them, which
+/// cover each kind of binder, nested applications, and shadowing, within tests.
+#[cfg(test)]
+pub(crate) const SAMPLES: [&str; 3] = [
+    "λa : □ . λx : a . Πy : a . Σz : (λb : □ . b) a . x",
+    "foo ((λbar : □ . bar) moo) baz",
+    "λx : □ . λx : □ . λy : □ . x y",
+];
+
+/// Nests an expression within a chain of λ-abstractions that all bind `x`, within tests.
+#[cfg(test)]
+pub(crate) fn binders(depth: usize, body: Exp) -> Exp {
+    (0..depth).fold(body, |exp, _| {
+        Exp::Abs(Abs {
+            sym: Sym::new("x"),
+            typ: Box::new(Exp::Unv(Unv::new())),
+            exp: Box::new(exp),
+        })
+    })
+}
+
 #[cfg(test)]
 mod test {
 
     use super::*;
+    use crate::ast::{Idx, Path, Step, Unv};
+    use crate::err::SystemErr;
 
     fn check(err: &mut Vec<String>, items: &Vec<&str>) {
         for val in items {
@@ -174,8 +685,221 @@ mod test {
             "λbar : Σf : char . f . λfoo : int . foo (bar moo)",
             "foo λbar : int . bar moo",
             "(λfoo : □ . bar) λmoo : □ . moo",
+            "foo (bar moo baz)",
+            "foo ((λbar : □ . bar) moo) baz",
         ];
         check(&mut err, &items);
         assert!(err.is_empty(), "checks failed:\n{}", err.join("\n"));
     }
+
+    #[test]
+    fn test_decode_indices() {
+        let val = "λa : □ . λx : a . Πy : a . Σz : (λb : □ . b) a . x";
+        let exp = Core::new().decode(&val.to_string()).unwrap();
+        assert_eq!(
+            Core::with_show_indices(true).encode(&exp),
+            "λa : □ . λx : 0 . Πy : 1 . Σz : (λb : □ . 0) 2 . 2"
+        );
+        let res = Core::new().decode(&Core::with_show_indices(true).encode(&exp));
+        assert_eq!(res, Ok(exp));
+        assert_eq!(
+            Core::new().decode(&"λa : □ . a 0 1".to_string()),
+            Err(DecodeErr::InvalidValue(
+                "/exp/snd".to_string(),
+                "an index that refers to an enclosing binder".to_string()
+            ))
+        );
+
+        let sym = Sym::new("x");
+        let exp = Exp::Abs(Abs {
+            sym: sym.clone(),
+            typ: Box::new(Exp::Unv(Unv::new())),
+            exp: Box::new(Exp::App(App::new(
+                Exp::Var(Var::Idx(Idx::new(&sym))),
+                Exp::Var(Var::Sym(sym)),
+            ))),
+        });
+        let val = Core::with_show_indices(true).encode(&exp);
+        assert_eq!(val, "λx1 : □ . 0 x"); // renamed, so as not to capture the free variable
+        assert_eq!(
+            Core::new().decode(&val).map(|res| res.alpha_eq(&exp)),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn test_decode_loc() {
+        let val = "λfoo : □ . foo (bar moo)";
+        let (exp, loc) = Core::new().decode_loc(val).unwrap();
+        assert_eq!(exp, Core::new().decode(&val.to_string()).unwrap());
+        assert_eq!((loc.sloc, loc.eloc, loc.sym), (0, val.len(), Some((2, 5))));
+        let path = |val: &str| val.parse::<Path>().unwrap();
+        assert_eq!(loc.find(0), path("/"));
+        assert_eq!(loc.find(8), path("/typ"));
+        assert_eq!(loc.find(14), path("/exp/fst"));
+        assert_eq!(loc.find(19), path("/exp/snd/fst"));
+        for pos in 0..val.len() {
+            let path = loc.find(pos);
+            assert!(
+                exp.get(&path).is_some() && loc.get(&path).is_some_and(|arg| arg.contains(pos))
+            );
+        }
+        let arg = loc.get(&path("/exp/snd")).unwrap();
+        assert_eq!(&val[arg.sloc..arg.eloc], "bar moo");
+        assert_eq!(loc.get(&path("/exp/snd/typ")), None);
+        assert_eq!(loc.get(&path("/exp/snd/fst/snd")), None);
+    }
+
+    #[test]
+    fn test_encode_width() {
+        let val = "λbar : float . λmoo : char . λfoo : int . foo (bar moo) (bar (moo foo))";
+        let exp = Core::new().decode(&val.to_string()).unwrap();
+        assert_eq!(Core::new().with_width(80).encode(&exp), val);
+        assert_eq!(
+            Core::new().with_width(40).encode(&exp),
+            "λbar : float .\nλmoo : char .\nλfoo : int .\n  foo (bar moo) (bar (moo foo))"
+        );
+        assert_eq!(
+            Core::new().with_width(20).encode(&exp),
+            "λbar : float .\nλmoo : char .\nλfoo : int .\n  foo\n    (bar moo)\n    (bar (moo foo))"
+        );
+    }
+
+    #[test]
+    fn test_decode_encode_width() {
+        let mut err = vec![];
+        let items = vec![
+            "λbar : Πf : int . f . λmoo : char . λfoo : int . foo (bar moo)",
+            "foo ((λbar : □ . bar) moo) baz",
+            "(λfoo : □ . bar) λmoo : □ . moo",
+        ];
+        for val in items {
+            let exp = Core::new().decode(&val.to_string()).unwrap();
+            for width in 0..val.len() {
+                let can = Core::new().with_width(width).encode(&exp);
+                if Core::new().decode(&can).as_ref() != Ok(&exp) {
+                    err.push(format!("round trip failed at width {}: {}", width, can));
+                }
+            }
+        }
+        assert!(err.is_empty(), "checks failed:\n{}", err.join("\n"));
+    }
+
+    #[test]
+    fn test_encode_shadowed() -> Result<(), SystemErr> {
+        let unv = || Exp::Unv(Unv::new());
+        let sym = Sym::new("x");
+        let idx = Idx::new(&sym).inc()?;
+        let o1 = Exp::Abs(Abs {
+            sym: sym.clone(),
+            typ: Box::new(unv()),
+            exp: Box::new(Exp::Abs(Abs {
+                sym: sym.clone(),
+                typ: Box::new(unv()),
+                exp: Box::new(Exp::Var(Var::Idx(idx))),
+            })),
+        });
+        assert_eq!(Core::new().encode(&o1), "λx : □ . λx1 : □ . x");
+
+        let o2 = Exp::Abs(Abs {
+            sym: sym.clone(),
+            typ: Box::new(unv()),
+            exp: Box::new(Exp::App(App::new(
+                Exp::Var(Var::Sym(sym.clone())),
+                Exp::Var(Var::Sym(Sym::new("x1"))),
+            ))),
+        });
+        assert_eq!(Core::new().encode(&o2), "λx2 : □ . x x1");
+
+        let o3 = Core::new().decode(&Core::new().encode(&o1)).unwrap();
+        assert_eq!(
+            Core::with_show_indices(true).encode(&o3),
+            "λx : □ . λx1 : □ . 1"
+        );
+
+        let o4 = Core::new().decode(&Core::new().encode(&o2)).unwrap();
+        assert_eq!(Core::with_show_indices(true).encode(&o4), "λx2 : □ . x x1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_repeated() {
+        let depth = 100_000;
+        let var = Exp::Var(Var::Idx(Idx {
+            val: depth - 1,
+            sym: Sym::new("x"),
+        }));
+        let exp = (0..depth).fold(var, |exp, _| {
+            Exp::Abs(Abs {
+                sym: Sym::new("x"),
+                typ: Box::new(Exp::Unv(Unv::new())),
+                exp: Box::new(exp),
+            })
+        });
+        let res = Core::new().encode(&exp);
+        let num = usize::try_from(depth - 1).unwrap();
+        assert_eq!(res, format!("λx : □ . {}x", "λx1 : □ . ".repeat(num)));
+        assert!(Core::new().decode(&res).unwrap().alpha_eq(&exp));
+    }
+
+    #[test]
+    fn test_encode_unshadowed() {
+        let val = "λx : □ . λx : □ . λy : □ . x y";
+        let exp = Core::new().decode(&val.to_string()).unwrap();
+        assert_eq!(Core::new().encode(&exp), val);
+    }
+
+    #[test]
+    fn test_encode_deep() {
+        let depth = 1_000_000;
+        let unv = || Box::new(Exp::Unv(Unv::new()));
+        let exp = (0..depth)
+            .rev()
+            .fold(Exp::Var(Var::Sym(Sym::new("y"))), |exp, num| {
+                let sym = Sym::new(&format!("x{}", num));
+                let idx = Exp::Var(Var::Idx(Idx::new(&sym)));
+                Exp::Abs(Abs {
+                    sym,
+                    typ: unv(),
+                    exp: Box::new(Exp::App(App::new(idx, exp))),
+                })
+            });
+        let res = Core::new().encode(&exp);
+        assert!(res.starts_with("λx0 : □ . x0 λx1 : □ . x1 λx2 : □ . x2 "));
+        assert!(res.ends_with(&format!("λx{0} : □ . x{0} y", depth - 1)));
+
+        let var = Exp::Var(Var::Idx(Idx {
+            val: depth - 1,
+            sym: Sym::new("x0"),
+        }));
+        let exp = (0..depth).rev().fold(var, |exp, num| {
+            let sym = Sym::new(&format!("x{}", num));
+            Exp::Abs(Abs {
+                sym,
+                typ: unv(),
+                exp: Box::new(exp),
+            })
+        });
+        let res = Core::new().with_width(80).encode(&exp);
+        assert!(res.starts_with("λx0 : □ .\nλx1 : □ .\n"));
+        assert!(res.lines().all(|line| line.chars().count() <= 80));
+        assert!(res.ends_with(&format!("λx{} : □ . x0", depth - 1)));
+
+        let var = |val: &str| Exp::Var(Var::Sym(Sym::new(val)));
+        let exp = (0..depth).fold(var("x"), |exp, _| Exp::App(App::new(var("f"), exp)));
+        let res = Core::new().encode(&exp);
+        let num = usize::try_from(depth - 1).unwrap();
+        assert_eq!(res, format!("{}f x{}", "f (".repeat(num), ")".repeat(num)));
+    }
+
+    #[test]
+    fn test_decode_deep() {
+        let num = 1_000_000;
+        let val = format!("{}f x{}", "f (".repeat(num), ")".repeat(num));
+        let (exp, loc) = Core::new().decode_loc(&val).unwrap();
+        let path = Path::from(vec![Step::Snd; 1_000_000]);
+        assert_eq!(loc.get(&path).map(|loc| loc.args.len()), Some(2));
+        assert_eq!(Core::new().encode(&exp), val);
+        assert_eq!(Core::new().decode(&val), Ok(exp));
+    }
 }
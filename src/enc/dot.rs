@@ -0,0 +1,114 @@
+//! Graphviz DOT encoding of the Abstract Syntax Tree (AST), for inspecting the binding structure
+//! of expressions.
+
+use crate::ast::{Binder, Exp, Var, Walk};
+use std::fmt::Write;
+
+/// Encoder of expressions as directed graphs, in the DOT language of Graphviz.
+///
+/// Each sub-expression is a node, labelled with its variant, and each constituent is linked from
+/// it by an edge labelled `fst`, `snd`, `typ` or `exp`, after the step that reaches it. Each bound
+/// variable is also linked by a dashed back-edge, labelled with its index, to the binder that the
+/// index refers to; a bound variable that refers to a binder outside of the expression has no
+/// back-edge, and is drawn in red instead.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Dot;
+
+impl Dot {
+    /// Create a new instance of the encoder.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Encode an [expression][Exp] as a DOT graph.
+    ///
+    /// Nodes are numbered in pre-order, starting with the expression itself as `n0`.
+    pub fn encode(&self, exp: &Exp) -> String {
+        let mut res = "digraph exp {\n    node [fontname=\"monospace\"];\n".to_string();
+        for site in Walk::new(exp) {
+            let (label, attrs) = match site.exp {
+                Exp::Var(Var::Sym(sym)) => (sym.to_string(), ""),
+                Exp::Var(Var::Idx(idx)) => {
+                    let label = format!("{} #{}", idx.sym, idx.val);
+                    match site.binder {
+                        Some(_) => (label, ""),
+                        None => (label, ", color=red, fontcolor=red"), // dangling
+                    }
+                }
+                Exp::App(_) => ("app".to_string(), ", shape=circle"),
+                Exp::Abs(abs) => (format!("{}{}", abs.prefix(), abs.sym), ", shape=box"),
+                Exp::Prd(prd) => (format!("{}{}", prd.prefix(), prd.sym), ", shape=box"),
+                Exp::Sum(sum) => (format!("{}{}", sum.prefix(), sum.sym), ", shape=box"),
+                Exp::Unv(unv) => (unv.to_string(), ", shape=plaintext"),
+            };
+            let node = site.id;
+            let _ = writeln!(
+                res,
+                "    n{} [label=\"{}\"{}];",
+                node,
+                escape(&label),
+                attrs
+            );
+            if let Some((parent, step)) = site.parent {
+                let _ = writeln!(res, "    n{} -> n{} [label=\"{}\"];", parent, node, step);
+            }
+            if let (Exp::Var(Var::Idx(idx)), Some(binder)) = (site.exp, site.binder) {
+                let _ = writeln!(
+                    res,
+                    "    n{} -> n{} [style=dashed, constraint=false, label=\"{}\"];",
+                    node, binder, idx.val
+                );
+            }
+        }
+        res.push_str("}\n");
+        res
+    }
+}
+
+/// Escapes the characters that are special within a quoted DOT string.
+fn escape(val: &str) -> String {
+    val.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::ast::{Idx, Sym};
+    use crate::enc::core::par::decode;
+
+    #[test]
+    fn test_encode() {
+        let res = Dot.encode(&decode("λx : □ . f x"));
+        let exp = [
+            "digraph exp {",
+            "    node [fontname=\"monospace\"];",
+            "    n0 [label=\"λx\", shape=box];",
+            "    n1 [label=\"□\", shape=plaintext];",
+            "    n0 -> n1 [label=\"typ\"];",
+            "    n2 [label=\"app\", shape=circle];",
+            "    n0 -> n2 [label=\"exp\"];",
+            "    n3 [label=\"f\"];",
+            "    n2 -> n3 [label=\"fst\"];",
+            "    n4 [label=\"x #0\"];",
+            "    n2 -> n4 [label=\"snd\"];",
+            "    n4 -> n0 [style=dashed, constraint=false, label=\"0\"];",
+            "}",
+            "",
+        ]
+        .join("\n");
+        assert_eq!(res, exp);
+    }
+
+    #[test]
+    fn test_encode_binding() {
+        let res = Dot.encode(&decode("Πa : □ . Σb : a . λc : b . a"));
+        assert!(res.contains("n3 -> n0 [style=dashed, constraint=false, label=\"0\"];")); // type of b
+        assert!(res.contains("n5 -> n2 [style=dashed, constraint=false, label=\"0\"];")); // type of c
+        assert!(res.contains("n6 -> n0 [style=dashed, constraint=false, label=\"2\"];"));
+
+        let res = Dot.encode(&Exp::Var(Var::Idx(Idx::new(&Sym::new("x")))));
+        assert!(res.contains("n0 [label=\"x #0\", color=red, fontcolor=red];"));
+        assert!(!res.contains("style=dashed"));
+    }
+}
@@ -0,0 +1,252 @@
+//! Graph encoding of the Abstract Syntax Tree (AST), as arrays for graph neural networks.
+//!
+//! Each sub-expression is a node, and each node has two features: its [kind][NODE_APP] and a
+//! value. The value is the ID of the symbol within a [vocabulary][Vocab] for a free variable or a
+//! binder, the index for a bound variable, the level for a universe, and 0 for an application.
+//!
+//! Syntax edges link each expression to its constituents, and binding edges link each binder to
+//! the bound variables that refer to it, each with an [edge kind][EDGE_BIND]. Arrays are written
+//! as NumPy `.npy` files of 64-bit integers, with edges in the `(2, E)` layout that is usual for
+//! graph neural networks.
+
+use super::tok::Vocab;
+use crate::ast::{Exp, Step, Var, Walk};
+use std::fs::File;
+use std::io::{BufWriter, Result, Write};
+use std::path::Path;
+
+/// Kind of a node for a free variable.
+pub const NODE_SYM: i64 = 0;
+/// Kind of a node for a bound variable.
+pub const NODE_IDX: i64 = 1;
+/// Kind of a node for an application.
+pub const NODE_APP: i64 = 2;
+/// Kind of a node for a λ-abstraction.
+pub const NODE_ABS: i64 = 3;
+/// Kind of a node for a Π-type.
+pub const NODE_PRD: i64 = 4;
+/// Kind of a node for a Σ-type.
+pub const NODE_SUM: i64 = 5;
+/// Kind of a node for a universe.
+pub const NODE_UNV: i64 = 6;
+
+/// Kind of an edge from an application to its first constituent.
+pub const EDGE_FST: i64 = 0;
+/// Kind of an edge from an application to its second constituent.
+pub const EDGE_SND: i64 = 1;
+/// Kind of an edge from a binder to its type.
+pub const EDGE_TYP: i64 = 2;
+/// Kind of an edge from a binder to its body.
+pub const EDGE_EXP: i64 = 3;
+/// Kind of an edge from a binder to a bound variable that refers to it.
+pub const EDGE_BIND: i64 = 4;
+
+/// Graph of one or more expressions, as arrays.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub struct Graph {
+    /// Kind and value of each node, with the nodes of each expression in pre-order.
+    pub nodes: Vec<[i64; 2]>,
+    /// Source and target node of each edge.
+    pub edges: Vec<[i64; 2]>,
+    /// Kind of each edge, in the same order.
+    pub kinds: Vec<i64>,
+    /// Position of the expression, within a batch, that each node belongs to.
+    pub batch: Vec<i64>,
+}
+
+/// Encoder of expressions as [graphs][Graph].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Gnn {
+    /// Vocabulary for the symbols of free variables and binders.
+    vocab: Vocab,
+}
+
+impl Gnn {
+    /// Create a new instance of the encoder, with a vocabulary for symbols.
+    pub fn new(vocab: Vocab) -> Self {
+        Self { vocab }
+    }
+
+    /// Encode an [expression][Exp] as a graph.
+    pub fn encode(&self, exp: &Exp) -> Graph {
+        let mut res = Graph::default();
+        self.append(exp, 0, &mut res);
+        res
+    }
+
+    /// Encode a batch of expressions as a single graph, which is the disjoint union of the graph of
+    /// each expression, in order.
+    pub fn encode_batch<'a, I: IntoIterator<Item = &'a Exp>>(&self, exps: I) -> Graph {
+        let mut res = Graph::default();
+        for (pos, exp) in exps.into_iter().enumerate() {
+            self.append(exp, pos as i64, &mut res);
+        }
+        res
+    }
+
+    /// Appends the nodes and edges of an expression to a graph, as the expression at the supplied
+    /// position within the batch.
+    fn append(&self, exp: &Exp, pos: i64, res: &mut Graph) {
+        let base = res.nodes.len() as i64;
+        for site in Walk::new(exp) {
+            let node = base + site.id as i64;
+            if let Some((parent, step)) = site.parent {
+                let kind = match step {
+                    Step::Fst => EDGE_FST,
+                    Step::Snd => EDGE_SND,
+                    Step::Typ => EDGE_TYP,
+                    Step::Exp => EDGE_EXP,
+                };
+                res.edges.push([base + parent as i64, node]);
+                res.kinds.push(kind);
+            }
+            if let Some(binder) = site.binder {
+                res.edges.push([base + binder as i64, node]);
+                res.kinds.push(EDGE_BIND);
+            }
+
+            let feat = match site.exp {
+                Exp::Var(Var::Sym(sym)) => [NODE_SYM, i64::from(self.vocab.sym_id(sym))],
+                Exp::Var(Var::Idx(idx)) => [NODE_IDX, clamp(idx.val)],
+                Exp::App(_) => [NODE_APP, 0],
+                Exp::Abs(abs) => [NODE_ABS, i64::from(self.vocab.sym_id(&abs.sym))],
+                Exp::Prd(prd) => [NODE_PRD, i64::from(self.vocab.sym_id(&prd.sym))],
+                Exp::Sum(sum) => [NODE_SUM, i64::from(self.vocab.sym_id(&sum.sym))],
+                Exp::Unv(unv) => [NODE_UNV, clamp(unv.level)],
+            };
+            res.nodes.push(feat);
+            res.batch.push(pos);
+        }
+    }
+}
+
+impl Graph {
+    /// Writes the arrays of the graph as NumPy `.npy` files within a directory: `nodes.npy` of
+    /// shape `(N, 2)`, `edges.npy` of shape `(2, E)`, `kinds.npy` of shape `(E,)` and `batch.npy`
+    /// of shape `(N,)`.
+    pub fn write(&self, dir: &Path) -> Result<()> {
+        let nodes: Vec<i64> = self.nodes.iter().flatten().copied().collect();
+        let edges: Vec<i64> = (0..2)
+            .flat_map(|pos| self.edges.iter().map(move |edge| edge[pos]))
+            .collect();
+        let items = [
+            ("nodes.npy", vec![self.nodes.len(), 2], nodes),
+            ("edges.npy", vec![2, self.edges.len()], edges),
+            ("kinds.npy", vec![self.kinds.len()], self.kinds.clone()),
+            ("batch.npy", vec![self.batch.len()], self.batch.clone()),
+        ];
+        for (name, shape, data) in items {
+            let mut out = BufWriter::new(File::create(dir.join(name))?);
+            write_npy(&mut out, &shape, &data)?;
+            out.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes an array of 64-bit integers, in row-major order with the supplied shape, in the NumPy
+/// `.npy` format (version 1.0).
+pub fn write_npy<W: Write>(out: &mut W, shape: &[usize], data: &[i64]) -> Result<()> {
+    let shape = match shape {
+        [len] => format!("({},)", len),
+        shape => {
+            let dims: Vec<String> = shape.iter().map(|dim| dim.to_string()).collect();
+            format!("({})", dims.join(", "))
+        }
+    };
+    let mut header = format!(
+        "{{'descr': '<i8', 'fortran_order': False, 'shape': {}, }}",
+        shape
+    );
+    // the magic string, version and length take 10 bytes, and the data starts 64-byte aligned
+    let len = (10 + header.len() + 1).div_ceil(64) * 64 - 10;
+    header.push_str(&" ".repeat(len - header.len() - 1));
+    header.push('\n');
+
+    out.write_all(b"\x93NUMPY\x01\x00")?;
+    out.write_all(&(len as u16).to_le_bytes())?;
+    out.write_all(header.as_bytes())?;
+    for val in data {
+        out.write_all(&val.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Converts a value to a 64-bit integer, saturating at [i64::MAX].
+fn clamp(val: u64) -> i64 {
+    i64::try_from(val).unwrap_or(i64::MAX)
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::ast::Sym;
+    use crate::enc::core::par::decode;
+
+    fn gnn() -> Gnn {
+        Gnn::new(Vocab::with_indices(&[Sym::new("f"), Sym::new("x")], 4))
+    }
+
+    #[test]
+    fn test_encode() {
+        let res = gnn().encode(&decode("λx : □ . f x"));
+        assert_eq!(
+            res.nodes,
+            vec![
+                [NODE_ABS, 12],
+                [NODE_UNV, 0],
+                [NODE_APP, 0],
+                [NODE_SYM, 11],
+                [NODE_IDX, 0]
+            ]
+        );
+        assert_eq!(res.edges, vec![[0, 1], [0, 2], [2, 3], [2, 4], [0, 4]]);
+        assert_eq!(
+            res.kinds,
+            vec![EDGE_TYP, EDGE_EXP, EDGE_FST, EDGE_SND, EDGE_BIND]
+        );
+        assert_eq!(res.batch, vec![0; 5]);
+    }
+
+    #[test]
+    fn test_encode_batch() {
+        let exps = [
+            decode("λx : □ . x"),
+            decode("f"),
+            decode("Πa : □ . Σb : a . a"),
+        ];
+        let res = gnn().encode_batch(&exps);
+        assert_eq!(res.batch, vec![0, 0, 0, 1, 2, 2, 2, 2, 2]);
+        assert_eq!(res.edges[..3], [[0, 1], [0, 2], [0, 2]]); // the first expression
+        assert_eq!(
+            res.edges[3..],
+            [[4, 5], [4, 6], [6, 7], [4, 7], [6, 8], [4, 8]]
+        );
+    }
+
+    #[test]
+    fn test_write_npy() {
+        let mut res = vec![];
+        write_npy(&mut res, &[2, 3], &[1, 2, 3, 4, 5, -1]).unwrap();
+        assert_eq!(&res[..8], b"\x93NUMPY\x01\x00");
+        let len = u16::from_le_bytes([res[8], res[9]]) as usize;
+        assert_eq!((10 + len) % 64, 0);
+        let header = std::str::from_utf8(&res[10..10 + len]).unwrap();
+        assert_eq!(
+            header.trim_end(),
+            "{'descr': '<i8', 'fortran_order': False, 'shape': (2, 3), }"
+        );
+        assert!(header.ends_with('\n'));
+        assert_eq!(res.len(), 10 + len + 6 * 8);
+        assert_eq!(res[10 + len..18 + len], 1i64.to_le_bytes());
+        assert_eq!(res[res.len() - 8..], (-1i64).to_le_bytes());
+
+        let mut res = vec![];
+        write_npy(&mut res, &[0], &[]).unwrap();
+        assert!(std::str::from_utf8(&res[10..])
+            .unwrap()
+            .contains("'shape': (0,)"));
+    }
+}
@@ -0,0 +1,142 @@
+//! HTML encoding of the Abstract Syntax Tree (AST), for presenting expressions on web pages.
+
+use super::core::lex::fmt_level;
+use super::core::par::{Core, Markup};
+use crate::ast::{Exp, Sym, Unv};
+
+/// Encoder of expressions as syntax-highlighted HTML.
+///
+/// Expressions are laid out and parenthesized exactly as in the [core language][Core], within a
+/// `<code class="fx-exp">` element. Each constituent is wrapped in an element whose class names
+/// its role: `fx-kw` for keywords and universes, `fx-punct` for punctuation, `fx-free` for free
+/// variables, `fx-bnd` for the symbols of binders, and `fx-var` for bound variables.
+///
+/// The symbol of each binder has a distinct `id`, and each bound variable links to the binder that
+/// it refers to. Both also carry a `data-ref` attribute with that `id`, so that a stylesheet or
+/// script is able to highlight a binder together with its occurrences on hover.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Html {
+    /// Prefix of the `id` of each binder, which distinguishes expressions on the same page.
+    prefix: String,
+}
+
+impl Html {
+    /// Create a new instance of the encoder, with `fx` as the prefix for identifiers.
+    pub fn new() -> Self {
+        Self::with_prefix("fx")
+    }
+
+    /// Create a new instance of the encoder with a prefix for the `id` of each binder, which must
+    /// differ between expressions that are presented on the same page.
+    pub fn with_prefix(prefix: &str) -> Self {
+        Self {
+            prefix: escape(prefix),
+        }
+    }
+
+    /// Encode an [expression][Exp] as HTML, on a single line.
+    pub fn encode(&self, exp: &Exp) -> String {
+        let doc = Core::new().fmt_markup(exp, self);
+        format!("<code class=\"fx-exp\">{}</code>", doc.render(usize::MAX))
+    }
+}
+
+impl Default for Html {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Markup for Html {
+    fn free(&self, sym: &Sym) -> String {
+        format!("<span class=\"fx-free\">{}</span>", escape(&sym.val))
+    }
+
+    fn bound(&self, sym: &Sym, id: usize) -> String {
+        format!(
+            "<a class=\"fx-var\" href=\"#{p}-{id}\" data-ref=\"{p}-{id}\">{}</a>",
+            escape(&sym.val),
+            p = self.prefix,
+            id = id
+        )
+    }
+
+    fn binder(&self, prefix: &str, sym: &Sym, id: usize) -> String {
+        format!(
+            "<span class=\"fx-kw\">{}</span><span class=\"fx-bnd\" id=\"{p}-{id}\" data-ref=\"{p}-{id}\">{}</span> <span class=\"fx-punct\">:</span> ",
+            escape(prefix),
+            escape(&sym.val),
+            p = self.prefix,
+            id = id
+        )
+    }
+
+    fn unv(&self, unv: &Unv) -> String {
+        format!("<span class=\"fx-kw\">{}</span>", fmt_level(unv.level))
+    }
+
+    fn punct(&self, val: &str) -> String {
+        let (space, val) = match val.strip_prefix(' ') {
+            Some(val) => (" ", val),
+            None => ("", val),
+        };
+        format!("{}<span class=\"fx-punct\">{}</span>", space, escape(val))
+    }
+}
+
+/// Escapes the characters that are special to HTML, within both text and attribute values.
+fn escape(val: &str) -> String {
+    let mut res = String::new();
+    for c in val.chars() {
+        match c {
+            '&' => res.push_str("&amp;"),
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '"' => res.push_str("&quot;"),
+            '\'' => res.push_str("&#39;"),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::enc::core::par::decode;
+
+    #[test]
+    fn test_encode() {
+        let res = Html::new().encode(&decode("λx : □ . (λy : □ . x) f"));
+        let exp = [
+            "<code class=\"fx-exp\">",
+            "<span class=\"fx-kw\">λ</span>",
+            "<span class=\"fx-bnd\" id=\"fx-0\" data-ref=\"fx-0\">x</span> ",
+            "<span class=\"fx-punct\">:</span> <span class=\"fx-kw\">□</span> ",
+            "<span class=\"fx-punct\">.</span> ",
+            "<span class=\"fx-punct\">(</span><span class=\"fx-kw\">λ</span>",
+            "<span class=\"fx-bnd\" id=\"fx-1\" data-ref=\"fx-1\">y</span> ",
+            "<span class=\"fx-punct\">:</span> <span class=\"fx-kw\">□</span> ",
+            "<span class=\"fx-punct\">.</span> ",
+            "<a class=\"fx-var\" href=\"#fx-0\" data-ref=\"fx-0\">x</a>",
+            "<span class=\"fx-punct\">)</span> <span class=\"fx-free\">f</span></code>",
+        ]
+        .concat();
+        assert_eq!(res, exp);
+    }
+
+    #[test]
+    fn test_encode_unv() {
+        let res = Html::new().encode(&decode("λx : □₁₂ . □"));
+        assert!(res.contains("<span class=\"fx-kw\">□₁₂</span>"));
+        assert!(res.contains("<span class=\"fx-kw\">□</span>"));
+    }
+
+    #[test]
+    fn test_encode_prefix() {
+        let res = Html::with_prefix("a\"b").encode(&decode("λx : □ . x"));
+        assert!(res.contains("id=\"a&quot;b-0\""));
+        assert!(res.contains("href=\"#a&quot;b-0\""));
+    }
+}
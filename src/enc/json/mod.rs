@@ -0,0 +1,407 @@
+//! JSON encoding of the Abstract Syntax Tree (AST), for exchanging expressions with tools that are
+//! not written in Rust.
+//!
+//! Each expression is encoded as an object whose `kind` field names its variant: `var`, `app`,
+//! `abs`, `prd`, `sum` or `unv`. The constituents of an application are its `fst` and `snd` fields,
+//! and those of a binder are its `typ` and `exp` fields, so that the location of a constituent is
+//! the same as its [path][crate::ast::Path]. A bound variable keeps the symbol of the binder that
+//! it refers to alongside its index, in the `sym` and `idx` fields. The level of a universe, and the
+//! span of any expression within its source, are optional. The encoding is described in full by
+//! the JSON Schema in [SCHEMA].
+//!
+//! This module is only available with the `json` feature, which is not enabled by default.
+//!
+//! For example, `λx : □ . f x` is encoded as:
+//!
+//! ```json
+//! {
+//!   "kind": "abs",
+//!   "sym": "x",
+//!   "typ": { "kind": "unv", "level": 0 },
+//!   "exp": {
+//!     "kind": "app",
+//!     "fst": { "kind": "var", "sym": "f" },
+//!     "snd": { "kind": "var", "sym": "x", "idx": 0 }
+//!   }
+//! }
+//! ```
+
+use super::core::loc::Loc;
+use super::Codec;
+use crate::ast::{Abs, App, Exp, Idx, Prd, Sum, Sym, Unv, Var};
+use crate::err::{DecodeErr, ScopeErr};
+use serde_json::{Map, Value};
+
+/// JSON Schema that describes the encoding.
+pub const SCHEMA: &str = include_str!("schema.json");
+
+/// Codec for the JSON encoding of the AST.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Json;
+
+/// Pending step in encoding an expression.
+enum Enc<'a> {
+    /// Encode an expression with its location, if known, and push the result.
+    Exp(&'a Exp, Option<&'a Loc>),
+    /// Pop the encoded constituents of an expression, and push the expression.
+    Build(&'a Exp, Option<&'a Loc>),
+}
+
+/// Pending step in decoding an expression.
+enum Dec<'a> {
+    /// Decode a value, reached by the supplied field from the value at the supplied depth, and push
+    /// the result.
+    Val(&'a Value, Option<&'static str>, usize),
+    /// Pop the decoded constituents of an expression of the supplied kind, and push the
+    /// expression.
+    Build(&'a str, Option<Sym>),
+}
+
+impl Json {
+    /// Create a new instance of the codec.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Encode an [expression][Exp], together with the spans of it and its constituents within the
+    /// source input that it was decoded from by the [core language][crate::enc::core::par::Core].
+    ///
+    /// The same limit on depth applies as to [Json::encode]; see [dismantle].
+    pub fn encode_loc(&self, exp: &Exp, loc: &Loc) -> Value {
+        self.fmt_exp(exp, Some(loc))
+    }
+
+    /// Encode an expression, together with its location and those of its constituents, if known.
+    fn fmt_exp(&self, exp: &Exp, loc: Option<&Loc>) -> Value {
+        let mut tasks = vec![Enc::Exp(exp, loc)];
+        let mut res: Vec<Value> = vec![];
+        while let Some(task) = tasks.pop() {
+            match task {
+                Enc::Exp(Exp::Var(var), loc) => res.push(match var {
+                    Var::Sym(sym) => object("var", [("sym", sym.val.as_str().into())], loc),
+                    Var::Idx(idx) => object(
+                        "var",
+                        [
+                            ("sym", idx.sym.val.as_str().into()),
+                            ("idx", idx.val.into()),
+                        ],
+                        loc,
+                    ),
+                }),
+                Enc::Exp(Exp::Unv(unv), loc) => {
+                    res.push(object("unv", [("level", unv.level.into())], loc))
+                }
+                Enc::Exp(exp, loc) => {
+                    let (fst, snd) = match exp {
+                        Exp::App(App { fst, snd }) => (fst, snd),
+                        Exp::Abs(Abs { typ, exp, .. })
+                        | Exp::Prd(Prd { typ, exp, .. })
+                        | Exp::Sum(Sum { typ, exp, .. }) => (typ, exp),
+                        Exp::Var(_) | Exp::Unv(_) => unreachable!("atomic expressions are encoded"),
+                    };
+                    let arg = |pos| loc.and_then(|loc: &Loc| loc.args.get(pos));
+                    tasks.push(Enc::Build(exp, loc));
+                    tasks.push(Enc::Exp(snd, arg(1)));
+                    tasks.push(Enc::Exp(fst, arg(0)));
+                }
+                Enc::Build(exp, loc) => {
+                    let snd = res.pop().unwrap_or_default();
+                    let fst = res.pop().unwrap_or_default();
+                    let sym = match exp {
+                        Exp::Abs(Abs { sym, .. }) => Some(("abs", sym)),
+                        Exp::Prd(Prd { sym, .. }) => Some(("prd", sym)),
+                        Exp::Sum(Sum { sym, .. }) => Some(("sum", sym)),
+                        _ => None,
+                    };
+                    res.push(match sym {
+                        Some((kind, sym)) => {
+                            let sym = sym.val.as_str().into();
+                            object(kind, [("sym", sym), ("typ", fst), ("exp", snd)], loc)
+                        }
+                        None => object("app", [("fst", fst), ("snd", snd)], loc),
+                    });
+                }
+            }
+        }
+        res.pop().unwrap_or_default()
+    }
+
+    /// Decode a value, reporting the location of the first field that is invalid.
+    fn parse_val(&self, val: &Value) -> Result<Exp, DecodeErr> {
+        let mut tasks = vec![Dec::Val(val, None, 0)];
+        let mut path: Vec<&str> = vec![];
+        let mut res: Vec<Exp> = vec![];
+        while let Some(task) = tasks.pop() {
+            let (val, step, len) = match task {
+                Dec::Val(val, step, len) => (val, step, len),
+                Dec::Build(kind, sym) => {
+                    let exp = Box::new(res.pop().unwrap_or(Exp::Unv(Unv::new())));
+                    let fst = Box::new(res.pop().unwrap_or(Exp::Unv(Unv::new())));
+                    res.push(match (kind, sym) {
+                        ("abs", Some(sym)) => Exp::Abs(Abs { sym, typ: fst, exp }),
+                        ("prd", Some(sym)) => Exp::Prd(Prd { sym, typ: fst, exp }),
+                        ("sum", Some(sym)) => Exp::Sum(Sum { sym, typ: fst, exp }),
+                        _ => Exp::App(App { fst, snd: exp }),
+                    });
+                    continue;
+                }
+            };
+            path.truncate(len);
+            path.extend(step);
+            let len = path.len();
+            let err = |field: Option<&str>, expected: &str| {
+                let loc = location(path.iter().copied().chain(field));
+                DecodeErr::InvalidValue(loc, expected.to_string())
+            };
+            let obj = val.as_object().ok_or_else(|| err(None, "an object"))?;
+            let kind = obj
+                .get("kind")
+                .and_then(Value::as_str)
+                .ok_or_else(|| err(Some("kind"), "a kind of expression"))?;
+            let fields: &[&str] = match kind {
+                "var" => &["kind", "sym", "idx", "span"],
+                "app" => &["kind", "fst", "snd", "span"],
+                "abs" | "prd" | "sum" => &["kind", "sym", "typ", "exp", "span"],
+                "unv" => &["kind", "level", "span"],
+                _ => return Err(err(Some("kind"), "one of: var, app, abs, prd, sum, unv")),
+            };
+            if let Some(key) = obj.keys().find(|key| !fields.contains(&key.as_str())) {
+                return Err(err(Some(key), "no such field"));
+            }
+            if let Some(span) = obj.get("span") {
+                match span.as_array().map(|span| span.as_slice()) {
+                    Some([sloc, eloc]) if sloc.is_u64() && eloc.is_u64() => {}
+                    _ => return Err(err(Some("span"), "a pair of locations")),
+                }
+            }
+            let sym = match obj.get("sym") {
+                Some(val) => match val.as_str() {
+                    Some(sym) if Sym::is_valid(sym) => Some(Sym::new(sym)),
+                    _ => return Err(err(Some("sym"), "a symbol")),
+                },
+                None => None,
+            };
+            let num = |field| match obj.get(field) {
+                Some(val) => val
+                    .as_u64()
+                    .map(Some)
+                    .ok_or_else(|| err(Some(field), "a non-negative integer")),
+                None => Ok(None),
+            };
+            let sub = |field| {
+                obj.get(field)
+                    .map(|val| Dec::Val(val, Some(field), len))
+                    .ok_or_else(|| err(Some(field), "an expression"))
+            };
+            match kind {
+                "var" => {
+                    let sym = sym.ok_or_else(|| err(Some("sym"), "a symbol"))?;
+                    res.push(Exp::Var(match num("idx")? {
+                        Some(val) => Var::Idx(Idx { val, sym }),
+                        None => Var::Sym(sym),
+                    }));
+                }
+                "unv" => {
+                    let level = num("level")?.unwrap_or_default();
+                    res.push(Exp::Unv(Unv { level }));
+                }
+                "app" => {
+                    let (fst, snd) = (sub("fst")?, sub("snd")?);
+                    tasks.extend([Dec::Build(kind, None), snd, fst]);
+                }
+                _ => {
+                    let sym = sym.ok_or_else(|| err(Some("sym"), "a symbol"))?;
+                    let (typ, exp) = (sub("typ")?, sub("exp")?);
+                    tasks.extend([Dec::Build(kind, Some(sym)), exp, typ]);
+                }
+            }
+        }
+        res.pop()
+            .ok_or_else(|| DecodeErr::InvalidValue(location([]), "an expression".to_string()))
+    }
+}
+
+/// Drops a value using an explicit stack, for values nested too deeply to be dropped recursively,
+/// such as the encodings of deep expressions.
+pub fn dismantle(val: Value) {
+    let mut stack = vec![val];
+    while let Some(val) = stack.pop() {
+        match val {
+            Value::Object(obj) => stack.extend(obj.into_iter().map(|(_, val)| val)),
+            Value::Array(arr) => stack.extend(arr),
+            _ => {}
+        }
+    }
+}
+
+/// Creates an object that encodes an expression of the supplied kind, with the supplied fields
+/// and span.
+fn object<const N: usize>(kind: &str, fields: [(&str, Value); N], loc: Option<&Loc>) -> Value {
+    let mut res = Map::new();
+    res.insert("kind".to_string(), kind.into());
+    for (key, val) in fields {
+        res.insert(key.to_string(), val);
+    }
+    if let Some(loc) = loc {
+        res.insert("span".to_string(), Value::from(vec![loc.sloc, loc.eloc]));
+    }
+    Value::Object(res)
+}
+
+/// Returns the location of a value reached by the supplied fields, in the same format as a
+/// [path][crate::ast::Path].
+fn location<'a, I: IntoIterator<Item = &'a str>>(fields: I) -> String {
+    let res: String = fields
+        .into_iter()
+        .map(|field| format!("/{}", field))
+        .collect();
+    if res.is_empty() {
+        "/".to_string()
+    } else {
+        res
+    }
+}
+
+impl Codec<Value> for Json {
+    /// Encode an [expression][Exp] as a value.
+    ///
+    /// <p style="background: rgba(255, 181, 77, 0.16); padding: 0.75em">
+    /// <strong>Warning:</strong> A [Value] is dropped, serialized and compared recursively, so the
+    /// value of an expression nested thousands of levels deep may overflow the stack unless it is
+    /// dropped with [dismantle]; the expression itself is encoded without recursion.
+    /// </p>
+    fn encode(&self, exp: &Exp) -> Value {
+        self.fmt_exp(exp, None)
+    }
+
+    /// Decode a value to an [expression][Exp], which must also be [well-scoped][Exp::validate].
+    ///
+    /// Any span within the value is checked, but otherwise ignored.
+    fn decode(&self, val: &Value) -> Result<Exp, DecodeErr> {
+        let res = self.parse_val(val)?;
+        match res.validate().into_iter().next() {
+            None => Ok(res),
+            Some(ScopeErr::Dangling(path, _)) => Err(DecodeErr::InvalidValue(
+                path.to_string(),
+                "an index that refers to an enclosing binder".to_string(),
+            )),
+            Some(ScopeErr::Mismatch(path, _, sym)) => Err(DecodeErr::InvalidValue(
+                path.to_string(),
+                format!("the symbol of the binder it refers to: {}", sym),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::enc::core::par::{binders, decode, Core, SAMPLES};
+    use serde_json::json;
+
+    #[test]
+    fn test_encode() {
+        let res = Json.encode(&decode("λx : □ . f x"));
+        let exp = json!({
+            "kind": "abs",
+            "sym": "x",
+            "typ": { "kind": "unv", "level": 0 },
+            "exp": {
+                "kind": "app",
+                "fst": { "kind": "var", "sym": "f" },
+                "snd": { "kind": "var", "sym": "x", "idx": 0 }
+            }
+        });
+        assert_eq!(res, exp);
+        assert_eq!(Json.decode(&res), Ok(decode("λx : □ . f x")));
+    }
+
+    #[test]
+    fn test_encode_loc() {
+        let val = "λx : □ . f x";
+        let (exp, loc) = Core::new().decode_loc(val).unwrap();
+        let res = Json.encode_loc(&exp, &loc);
+        assert_eq!(res["span"], json!([0, val.len()]));
+        assert_eq!(res["exp"]["snd"]["span"], json!([val.len() - 1, val.len()]));
+        assert_eq!(Json.decode(&res), Ok(exp));
+    }
+
+    #[test]
+    fn test_decode_encode() {
+        for val in SAMPLES {
+            let exp = decode(val);
+            assert_eq!(Json.decode(&Json.encode(&exp)), Ok(exp));
+        }
+        let res = Json.decode(&json!({"kind": "unv"}));
+        assert_eq!(res, Ok(Exp::Unv(Unv::new()))); // level is optional
+    }
+
+    #[test]
+    fn test_encode_deep() {
+        let exp = binders(100_000, Exp::Var(Var::Idx(Idx::new(&Sym::new("x")))));
+        let val = Json.encode(&exp);
+        assert_eq!(Json.decode(&val), Ok(exp));
+        dismantle(val);
+    }
+
+    #[test]
+    fn test_decode_err() {
+        let err = |loc: &str, expected: &str| {
+            Err(DecodeErr::InvalidValue(
+                loc.to_string(),
+                expected.to_string(),
+            ))
+        };
+        let var = json!({"kind": "var", "sym": "x", "idx": 0});
+        let abs =
+            |exp: &Value| json!({"kind": "abs", "sym": "y", "typ": {"kind": "unv"}, "exp": exp});
+        assert_eq!(Json.decode(&json!([])), err("/", "an object"));
+        assert_eq!(
+            Json.decode(&json!({"kind": "lam"})),
+            err("/kind", "one of: var, app, abs, prd, sum, unv")
+        );
+        assert_eq!(
+            Json.decode(&json!({"kind": "app", "fst": var})),
+            err("/snd", "an expression")
+        );
+        assert_eq!(
+            Json.decode(&abs(&json!({"kind": "unv", "lvl": 1}))),
+            err("/exp/lvl", "no such field")
+        );
+        assert_eq!(
+            Json.decode(&json!({"kind": "var", "sym": "Foo"})),
+            err("/sym", "a symbol")
+        );
+        assert_eq!(
+            Json.decode(&abs(&json!({"kind": "var", "sym": "x", "idx": -1}))),
+            err("/exp/idx", "a non-negative integer")
+        );
+        assert_eq!(
+            Json.decode(&abs(&json!({"kind": "unv", "span": [0]}))),
+            err("/exp/span", "a pair of locations")
+        );
+        assert_eq!(
+            Json.decode(&var),
+            err("/", "an index that refers to an enclosing binder")
+        );
+        assert_eq!(
+            Json.decode(&abs(&var)),
+            err("/exp", "the symbol of the binder it refers to: y")
+        );
+    }
+
+    #[test]
+    fn test_schema() {
+        let schema: Value = serde_json::from_str(SCHEMA).unwrap();
+        let defs = &schema["$defs"];
+        for kind in ["var", "app", "unv"] {
+            assert_eq!(defs[kind]["properties"]["kind"]["const"], kind);
+        }
+        assert_eq!(
+            defs["binder"]["properties"]["kind"]["enum"],
+            json!(["abs", "prd", "sum"])
+        );
+        assert_eq!(defs["sym"]["pattern"], "^[a-z][a-z0-9_]*$");
+    }
+}
@@ -12,4 +12,14 @@ pub trait Codec<T> {
     fn decode(&self, val: &T) -> Result<Exp, DecodeErr>;
 }
 
+pub mod bin;
 pub mod core;
+pub mod dot;
+pub mod gnn;
+pub mod html;
+pub mod sexp;
+pub mod tex;
+pub mod tok;
+
+#[cfg(feature = "json")]
+pub mod json;
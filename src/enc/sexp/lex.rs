@@ -0,0 +1,122 @@
+//! Lexing utilities for the S-expression language.
+
+use crate::err::DecodeErr;
+use crate::fmt::Formatted;
+use logos::{Logos, SpannedIter};
+use std::fmt::{Display, Formatter};
+
+/// Tokens available to the grammar of the S-expression language.
+///
+/// Keywords are only significant at the head of a list, and are otherwise accepted as symbols.
+#[derive(Logos, Clone, Debug, Eq, Hash, PartialEq)]
+#[logos(skip r"[ \t\n\f\r]+")]
+#[logos(skip r";[^\n]*")]
+pub enum Tok {
+    /// Identifier token, with the same form as in the [core language][crate::enc::core::lex::Tok].
+    #[regex("[a-z][a-z0-9_]*", |lex| lex.slice().parse().ok())]
+    Ident(String),
+    /// Number token, which is a non-negative decimal that fits within 64 bits.
+    #[regex("[0-9]+", |lex| lex.slice().parse().ok())]
+    Num(u64),
+    /// Left parenthesis token.
+    #[token("(")]
+    LParen,
+    /// Right parenthesis token.
+    #[token(")")]
+    RParen,
+    /// Keyword token for a free variable.
+    #[token("free")]
+    Free,
+    /// Keyword token for a bound variable.
+    #[token("var")]
+    Var,
+    /// Keyword token for an application.
+    #[token("app")]
+    App,
+    /// Keyword token for a λ-abstraction.
+    #[token("lam")]
+    Lam,
+    /// Keyword token for a Π-type.
+    #[token("pi")]
+    Pi,
+    /// Keyword token for a Σ-type.
+    #[token("sigma")]
+    Sigma,
+    /// Keyword token for a universe.
+    #[token("type")]
+    Type,
+}
+
+impl Display for Tok {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Formatted {
+        match self {
+            Tok::Ident(ident) => write!(f, "{}", ident),
+            Tok::Num(num) => write!(f, "{}", num),
+            Tok::LParen => write!(f, "("),
+            Tok::RParen => write!(f, ")"),
+            Tok::Free => write!(f, "free"),
+            Tok::Var => write!(f, "var"),
+            Tok::App => write!(f, "app"),
+            Tok::Lam => write!(f, "lam"),
+            Tok::Pi => write!(f, "pi"),
+            Tok::Sigma => write!(f, "sigma"),
+            Tok::Type => write!(f, "type"),
+        }
+    }
+}
+
+pub struct Lexer<'input> {
+    /// Stream of tokens, where each token is paired with its location in the source input stream.
+    token_stream: SpannedIter<'input, Tok>,
+}
+
+impl<'input> Lexer<'input> {
+    /// Create a new instance of the lexer.
+    pub fn new(input: &'input str) -> Self {
+        Self {
+            token_stream: Tok::lexer(input).spanned(),
+        }
+    }
+}
+
+impl<'input> Iterator for Lexer<'input> {
+    type Item = Result<(usize, Tok, usize), DecodeErr>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.token_stream.next().map(|(res, span)| match res {
+            Ok(tok) => Ok((span.start, tok, span.end)),
+            Err(()) => Err(DecodeErr::InvalidToken(span.start)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_lex() {
+        let val = "; head\n(lam lambda (type 18446744073709551615) ; tail\n(var 0))";
+        let toks: Vec<Tok> = Lexer::new(val).map(|res| res.unwrap().1).collect();
+        assert_eq!(
+            toks,
+            vec![
+                Tok::LParen,
+                Tok::Lam,
+                Tok::Ident("lambda".to_string()),
+                Tok::LParen,
+                Tok::Type,
+                Tok::Num(u64::MAX),
+                Tok::RParen,
+                Tok::LParen,
+                Tok::Var,
+                Tok::Num(0),
+                Tok::RParen,
+                Tok::RParen,
+            ]
+        );
+        let res: Vec<_> = Lexer::new("(type 18446744073709551616)").collect();
+        assert_eq!(res[2], Err(DecodeErr::InvalidToken(6))); // too large for 64 bits
+    }
+}
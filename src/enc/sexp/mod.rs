@@ -0,0 +1,21 @@
+//! S-expression language, which is a trivially parseable encoding of the Abstract Syntax Tree
+//! (AST) for interoperating with Lisp-based tooling.
+//!
+//! Every expression is a parenthesized list whose head names its variant:
+//!
+//! | Expression                 | S-expression                 |
+//! |----------------------------|------------------------------|
+//! | free variable `f`          | `(free f)`                   |
+//! | bound variable, at index 0 | `(var 0)`                    |
+//! | application `f x`          | `(app (free f) (free x))`    |
+//! | `λx : □ . x`               | `(lam x (type 0) (var 0))`   |
+//! | `Πx : □ . x`               | `(pi x (type 0) (var 0))`    |
+//! | `Σx : □ . x`               | `(sigma x (type 0) (var 0))` |
+//! | universe `□`               | `(type 0)`                   |
+//!
+//! Bound variables are written as De Bruijn indices alone, and take their symbols from the binders
+//! that they refer to. Whitespace separates atoms, and `;` starts a comment that extends to the
+//! end of the line.
+
+pub mod lex;
+pub mod par;
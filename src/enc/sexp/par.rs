@@ -0,0 +1,143 @@
+//! Parsing utilities for the S-expression language.
+
+lalrpop_mod!(#[allow(clippy::all)] pub grammar, "/enc/sexp/grammar.rs");
+
+use super::lex::Lexer;
+use crate::ast::{Exp, Var};
+use crate::enc::core::par::resolve;
+use crate::enc::Codec;
+use crate::err::DecodeErr;
+use grammar::ExpParser;
+use std::fmt::Write;
+
+/// S-expression language implementing a trivially parseable encoding of the Abstract Syntax Tree
+/// (AST).
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Sexp;
+
+/// Pending step in encoding an expression.
+enum Part<'a> {
+    /// Encode an expression.
+    Exp(&'a Exp),
+    /// Write some text as is.
+    Text(&'static str),
+}
+
+impl Sexp {
+    /// Create a new instance of the codec.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Codec<String> for Sexp {
+    fn encode(&self, exp: &Exp) -> String {
+        let mut res = String::new();
+        let mut stack = vec![Part::Exp(exp)];
+        while let Some(part) = stack.pop() {
+            let exp = match part {
+                Part::Exp(exp) => exp,
+                Part::Text(val) => {
+                    res.push_str(val);
+                    continue;
+                }
+            };
+            let (head, sym, fst, snd) = match exp {
+                Exp::Var(Var::Sym(sym)) => {
+                    let _ = write!(res, "(free {})", sym.val);
+                    continue;
+                }
+                Exp::Var(Var::Idx(idx)) => {
+                    let _ = write!(res, "(var {})", idx.val);
+                    continue;
+                }
+                Exp::Unv(unv) => {
+                    let _ = write!(res, "(type {})", unv.level);
+                    continue;
+                }
+                Exp::App(app) => ("app", None, &app.fst, &app.snd),
+                Exp::Abs(abs) => ("lam", Some(&abs.sym), &abs.typ, &abs.exp),
+                Exp::Prd(prd) => ("pi", Some(&prd.sym), &prd.typ, &prd.exp),
+                Exp::Sum(sum) => ("sigma", Some(&sum.sym), &sum.typ, &sum.exp),
+            };
+            let _ = write!(res, "({}", head);
+            if let Some(sym) = sym {
+                let _ = write!(res, " {}", sym.val);
+            }
+            stack.extend([
+                Part::Text(")"),
+                Part::Exp(snd),
+                Part::Text(" "),
+                Part::Exp(fst),
+                Part::Text(" "),
+            ]);
+        }
+        res
+    }
+
+    /// Decode source input to an [expression][Exp], which must also be
+    /// [well-scoped][Exp::validate].
+    fn decode(&self, val: &String) -> Result<Exp, DecodeErr> {
+        resolve(ExpParser::new().parse(Lexer::new(val.as_str()))?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::ast::{Abs, Idx, Sym, Unv};
+    use crate::enc::core::par::decode;
+
+    #[test]
+    fn test_encode() {
+        let items = [
+            ("λx : □ . x", "(lam x (type 0) (var 0))"),
+            ("f (g x)", "(app (free f) (app (free g) (free x)))"),
+            (
+                "Πa : □ . Σb : a . a b",
+                "(pi a (type 0) (sigma b (var 0) (app (var 1) (var 0))))",
+            ),
+        ];
+        for (val, exp) in items {
+            assert_eq!(Sexp.encode(&decode(val)), exp);
+            assert_eq!(Sexp.decode(&exp.to_string()), Ok(decode(val)));
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        let val = "; identity\n(lam lam (type 3)\n  (var 0))".to_string();
+        let res = Exp::Abs(Abs {
+            sym: Sym::new("lam"),
+            typ: Box::new(Exp::Unv(Unv { level: 3 })),
+            exp: Box::new(Exp::Var(Var::Idx(Idx {
+                val: 0,
+                sym: Sym::new("lam"),
+            }))),
+        });
+        assert_eq!(Sexp.decode(&val), Ok(res.clone()));
+        assert_eq!(Sexp.decode(&Sexp.encode(&res)), Ok(res));
+    }
+
+    #[test]
+    fn test_decode_err() {
+        let decode = |val: &str| Sexp.decode(&val.to_string());
+        assert_eq!(
+            decode("(lam x (type 0) (var 1))"),
+            Err(DecodeErr::InvalidValue(
+                "/exp".to_string(),
+                "an index that refers to an enclosing binder".to_string()
+            ))
+        );
+        assert!(matches!(
+            decode("(app (free f))"),
+            Err(DecodeErr::UnexpectedText(val, 13, 14, _)) if val == ")"
+        ));
+        assert!(matches!(
+            decode("(type 0"),
+            Err(DecodeErr::EndOfStream(7, _))
+        ));
+        assert_eq!(decode("(type -1)"), Err(DecodeErr::InvalidToken(6)));
+    }
+}
@@ -0,0 +1,122 @@
+//! LaTeX encoding of the Abstract Syntax Tree (AST), for typesetting expressions in papers.
+
+use super::core::par::{Core, Markup};
+use crate::ast::{Exp, Sym, Unv};
+
+/// Encoder of expressions as LaTeX, in math mode.
+///
+/// Expressions are laid out and parenthesized exactly as in the [core language][Core], with
+/// binders rendered as `\lambda`, `\Pi` and `\Sigma`, and universes as `\square`. Symbols of more
+/// than one letter are set in `\mathit`, and any numeric suffix as a subscript.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Latex;
+
+impl Latex {
+    /// Create a new instance of the encoder.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Encode an [expression][Exp] as LaTeX, on a single line.
+    pub fn encode(&self, exp: &Exp) -> String {
+        Core::new().fmt_markup(exp, self).render(usize::MAX)
+    }
+}
+
+impl Markup for Latex {
+    fn free(&self, sym: &Sym) -> String {
+        fmt_sym(sym)
+    }
+
+    fn bound(&self, sym: &Sym, _: usize) -> String {
+        fmt_sym(sym)
+    }
+
+    fn binder(&self, prefix: &str, sym: &Sym, _: usize) -> String {
+        let prefix = match prefix {
+            "λ" => "\\lambda",
+            "Π" => "\\Pi",
+            "Σ" => "\\Sigma",
+            prefix => prefix,
+        };
+        format!("{} {} : ", prefix, fmt_sym(sym))
+    }
+
+    fn unv(&self, unv: &Unv) -> String {
+        if unv.level == 0 {
+            "\\square".to_string()
+        } else {
+            format!("\\square_{{{}}}", unv.level)
+        }
+    }
+
+    fn punct(&self, val: &str) -> String {
+        match val {
+            " ." => " .\\,".to_string(), // thin space before the body
+            val => val.to_string(),
+        }
+    }
+
+    fn space(&self) -> String {
+        "\\;".to_string() // math mode ignores spaces, so applications need explicit ones
+    }
+}
+
+/// Formats a symbol, setting any numeric suffix as a subscript.
+fn fmt_sym(sym: &Sym) -> String {
+    let stem = sym.val.trim_end_matches(|c: char| c.is_ascii_digit());
+    let num = &sym.val[stem.len()..];
+    let mut res = if stem.chars().count() == 1 && stem.chars().all(|c| c.is_ascii_alphabetic()) {
+        stem.to_string()
+    } else {
+        format!("\\mathit{{{}}}", escape(stem))
+    };
+    if !num.is_empty() {
+        res.push_str(&format!("_{{{}}}", num));
+    }
+    res
+}
+
+/// Escapes the characters that are special to LaTeX.
+fn escape(val: &str) -> String {
+    let mut res = String::new();
+    for c in val.chars() {
+        match c {
+            '_' | '#' | '$' | '%' | '&' | '{' | '}' => {
+                res.push('\\');
+                res.push(c);
+            }
+            '\\' => res.push_str("\\backslash{}"),
+            '^' => res.push_str("\\hat{}"),
+            '~' => res.push_str("\\sim{}"),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::enc::core::par::decode;
+
+    #[test]
+    fn test_encode() {
+        let items = [
+            ("λx : □ . x", r"\lambda x : \square .\, x"),
+            (
+                "Πa : □ . Σb : a . f_g (a b)",
+                r"\Pi a : \square .\, \Sigma b : a .\, \mathit{f\_g}\; (a\; b)",
+            ),
+            (
+                "(λx : □ . x) (λx : □ . λx1 : □ . x)",
+                r"(\lambda x : \square .\, x)\; \lambda x : \square .\, \lambda x_{1} : \square .\, x",
+            ),
+        ];
+        for (val, exp) in items {
+            assert_eq!(Latex.encode(&decode(val)), exp);
+        }
+        assert_eq!(Latex.encode(&Exp::Unv(Unv { level: 2 })), r"\square_{2}");
+    }
+}
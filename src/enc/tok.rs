@@ -0,0 +1,366 @@
+//! Token ID encoding of the Abstract Syntax Tree (AST), for training and sampling from models of
+//! expressions.
+//!
+//! An expression is encoded as a sequence of IDs from a [vocabulary][Vocab], in prefix form:
+//!
+//! | Expression            | Tokens                                    |
+//! |-----------------------|-------------------------------------------|
+//! | free variable         | symbol                                    |
+//! | bound variable        | index                                     |
+//! | application           | [LPAREN], first, second, [RPAREN]         |
+//! | λ-abstraction         | [LAMBDA], symbol, type, body              |
+//! | Π-type                | [PI], symbol, type, body                  |
+//! | Σ-type                | [SIGMA], symbol, type, body               |
+//! | universe              | [BOX], level                              |
+//!
+//! The IDs of the structural tokens are fixed, and are followed by the IDs of the index tokens and
+//! then those of the symbols in the vocabulary. The index tokens also encode the levels of
+//! universes. Bound variables take their symbols from the binders that they refer to. The encoding
+//! is lossy where a fixed vocabulary demands it: symbols outside of the vocabulary, and indices
+//! and levels beyond the index tokens, are encoded as [UNK]; an unknown level is decoded as 0.
+
+use super::Codec;
+use crate::ast::{Abs, App, Exp, Idx, Prd, Sum, Sym, Unv, Var};
+use crate::err::DecodeErr;
+use std::collections::HashMap;
+
+/// ID of the unknown token, for symbols outside of the vocabulary and indices beyond the index
+/// tokens; decoded as a free variable with the symbol `unk`.
+pub const UNK: u32 = 0;
+/// ID of the left parenthesis token, which starts an application.
+pub const LPAREN: u32 = 1;
+/// ID of the right parenthesis token, which ends an application.
+pub const RPAREN: u32 = 2;
+/// ID of the token for a λ-abstraction.
+pub const LAMBDA: u32 = 3;
+/// ID of the token for a Π-type.
+pub const PI: u32 = 4;
+/// ID of the token for a Σ-type.
+pub const SIGMA: u32 = 5;
+/// ID of the token for a universe, which is followed by the token for its level.
+pub const BOX: u32 = 6;
+/// ID of the token for index 0, which is followed by the tokens for the other indices, in order.
+/// The same tokens stand for the levels of universes.
+pub const IDX: u32 = 7;
+
+/// Number of index tokens in a vocabulary, unless supplied otherwise.
+const INDICES: u32 = 256;
+
+/// Codec that maps expressions to and from sequences of token IDs, with a vocabulary of symbols.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Vocab {
+    /// Number of index tokens, for indices from 0.
+    indices: u32,
+    /// Symbols in the vocabulary, in order of their IDs.
+    syms: Vec<Sym>,
+    /// ID of each symbol in the vocabulary; the first is kept for any symbol supplied twice.
+    ids: HashMap<String, u32>,
+}
+
+/// Pending step in decoding an expression.
+enum Task {
+    /// Decode the expression that starts at the current position, and push it.
+    Exp,
+    /// Expect the end of an application, then pop its constituents and push it.
+    App,
+    /// Pop the decoded type and body of a binder with the supplied token and symbol, and push the
+    /// binder.
+    Binder(u32, Sym),
+    /// Bring the binder with the supplied symbol into scope.
+    Bind(Sym),
+    /// Remove the innermost binder from scope.
+    Unbind,
+}
+
+impl Vocab {
+    /// Create a new instance of the codec with a vocabulary of symbols, and 256 index tokens.
+    pub fn new(syms: &[Sym]) -> Self {
+        Self::with_indices(syms, INDICES)
+    }
+
+    /// Create a new instance of the codec with a vocabulary of symbols, and the supplied number of
+    /// index tokens.
+    ///
+    /// The vocabulary is truncated to fit within the range of IDs, if necessary.
+    pub fn with_indices(syms: &[Sym], indices: u32) -> Self {
+        let indices = indices.min(u32::MAX - IDX);
+        let len = (u32::MAX - IDX - indices) as usize;
+        let syms: Vec<Sym> = syms.iter().take(len).cloned().collect();
+        let mut ids = HashMap::new();
+        for (pos, sym) in syms.iter().enumerate() {
+            let id = IDX + indices + pos as u32;
+            ids.entry(sym.val.clone()).or_insert(id);
+        }
+        Self { indices, syms, ids }
+    }
+
+    /// Returns the number of token IDs in use, which are those from 0 up to, but not including,
+    /// the value returned.
+    pub fn size(&self) -> u32 {
+        IDX + self.indices + self.syms.len() as u32
+    }
+
+    /// Returns the text of each token, in order of their IDs, for inspecting sequences.
+    pub fn tokens(&self) -> Vec<String> {
+        let mut res: Vec<String> = ["<unk>", "(", ")", "λ", "Π", "Σ", "□"]
+            .iter()
+            .map(|tok| tok.to_string())
+            .collect();
+        res.extend((0..self.indices).map(|val| format!("#{}", val)));
+        res.extend(self.syms.iter().map(|sym| sym.to_string()));
+        res
+    }
+
+    /// Returns the ID of a symbol, or [UNK] if it is outside of the vocabulary.
+    pub(crate) fn sym_id(&self, sym: &Sym) -> u32 {
+        self.ids.get(&sym.val).copied().unwrap_or(UNK)
+    }
+
+    /// Returns the ID of the index token for a value, or [UNK] if it is beyond the index tokens.
+    fn idx_id(&self, val: u64) -> u32 {
+        match u32::try_from(val) {
+            Ok(val) if val < self.indices => IDX + val,
+            _ => UNK,
+        }
+    }
+
+    /// Returns the symbol with the supplied ID, or `None` if the ID is not that of a symbol.
+    fn sym(&self, id: u32) -> Option<Sym> {
+        match id {
+            UNK => Some(Sym::new("unk")),
+            id => id
+                .checked_sub(IDX + self.indices)
+                .and_then(|pos| self.syms.get(pos as usize))
+                .cloned(),
+        }
+    }
+}
+
+impl Codec<Vec<u32>> for Vocab {
+    fn encode(&self, exp: &Exp) -> Vec<u32> {
+        let mut res = vec![];
+        let mut stack = vec![Some(exp)]; // none marks the end of an application
+        while let Some(exp) = stack.pop() {
+            match exp {
+                None => res.push(RPAREN),
+                Some(Exp::Var(Var::Sym(sym))) => res.push(self.sym_id(sym)),
+                Some(Exp::Var(Var::Idx(idx))) => res.push(self.idx_id(idx.val)),
+                Some(Exp::App(App { fst, snd })) => {
+                    res.push(LPAREN);
+                    stack.extend([None, Some(snd.as_ref()), Some(fst.as_ref())]);
+                }
+                Some(Exp::Abs(Abs {
+                    sym,
+                    typ,
+                    exp: body,
+                }))
+                | Some(Exp::Prd(Prd {
+                    sym,
+                    typ,
+                    exp: body,
+                }))
+                | Some(Exp::Sum(Sum {
+                    sym,
+                    typ,
+                    exp: body,
+                })) => {
+                    res.push(match exp {
+                        Some(Exp::Abs(_)) => LAMBDA,
+                        Some(Exp::Prd(_)) => PI,
+                        _ => SIGMA,
+                    });
+                    res.push(self.sym_id(sym));
+                    stack.extend([Some(body.as_ref()), Some(typ.as_ref())]);
+                }
+                Some(Exp::Unv(unv)) => res.extend([BOX, self.idx_id(unv.level)]),
+            }
+        }
+        res
+    }
+
+    /// Decode a sequence of token IDs to an [expression][Exp], which must also be
+    /// [well-scoped][Exp::validate].
+    ///
+    /// The location reported by an error is the position of the offending ID within the sequence.
+    fn decode(&self, val: &Vec<u32>) -> Result<Exp, DecodeErr> {
+        let invalid = |pos, expected: &str| DecodeErr::InvalidData(pos, expected.to_string());
+        let mut pos = 0;
+        let mut tasks = vec![Task::Exp];
+        let mut scope: Vec<Sym> = vec![];
+        let mut res: Vec<Exp> = vec![];
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Exp => {}
+                Task::App => {
+                    match val.get(pos) {
+                        Some(&RPAREN) => pos += 1,
+                        Some(_) => return Err(invalid(pos, "the end of an application")),
+                        None => return Err(DecodeErr::Truncated(pos)),
+                    }
+                    let snd = Box::new(res.pop().unwrap_or(Exp::Unv(Unv::new())));
+                    let fst = Box::new(res.pop().unwrap_or(Exp::Unv(Unv::new())));
+                    res.push(Exp::App(App { fst, snd }));
+                    continue;
+                }
+                Task::Binder(tok, sym) => {
+                    let exp = Box::new(res.pop().unwrap_or(Exp::Unv(Unv::new())));
+                    let typ = Box::new(res.pop().unwrap_or(Exp::Unv(Unv::new())));
+                    res.push(match tok {
+                        LAMBDA => Exp::Abs(Abs { sym, typ, exp }),
+                        PI => Exp::Prd(Prd { sym, typ, exp }),
+                        _ => Exp::Sum(Sum { sym, typ, exp }),
+                    });
+                    continue;
+                }
+                Task::Bind(sym) => {
+                    scope.push(sym);
+                    continue;
+                }
+                Task::Unbind => {
+                    scope.pop();
+                    continue;
+                }
+            }
+
+            let tok = *val.get(pos).ok_or(DecodeErr::Truncated(pos))?;
+            pos += 1;
+            match tok {
+                LPAREN => tasks.extend([Task::App, Task::Exp, Task::Exp]),
+                LAMBDA | PI | SIGMA => {
+                    let sym = val
+                        .get(pos)
+                        .ok_or(DecodeErr::Truncated(pos))
+                        .and_then(|id| self.sym(*id).ok_or_else(|| invalid(pos, "a symbol")))?;
+                    pos += 1;
+                    tasks.extend([
+                        Task::Binder(tok, sym.clone()),
+                        Task::Unbind,
+                        Task::Exp,
+                        Task::Bind(sym),
+                        Task::Exp,
+                    ]);
+                }
+                BOX => {
+                    let level = match val.get(pos) {
+                        Some(&UNK) => 0,
+                        Some(&tok) if tok >= IDX && tok - IDX < self.indices => tok - IDX,
+                        Some(_) => return Err(invalid(pos, "a level")),
+                        None => return Err(DecodeErr::Truncated(pos)),
+                    };
+                    pos += 1;
+                    res.push(Exp::Unv(Unv {
+                        level: u64::from(level),
+                    }));
+                }
+                tok if tok >= IDX && tok - IDX < self.indices => {
+                    let mut idx = Idx {
+                        val: u64::from(tok - IDX),
+                        sym: Sym::new(""), // replaced with the symbol of the binder below
+                    };
+                    idx.sym = idx
+                        .position(scope.len())
+                        .map(|pos| scope[pos].clone())
+                        .ok_or_else(|| {
+                            invalid(pos - 1, "an index that refers to an enclosing binder")
+                        })?;
+                    res.push(Exp::Var(Var::Idx(idx)));
+                }
+                tok => match self.sym(tok) {
+                    Some(sym) => res.push(Exp::Var(Var::Sym(sym))),
+                    None => return Err(invalid(pos - 1, "an expression")),
+                },
+            }
+        }
+
+        if pos < val.len() {
+            return Err(invalid(pos, "the end of the sequence"));
+        }
+        res.pop().ok_or(DecodeErr::Truncated(pos))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::enc::core::par::{decode, Core};
+
+    fn vocab() -> Vocab {
+        let syms: Vec<Sym> = ["f", "x", "y"].iter().map(|sym| Sym::new(sym)).collect();
+        Vocab::with_indices(&syms, 4)
+    }
+
+    #[test]
+    fn test_encode() {
+        let vocab = vocab();
+        assert_eq!(vocab.size(), 14);
+        assert_eq!(
+            vocab.tokens()[IDX as usize..].join(" "),
+            "#0 #1 #2 #3 f x y"
+        );
+
+        let res = vocab.encode(&decode("λx : □ . f x"));
+        assert_eq!(res, vec![LAMBDA, 12, BOX, IDX, LPAREN, 11, IDX, RPAREN]);
+        assert_eq!(vocab.decode(&res), Ok(decode("λx : □ . f x")));
+
+        let res = vocab.encode(&decode("λz : □ . g z"));
+        assert_eq!(res, vec![LAMBDA, UNK, BOX, IDX, LPAREN, UNK, IDX, RPAREN]);
+        let res = vocab.decode(&res).map(|exp| Core::new().encode(&exp));
+        assert_eq!(res, Ok("λunk1 : □ . unk unk1".to_string())); // free, so not captured
+    }
+
+    #[test]
+    fn test_encode_level() {
+        let vocab = vocab();
+        let exp = Exp::Unv(Unv { level: 3 });
+        assert_eq!(vocab.encode(&exp), vec![BOX, IDX + 3]);
+        assert_eq!(vocab.decode(&vocab.encode(&exp)), Ok(exp));
+
+        let exp = Exp::Unv(Unv { level: 4 }); // beyond the index tokens
+        assert_eq!(vocab.encode(&exp), vec![BOX, UNK]);
+        assert_eq!(vocab.decode(&vocab.encode(&exp)), Ok(Exp::Unv(Unv::new())));
+    }
+
+    #[test]
+    fn test_decode_encode() {
+        let vocab = vocab();
+        let items = [
+            "Πx : □ . Σy : x . (λf : x . f) y",
+            "f (f x) (λy : □ . y)",
+            "λx : □ . λx : □ . λy : □ . x y",
+        ];
+        for val in items {
+            let exp = decode(val);
+            assert_eq!(vocab.decode(&vocab.encode(&exp)), Ok(exp));
+        }
+    }
+
+    #[test]
+    fn test_decode_err() {
+        let vocab = vocab();
+        let invalid = |pos, expected: &str| Err(DecodeErr::InvalidData(pos, expected.to_string()));
+        assert_eq!(vocab.decode(&vec![]), Err(DecodeErr::Truncated(0)));
+        assert_eq!(
+            vocab.decode(&vec![LPAREN, 11, 12]),
+            Err(DecodeErr::Truncated(3))
+        );
+        assert_eq!(
+            vocab.decode(&vec![LPAREN, 11, 12, 13]),
+            invalid(3, "the end of an application")
+        );
+        assert_eq!(vocab.decode(&vec![LAMBDA, BOX]), invalid(1, "a symbol"));
+        assert_eq!(vocab.decode(&vec![RPAREN]), invalid(0, "an expression"));
+        assert_eq!(vocab.decode(&vec![14]), invalid(0, "an expression"));
+        assert_eq!(
+            vocab.decode(&vec![BOX, IDX, BOX]),
+            invalid(2, "the end of the sequence")
+        );
+        assert_eq!(vocab.decode(&vec![BOX]), Err(DecodeErr::Truncated(1)));
+        assert_eq!(vocab.decode(&vec![BOX, BOX]), invalid(1, "a level"));
+        assert_eq!(vocab.decode(&vec![BOX, 11]), invalid(1, "a level"));
+        assert_eq!(
+            vocab.decode(&vec![LAMBDA, 11, BOX, IDX, IDX + 1]),
+            invalid(4, "an index that refers to an enclosing binder")
+        );
+    }
+}
@@ -1,6 +1,10 @@
 //! Top-level error types.
 
+use crate::ast::{Exp, Idx, Path, Step, Sym};
 use crate::enc::core::lex::Tok;
+use crate::enc::core::par::Core;
+use crate::enc::sexp::lex::Tok as SexpTok;
+use crate::enc::Codec;
 use crate::fmt::Formatted;
 use lalrpop_util::ParseError;
 use std::error::Error;
@@ -13,6 +17,10 @@ pub enum SystemErr {
     MaxLimitIdx(u64),
     /// Maximum limit for [universe][crate::ast::Unv] levels has been reached.
     MaxLimitUnv(u64),
+    /// Minimum limit of 0 for [indices][crate::ast::Idx] has been passed, from the supplied value.
+    MinLimitIdx(u64),
+    /// Bound variable refers to a binder that is being removed, so cannot be kept.
+    UsedIdx(Idx),
 }
 
 /// Error indicating a syntactic or semantic error decoding a value to an [expression][crate::ast::Exp].
@@ -24,12 +32,68 @@ pub enum DecodeErr {
     InvalidToken(usize),
     /// Valid token was found but a different one (or none at all) was expected.
     UnexpectedToken(Tok, usize, usize, Vec<String>),
+    /// Valid token of an encoding other than the core language, supplied as text, was found but a
+    /// different one (or none at all) was expected.
+    UnexpectedText(String, usize, usize, Vec<String>),
+    /// Value at the supplied location, in the same format as a [path][crate::ast::Path], is not
+    /// what was expected, which is described second.
+    InvalidValue(String, String),
+    /// Encoded bytes ended abruptly, at the supplied location.
+    Truncated(usize),
+    /// Tag byte supplied first, at the supplied location, does not denote any kind of expression.
+    InvalidTag(u8, usize),
+    /// Encoded bytes at the supplied location are not what was expected, which is described
+    /// second.
+    InvalidData(usize, String),
     /// A [SystemErr] was encountered while decoding the value.
     SystemErr(SystemErr),
 }
 
+/// Error indicating that an [expression][crate::ast::Exp] is not well-typed within a [typing
+/// context][crate::ast::Ctx].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum TypeErr {
+    /// Free variable has not been declared within the typing context.
+    Undeclared(Sym),
+    /// Bound variable refers to a binder that is not declared within the typing context.
+    Unbound(Idx),
+    /// Expression was expected to be a type, but its type (supplied second) is not a universe.
+    NotType(Box<Exp>, Box<Exp>),
+    /// Expression was applied to an argument, but its type (supplied second) is not a Π-type.
+    NotFunction(Box<Exp>, Box<Exp>),
+    /// Expression was expected to have the first type supplied, but has the second instead.
+    Mismatch(Box<Exp>, Box<Exp>, Box<Exp>),
+    /// A [SystemErr] was encountered while type checking the expression.
+    SystemErr(SystemErr),
+}
+
+/// Error indicating that a [path][crate::ast::Path] does not lead to a sub-expression.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum PathErr {
+    /// Value could not be parsed as a path.
+    InvalidPath(String),
+    /// Step cannot be taken from the sub-expression at the end of the path supplied first.
+    InvalidStep(Path, Step),
+    /// A [SystemErr] was encountered while following the path.
+    SystemErr(SystemErr),
+}
+
+/// Error indicating that an [expression][crate::ast::Exp] is not well-scoped, found at the
+/// supplied [path][crate::ast::Path] to a bound variable within it.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ScopeErr {
+    /// Bound variable refers to a binder outside of the expression.
+    Dangling(Path, Idx),
+    /// Bound variable is annotated with a symbol other than that of the binder it refers to,
+    /// which is supplied last.
+    Mismatch(Path, Idx, Sym),
+}
+
 impl Error for SystemErr {}
 impl Error for DecodeErr {}
+impl Error for TypeErr {}
+impl Error for PathErr {}
+impl Error for ScopeErr {}
 
 impl Display for SystemErr {
     fn fmt(&self, f: &mut Formatter<'_>) -> Formatted {
@@ -40,6 +104,14 @@ impl Display for SystemErr {
             SystemErr::MaxLimitUnv(lim) => {
                 write!(f, "max limit {} for universe levels has been reached", lim)
             }
+            SystemErr::MinLimitIdx(val) => {
+                write!(f, "min limit 0 for indices has been passed, from: {}", val)
+            }
+            SystemErr::UsedIdx(idx) => write!(
+                f,
+                "bound variable: {}, refers to a binder that is being removed",
+                idx.sym
+            ),
         }
     }
 }
@@ -66,11 +138,110 @@ impl Display for DecodeErr {
                     expected.join(" | ")
                 }
             ),
+            DecodeErr::UnexpectedText(tok, sloc, eloc, expected) => write!(
+                f,
+                "unexpected token: {}, at location: {}..{}, expected: {}",
+                tok,
+                sloc,
+                eloc,
+                if expected.is_empty() {
+                    "none".to_string()
+                } else {
+                    expected.join(" | ")
+                }
+            ),
+            DecodeErr::InvalidValue(loc, expected) => write!(
+                f,
+                "invalid value, at location: {}, expected: {}",
+                loc, expected
+            ),
+            DecodeErr::Truncated(sloc) => {
+                write!(f, "unexpected end of bytes, at location: {}", sloc)
+            }
+            DecodeErr::InvalidTag(tag, sloc) => {
+                write!(f, "invalid tag: {}, at location: {}", tag, sloc)
+            }
+            DecodeErr::InvalidData(sloc, expected) => write!(
+                f,
+                "invalid data, at location: {}, expected: {}",
+                sloc, expected
+            ),
             DecodeErr::SystemErr(err) => write!(f, "{}", err),
         }
     }
 }
 
+impl Display for TypeErr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Formatted {
+        let codec = Core::new();
+        match self {
+            TypeErr::Undeclared(sym) => write!(f, "undeclared variable: {}", sym),
+            TypeErr::Unbound(idx) => write!(f, "unbound variable: {}, at index: {}", idx.sym, idx),
+            TypeErr::NotType(exp, typ) => write!(
+                f,
+                "expected a type, found: {}, of type: {}",
+                codec.encode(exp),
+                codec.encode(typ)
+            ),
+            TypeErr::NotFunction(exp, typ) => write!(
+                f,
+                "expected a function, found: {}, of type: {}",
+                codec.encode(exp),
+                codec.encode(typ)
+            ),
+            TypeErr::Mismatch(exp, expected, found) => write!(
+                f,
+                "type mismatch for: {}, expected: {}, found: {}",
+                codec.encode(exp),
+                codec.encode(expected),
+                codec.encode(found)
+            ),
+            TypeErr::SystemErr(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Display for PathErr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Formatted {
+        match self {
+            PathErr::InvalidPath(val) => write!(f, "invalid path: {}", val),
+            PathErr::InvalidStep(path, step) => {
+                write!(f, "invalid step: {}, from the end of path: {}", step, path)
+            }
+            PathErr::SystemErr(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Display for ScopeErr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Formatted {
+        match self {
+            ScopeErr::Dangling(path, idx) => write!(
+                f,
+                "dangling variable: {}, at index: {}, at path: {}",
+                idx.sym, idx, path
+            ),
+            ScopeErr::Mismatch(path, idx, sym) => write!(
+                f,
+                "mismatched variable: {}, at index: {}, bound as: {}, at path: {}",
+                idx.sym, idx, sym, path
+            ),
+        }
+    }
+}
+
+impl From<SystemErr> for PathErr {
+    fn from(err: SystemErr) -> Self {
+        PathErr::SystemErr(err)
+    }
+}
+
+impl From<SystemErr> for TypeErr {
+    fn from(err: SystemErr) -> Self {
+        TypeErr::SystemErr(err)
+    }
+}
+
 impl From<ParseError<usize, Tok, DecodeErr>> for DecodeErr {
     fn from(err: ParseError<usize, Tok, DecodeErr>) -> Self {
         match err {
@@ -95,3 +266,23 @@ impl From<ParseError<usize, Tok, DecodeErr>> for DecodeErr {
         }
     }
 }
+
+impl From<ParseError<usize, SexpTok, DecodeErr>> for DecodeErr {
+    fn from(err: ParseError<usize, SexpTok, DecodeErr>) -> Self {
+        match err {
+            ParseError::InvalidToken { location: sloc } => DecodeErr::InvalidToken(sloc),
+            ParseError::UnrecognizedEof {
+                location: sloc,
+                expected,
+            } => DecodeErr::EndOfStream(sloc, expected),
+            ParseError::UnrecognizedToken {
+                token: (sloc, tok, eloc),
+                expected,
+            } => DecodeErr::UnexpectedText(tok.to_string(), sloc, eloc, expected),
+            ParseError::ExtraToken {
+                token: (sloc, tok, eloc),
+            } => DecodeErr::UnexpectedText(tok.to_string(), sloc, eloc, vec![]),
+            ParseError::User { error } => error,
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Document layout engine, in the style of Wadler and Leijen's *prettier printer*.
+
+/// Document, which describes a piece of text along with the points where it may be broken.
+///
+/// A document is rendered within a target width. Each [group][Doc::Group] is laid out on a single
+/// line if it fits within the space remaining on the current line, otherwise every
+/// [line][Doc::Line] that belongs directly to the group is broken. Nested groups are considered
+/// independently, so that the outermost groups are broken first.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Doc {
+    /// Empty document.
+    Nil,
+    /// Text, which must not contain any line breaks.
+    Text(String),
+    /// Line break, which is rendered as a single space when its group is laid out on one line.
+    Line,
+    /// Document in which broken lines are indented by an additional number of columns.
+    Nest(usize, Box<Doc>),
+    /// Document that is laid out on a single line whenever it fits.
+    Group(Box<Doc>),
+    /// Concatenation of documents, in order.
+    Cat(Vec<Doc>),
+}
+
+/// Layout chosen for the lines within a group.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Mode {
+    /// Lines are rendered as spaces.
+    Flat,
+    /// Lines are rendered as line breaks followed by indentation.
+    Break,
+}
+
+/// Pending item in the layout of a document: indentation, mode and the document itself.
+type Cmd<'a> = (usize, Mode, &'a Doc);
+
+impl Doc {
+    /// Create a new [text][Doc::Text] document.
+    pub fn text<S: Into<String>>(val: S) -> Self {
+        Doc::Text(val.into())
+    }
+
+    /// Create a new [nested][Doc::Nest] document.
+    pub fn nest(ind: usize, doc: Doc) -> Self {
+        Doc::Nest(ind, Box::new(doc))
+    }
+
+    /// Create a new [group][Doc::Group].
+    pub fn group(doc: Doc) -> Self {
+        Doc::Group(Box::new(doc))
+    }
+
+    /// Render the document, breaking lines so that they fit within `width` columns wherever
+    /// possible.
+    ///
+    /// A width of [usize::MAX] never breaks any lines, and renders the document on a single line.
+    pub fn render(&self, width: usize) -> String {
+        let mut res = String::new();
+        let mut col = 0;
+        let mut cmds: Vec<Cmd> = vec![(0, Mode::Break, self)];
+        while let Some((ind, mode, doc)) = cmds.pop() {
+            match doc {
+                Doc::Nil => {}
+                Doc::Text(val) => {
+                    res.push_str(val);
+                    col += val.chars().count();
+                }
+                Doc::Line => match mode {
+                    Mode::Flat => {
+                        res.push(' ');
+                        col += 1;
+                    }
+                    Mode::Break => {
+                        res.push('\n');
+                        res.push_str(&" ".repeat(ind));
+                        col = ind;
+                    }
+                },
+                Doc::Nest(inc, doc) => cmds.push((ind + inc, mode, doc)),
+                Doc::Group(doc) => {
+                    let next = (ind, Mode::Flat, doc.as_ref());
+                    if mode == Mode::Flat || fits(width.saturating_sub(col), next, &cmds) {
+                        cmds.push(next);
+                    } else {
+                        cmds.push((ind, Mode::Break, doc));
+                    }
+                }
+                Doc::Cat(docs) => cmds.extend(docs.iter().rev().map(|doc| (ind, mode, doc))),
+            }
+        }
+        res
+    }
+}
+
+impl Drop for Doc {
+    /// Drops the document without recursion, since documents are nested as deeply as the
+    /// [expressions][crate::ast::Exp] they format.
+    fn drop(&mut self) {
+        let mut docs = vec![];
+        detach(self, &mut docs);
+        while let Some(mut doc) = docs.pop() {
+            detach(&mut doc, &mut docs);
+        } // each document is dropped here, with no constituents left
+    }
+}
+
+/// Moves the constituents of a document onto the supplied stack.
+fn detach(doc: &mut Doc, docs: &mut Vec<Doc>) {
+    match doc {
+        Doc::Nest(_, doc) | Doc::Group(doc) => docs.push(std::mem::replace(doc, Doc::Nil)),
+        Doc::Cat(vals) => docs.append(vals),
+        Doc::Nil | Doc::Text(_) | Doc::Line => {}
+    }
+}
+
+/// Checks whether the supplied command fits within the remaining width, up to the first line
+/// break that follows it.
+fn fits(mut rem: usize, next: Cmd, rest: &[Cmd]) -> bool {
+    let mut rest = rest.iter().rev();
+    let mut cmds = vec![next];
+    loop {
+        let (ind, mode, doc) = match cmds.pop() {
+            Some(cmd) => cmd,
+            None => match rest.next() {
+                Some(cmd) => *cmd,
+                None => return true, // end of document
+            },
+        };
+        match doc {
+            Doc::Nil => {}
+            Doc::Text(val) => match rem.checked_sub(val.chars().count()) {
+                Some(val) => rem = val,
+                None => return false,
+            },
+            Doc::Line => match mode {
+                Mode::Flat => match rem.checked_sub(1) {
+                    Some(val) => rem = val,
+                    None => return false,
+                },
+                Mode::Break => return true, // remaining text starts on a new line
+            },
+            Doc::Nest(inc, doc) => cmds.push((ind + inc, mode, doc)),
+            Doc::Group(doc) => cmds.push((ind, mode, doc)),
+            Doc::Cat(docs) => cmds.extend(docs.iter().rev().map(|doc| (ind, mode, doc))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn sample() -> Doc {
+        Doc::group(Doc::Cat(vec![
+            Doc::text("foo"),
+            Doc::nest(
+                2,
+                Doc::Cat(vec![
+                    Doc::Line,
+                    Doc::text("bar"),
+                    Doc::Line,
+                    Doc::group(Doc::Cat(vec![
+                        Doc::text("(moo"),
+                        Doc::nest(2, Doc::Cat(vec![Doc::Line, Doc::text("baz)")])),
+                    ])),
+                ]),
+            ),
+        ]))
+    }
+
+    #[test]
+    fn test_render_flat() {
+        assert_eq!(sample().render(usize::MAX), "foo bar (moo baz)");
+        assert_eq!(sample().render(17), "foo bar (moo baz)");
+    }
+
+    #[test]
+    fn test_render_break() {
+        assert_eq!(sample().render(16), "foo\n  bar\n  (moo baz)");
+        assert_eq!(sample().render(8), "foo\n  bar\n  (moo\n    baz)");
+    }
+
+    #[test]
+    fn test_render_unicode() {
+        let doc = Doc::group(Doc::Cat(vec![Doc::text("λx"), Doc::Line, Doc::text("x")]));
+        assert_eq!(doc.render(4), "λx x");
+        assert_eq!(doc.render(3), "λx\nx");
+    }
+
+    #[test]
+    fn test_render_nil() {
+        assert_eq!(Doc::Nil.render(0), "");
+        assert_eq!(Doc::Cat(vec![]).render(0), "");
+    }
+
+    #[test]
+    fn test_drop_deep() {
+        let doc = (0..1_000_000).fold(Doc::Nil, |doc, _| {
+            Doc::group(Doc::Cat(vec![
+                Doc::text("("),
+                Doc::nest(2, doc),
+                Doc::text(")"),
+            ]))
+        });
+        assert_eq!(doc.render(usize::MAX).len(), 2_000_000);
+    }
+}
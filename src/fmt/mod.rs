@@ -1,4 +1,8 @@
 //! Functions and utilities related to formatting.
 
+mod doc;
+
+pub use doc::Doc;
+
 /// Type alias for a formatted result.
 pub type Formatted = std::fmt::Result;
@@ -5,6 +5,7 @@
 extern crate lalrpop_util;
 
 pub mod ast;
+pub mod chk;
 pub mod enc;
 pub mod err;
 pub mod fmt;
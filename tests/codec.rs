@@ -0,0 +1,79 @@
+//! Property tests that every codec decodes what it encodes, for randomly generated expressions.
+//!
+//! Expressions are generated by the [strategy][Gen::strategy] of the generator, and failing cases
+//! are shrunk as that strategy describes.
+
+use fluxo::ast::{Gen, Sym};
+use fluxo::enc::bin::Binary;
+use fluxo::enc::core::par::Core;
+use fluxo::enc::sexp::par::Sexp;
+use fluxo::enc::tok::Vocab;
+use fluxo::enc::Codec;
+use proptest::prelude::*;
+
+/// Symbols of the free variables within generated expressions.
+fn free() -> Vec<Sym> {
+    vec![Sym::new("a"), Sym::new("b")]
+}
+
+/// Generates well-scoped expressions with up to 48 sub-expressions, and universes of up to level 3.
+fn exp() -> Gen {
+    Gen::new(0).with_size(48).with_free(&free()).with_level(3)
+}
+
+/// Generates expressions nested deeper than the default limit of the generator.
+fn deep() -> Gen {
+    exp().with_size(256).with_depth(64)
+}
+
+proptest! {
+    #[test]
+    fn test_core(exp in exp().strategy()) {
+        let val = Core::new().encode(&exp);
+        let res = Core::new().decode(&val);
+        prop_assert!(res.as_ref().is_ok_and(|res| res.alpha_eq(&exp)), "{}: {:?}", val, res);
+    }
+
+    #[test]
+    fn test_core_show_indices(exp in exp().strategy()) {
+        let val = Core::with_show_indices(true).encode(&exp);
+        let res = Core::with_show_indices(true).decode(&val);
+        prop_assert!(res.as_ref().is_ok_and(|res| res.alpha_eq(&exp)), "{}: {:?}", val, res);
+    }
+
+    #[test]
+    fn test_core_deep(exp in deep().strategy()) {
+        let val = Core::new().encode(&exp);
+        let res = Core::new().decode(&val);
+        prop_assert!(res.as_ref().is_ok_and(|res| res.alpha_eq(&exp)), "{}: {:?}", val, res);
+    }
+
+    #[test]
+    fn test_sexp(exp in exp().strategy()) {
+        let val = Sexp::new().encode(&exp);
+        let res = Sexp::new().decode(&val);
+        prop_assert!(res.as_ref().is_ok_and(|res| res.alpha_eq(&exp)), "{}: {:?}", val, res);
+    }
+
+    #[test]
+    fn test_binary(exp in deep().strategy()) {
+        let val = Binary::new().encode(&exp);
+        prop_assert_eq!(Binary::new().decode(&val), Ok(exp));
+    }
+
+    #[test]
+    fn test_tok(exp in exp().strategy()) {
+        let vocab = Vocab::new(&free());
+        let val = vocab.encode(&exp);
+        let res = vocab.decode(&val);
+        prop_assert!(res.as_ref().is_ok_and(|res| res.alpha_eq(&exp)), "{:?}: {:?}", val, res);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json(exp in deep().strategy()) {
+        use fluxo::enc::json::Json;
+        let val = Json.encode(&exp);
+        prop_assert_eq!(Json.decode(&val), Ok(exp));
+    }
+}
@@ -0,0 +1,182 @@
+//! Tests for the language server, driven by a scripted client over the standard streams.
+
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+/// Client that drives a language server process.
+struct Client {
+    proc: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next: u64,
+    /// Notifications received while waiting for responses.
+    notes: Vec<Value>,
+}
+
+impl Client {
+    fn spawn() -> Self {
+        let mut proc = Command::new(env!("CARGO_BIN_EXE_fluxo-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = proc.stdin.take().unwrap();
+        let stdout = BufReader::new(proc.stdout.take().unwrap());
+        Self {
+            proc,
+            stdin,
+            stdout,
+            next: 0,
+            notes: vec![],
+        }
+    }
+
+    fn send(&mut self, msg: Value) {
+        let body = msg.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn recv(&mut self) -> Value {
+        let mut len = 0;
+        loop {
+            let mut line = String::new();
+            self.stdout.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(val) = line.strip_prefix("Content-Length: ") {
+                len = val.parse().unwrap();
+            }
+        }
+        let mut buf = vec![0; len];
+        self.stdout.read_exact(&mut buf).unwrap();
+        serde_json::from_slice(&buf).unwrap()
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.next += 1;
+        let id = self.next;
+        self.send(json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params}));
+        loop {
+            let msg = self.recv();
+            if msg["id"] == json!(id) {
+                return msg;
+            }
+            self.notes.push(msg);
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({"jsonrpc": "2.0", "method": method, "params": params}));
+    }
+
+    /// Waits for the diagnostics published for a document.
+    fn diagnostics(&mut self, uri: &str) -> Value {
+        loop {
+            let pos = self.notes.iter().position(|msg| {
+                msg["method"] == "textDocument/publishDiagnostics" && msg["params"]["uri"] == uri
+            });
+            if let Some(pos) = pos {
+                return self.notes.remove(pos)["params"]["diagnostics"].clone();
+            }
+            let msg = self.recv();
+            self.notes.push(msg);
+        }
+    }
+
+    fn open(&mut self, uri: &str, text: &str) {
+        let doc = json!({"uri": uri, "languageId": "fluxo", "version": 1, "text": text});
+        self.notify("textDocument/didOpen", json!({ "textDocument": doc }));
+    }
+}
+
+fn dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("fluxo-lsp-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_session() {
+    let dir = dir();
+    std::fs::write(dir.join("id.fx"), "λa : □ . λx : a . x\n").unwrap();
+    let uri = |name: &str| format!("file://{}", dir.join(name).display());
+    let (main, bad) = (uri("main.fx"), uri("bad.fx"));
+
+    let mut client = Client::spawn();
+    let res = client.request("initialize", json!({"capabilities": {}}));
+    assert_eq!(res["result"]["capabilities"]["hoverProvider"], true);
+    client.notify("initialized", json!({}));
+
+    client.open(&main, "λb : □ . id b");
+    assert_eq!(client.diagnostics(&main), json!([]));
+    client.open(&bad, "λb : □ . (λ");
+    let res = client.diagnostics(&bad);
+    assert_eq!(res.as_array().unwrap().len(), 1);
+
+    let pos = |line, character| json!({"line": line, "character": character});
+    let doc = json!({ "uri": main });
+    let res = client.request(
+        "textDocument/hover",
+        json!({"textDocument": doc, "position": pos(0, 9)}),
+    );
+    assert_eq!(
+        res["result"]["contents"]["value"],
+        "```fluxo\nΠa : □ . Πx : a . a\n```"
+    );
+
+    let res = client.request(
+        "textDocument/definition",
+        json!({"textDocument": doc, "position": pos(0, 12)}),
+    );
+    assert_eq!(res["result"]["uri"], main);
+    assert_eq!(res["result"]["range"]["start"], pos(0, 1));
+    let res = client.request(
+        "textDocument/definition",
+        json!({"textDocument": doc, "position": pos(0, 9)}),
+    );
+    assert_eq!(res["result"]["uri"], uri("id.fx"));
+
+    let res = client.request(
+        "textDocument/documentSymbol",
+        json!({ "textDocument": doc }),
+    );
+    assert_eq!(res["result"][0]["name"], "main");
+    assert_eq!(res["result"][0]["children"][0]["name"], "b");
+
+    let opts = json!({"tabSize": 2, "insertSpaces": true});
+    let res = client.request(
+        "textDocument/formatting",
+        json!({"textDocument": doc, "options": opts}),
+    );
+    assert_eq!(res["result"][0]["newText"], "λb : □ . id b\n");
+
+    let res = client.request("textDocument/bogus", json!({}));
+    assert_eq!(res["error"]["code"], -32601);
+
+    client.notify(
+        "textDocument/didClose",
+        json!({ "textDocument": { "uri": bad } }),
+    );
+    assert_eq!(client.diagnostics(&bad), json!([]));
+
+    let (k, used) = (uri("k.fx"), uri("used.fx")); // neither is saved to a file
+    client.open(&k, "\n  λa : □ . a");
+    client.open(&used, "k");
+    let res = client.request(
+        "textDocument/definition",
+        json!({"textDocument": { "uri": used }, "position": pos(0, 0)}),
+    );
+    assert_eq!(res["result"]["uri"], k);
+    assert_eq!(res["result"]["range"]["start"], pos(1, 2));
+
+    let res = client.request("shutdown", Value::Null);
+    assert_eq!(res["result"], Value::Null);
+    client.notify("exit", Value::Null);
+    assert!(client.proc.wait().unwrap().success());
+    std::fs::remove_dir_all(&dir).unwrap();
+}